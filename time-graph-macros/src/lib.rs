@@ -45,7 +45,7 @@ use proc_macro::TokenStream;
 
 use quote::quote;
 use syn::parse::{Parse, ParseStream};
-use syn::{ItemFn, Signature, LitStr, Token};
+use syn::{Ident, ItemFn, LitStr, Path, Token};
 
 
 /// Instruments a function to create and enter a [`time-graph`] [span] every
@@ -69,6 +69,95 @@ use syn::{ItemFn, Signature, LitStr, Token};
 ///     // ...
 /// }
 /// ```
+/// Instrumenting an `async` function. The span is entered every time the
+/// corresponding future is polled, and the recorded time is the total time
+/// spent polling this future:
+/// ```
+/// # use time_graph_macros::instrument;
+/// #[instrument]
+/// pub async fn my_function() {
+///     // ...
+/// }
+/// ```
+/// Only recording the span when a runtime condition holds. The path should
+/// refer to a function with the `fn() -> bool` signature, which is called
+/// every time the instrumented function is called:
+/// ```
+/// # use time_graph_macros::instrument;
+/// fn should_record() -> bool {
+///     // ...
+/// # true
+/// }
+///
+/// #[instrument(enabled_if = should_record)]
+/// pub fn my_function() {
+///     // ...
+/// }
+/// ```
+/// Attaching static `key = "value"` metadata to the generated callsite:
+/// ```
+/// # use time_graph_macros::instrument;
+/// #[instrument(category = "io", subsystem = "network")]
+/// pub fn my_function() {
+///     // ...
+/// }
+/// ```
+/// Setting the verbosity level of the span, which can later be used to only
+/// report the most important spans with `FullCallGraph::filter_by_level`. The
+/// level is one of `"trace"`, `"debug"`, `"info"` (the default), `"warn"` or
+/// `"error"`, and is stored in the `level` metadata:
+/// ```
+/// # use time_graph_macros::instrument;
+/// #[instrument(level = "debug")]
+/// pub fn my_function() {
+///     // ...
+/// }
+/// ```
+/// Only counting the calls to the function, without measuring their duration.
+/// This removes the clock reads from the instrumentation, and is useful for
+/// functions called very often where only the number of calls is interesting.
+/// This adds `count_only = "true"` to the callsite metadata, see
+/// `time_graph::set_count_only` for more information:
+/// ```
+/// # use time_graph_macros::instrument;
+/// #[instrument(count_only)]
+/// pub fn my_function() {
+///     // ...
+/// }
+/// ```
+/// Including the `Debug` representation of some arguments in the span name,
+/// creating one span per distinct value (here `my_function(size=3)`,
+/// `my_function(size=8)`, …). This formats the arguments and locks a global
+/// mutex on every call, and leaks one callsite per distinct name, so it
+/// should only be used with arguments taking a small number of values:
+/// ```
+/// # use time_graph_macros::instrument;
+/// #[instrument(record = "size")]
+/// pub fn my_function(size: usize) {
+///     // ...
+/// }
+/// ```
+/// Prefixing the span name with the `Self` type when instrumenting a method,
+/// creating a span named `MyType::my_method`. The attribute only sees the
+/// function and not the surrounding `impl` block, so the name of the type is
+/// obtained at runtime with [`std::any::type_name`], with the same overhead as
+/// `record` above. This can only be used inside `impl` blocks, and the name of
+/// the implemented trait (if any) is not included. Generic types create one
+/// span per instantiation (`Wrapper<u8>::method`, `Wrapper<u16>::method`, …):
+/// ```
+/// # use time_graph_macros::instrument;
+/// struct MyType;
+///
+/// impl MyType {
+///     #[instrument(qualified)]
+///     pub fn my_method(&self) {
+///         // ...
+///     }
+/// }
+/// ```
+///
+/// When the `disabled` cargo feature of `time-graph` is enabled, this macro
+/// returns the function unchanged, removing all overhead.
 ///
 /// [span]: https://docs.rs/time-graph/latest/time_graph/struct.Span.html
 /// [`time-graph`]: https://github.com/luthaf/time-graph
@@ -77,7 +166,49 @@ pub fn instrument(args: TokenStream, tokens: TokenStream) -> TokenStream {
     let input: ItemFn = syn::parse_macro_input!(tokens as ItemFn);
     let args: TimedArgs = syn::parse_macro_input!(args as TimedArgs);
 
+    if cfg!(feature = "disabled") {
+        // instrumentation is disabled at compile time
+        let mut input = input;
+        if let Some(enabled_if) = &args.enabled_if {
+            // keep using the function to prevent dead code warnings
+            let statement = syn::parse_quote!(let _ = #enabled_if as fn() -> bool;);
+            input.block.stmts.insert(0, statement);
+        }
+        return quote!(#input).into();
+    }
+
     let name = args.name.unwrap_or_else(|| input.sig.ident.to_string());
+    let keys = args.metadata.iter().map(|(key, _)| key);
+    let values = args.metadata.iter().map(|(_, value)| value);
+    let count_only = if args.count_only {
+        quote!(, count_only = "true")
+    } else {
+        quote!()
+    };
+    let callsite = quote!(time_graph::callsite!(#name #(, #keys = #values)* #count_only));
+    let callsite = if args.record.is_empty() && !args.qualified {
+        callsite
+    } else {
+        let name = if args.qualified {
+            // the `impl` block is not visible from here, so we use the type
+            // of `Self` at runtime to qualify the name
+            quote!(format!("{}::{}", time_graph::short_type_name(std::any::type_name::<Self>()), #name))
+        } else {
+            quote!(#name)
+        };
+
+        if args.record.is_empty() {
+            quote!(time_graph::dynamic_callsite(#callsite, &#name))
+        } else {
+            let format = format!("{{}}({})", args.record.iter()
+                .map(|arg| format!("{}={{:?}}", arg))
+                .collect::<Vec<_>>()
+                .join(", ")
+            );
+            let record = &args.record;
+            quote!(time_graph::dynamic_callsite(#callsite, &format!(#format, #name #(, #record)*)))
+        }
+    };
 
     let ItemFn {
         attrs,
@@ -87,31 +218,72 @@ pub fn instrument(args: TokenStream, tokens: TokenStream) -> TokenStream {
         ..
     } = input;
 
-    let Signature {
-        output: return_type,
-        inputs: params,
-        unsafety,
-        asyncness,
-        constness,
-        abi,
-        ident,
-        generics:
-            syn::Generics {
-                params: gen_params,
-                where_clause,
-                ..
-            },
-        ..
-    } = sig;
+    let body = if sig.asyncness.is_some() {
+        // the span is entered every time the future returned by the function
+        // is polled, instead of around the creation of the future
+        let result_type = match &sig.output {
+            syn::ReturnType::Default => Some(quote!(())),
+            // `impl Trait` can not be used in variable declarations
+            syn::ReturnType::Type(_, ty) if matches!(**ty, syn::Type::ImplTrait(_)) => None,
+            syn::ReturnType::Type(_, ty) => Some(quote!(#ty)),
+        };
+
+        let future = if let Some(result_type) = result_type {
+            quote!(async move {
+                let __tfg_result: #result_type = #block;
+                __tfg_result
+            })
+        } else {
+            quote!(async move #block)
+        };
+
+        if let Some(enabled_if) = args.enabled_if {
+            // the callsite is created before the future, since the future
+            // takes ownership of the function arguments
+            quote!(
+                let __tfg_callsite = if #enabled_if() {
+                    Some(#callsite)
+                } else {
+                    None
+                };
+                let __tfg_future = #future;
+                match __tfg_callsite {
+                    Some(__tfg_callsite) => time_graph::Instrumented::new(__tfg_callsite, __tfg_future).await,
+                    None => __tfg_future.await,
+                }
+            )
+        } else {
+            quote!(
+                time_graph::Instrumented::new(#callsite, #future).await
+            )
+        }
+    } else if let Some(enabled_if) = args.enabled_if {
+        quote!(
+            let __tfg_callsite = #callsite;
+            let __tfg_span = time_graph::Span::new(__tfg_callsite);
+            let __tfg_guard = if #enabled_if() {
+                Some(__tfg_span.enter())
+            } else {
+                None
+            };
+
+            #block
+        )
+    } else {
+        quote!(
+            let __tfg_callsite = #callsite;
+            let __tfg_span = time_graph::Span::new(__tfg_callsite);
+            let __tfg_guard = __tfg_span.enter();
+
+            #block
+        )
+    };
 
     let stream = quote!(
         #(#attrs) *
-        #vis #constness #unsafety #asyncness #abi fn #ident<#gen_params>(#params) #return_type
-        #where_clause
+        #vis #sig
         {
-            time_graph::spanned!(#name, {
-                #block
-            })
+            #body
         }
     );
 
@@ -120,16 +292,33 @@ pub fn instrument(args: TokenStream, tokens: TokenStream) -> TokenStream {
 
 struct TimedArgs {
     name: Option<String>,
+    enabled_if: Option<Path>,
+    metadata: Vec<(Ident, LitStr)>,
+    record: Vec<Ident>,
+    qualified: bool,
+    count_only: bool,
 }
 
+/// Valid values for the `level` metadata
+const LEVELS: &[&str] = &["trace", "debug", "info", "warn", "error"];
+
 mod kw {
     syn::custom_keyword!(name);
+    syn::custom_keyword!(enabled_if);
+    syn::custom_keyword!(record);
+    syn::custom_keyword!(qualified);
+    syn::custom_keyword!(count_only);
 }
 
 impl Parse for TimedArgs {
     fn parse(input: ParseStream<'_>) -> syn::Result<Self> {
         let mut args = TimedArgs {
             name: None,
+            enabled_if: None,
+            metadata: Vec::new(),
+            record: Vec::new(),
+            qualified: false,
+            count_only: false,
         };
         while !input.is_empty() {
             let lookahead = input.lookahead1();
@@ -145,9 +334,54 @@ impl Parse for TimedArgs {
                     return Err(input.error("expected only a single `name` argument"));
                 }
                 args.name = Some(input.parse::<LitStr>()?.value());
+            } else if lookahead.peek(kw::enabled_if) {
+                if args.enabled_if.is_some() {
+                    return Err(input.error("expected only a single `enabled_if` argument"));
+                }
+                let _ = input.parse::<kw::enabled_if>()?;
+                let _ = input.parse::<Token![=]>()?;
+                args.enabled_if = Some(input.parse::<Path>()?);
+            } else if lookahead.peek(kw::record) {
+                let _ = input.parse::<kw::record>()?;
+                let _ = input.parse::<Token![=]>()?;
+                let value = input.parse::<LitStr>()?;
+                let arg = value.parse::<Ident>()?;
+                if args.record.contains(&arg) {
+                    return Err(syn::Error::new(value.span(), format!("`{}` is already recorded", arg)));
+                }
+                args.record.push(arg);
+            } else if lookahead.peek(kw::qualified) {
+                if args.qualified {
+                    return Err(input.error("expected only a single `qualified` argument"));
+                }
+                let _ = input.parse::<kw::qualified>()?;
+                args.qualified = true;
+            } else if lookahead.peek(kw::count_only) {
+                if args.count_only {
+                    return Err(input.error("expected only a single `count_only` argument"));
+                }
+                let _ = input.parse::<kw::count_only>()?;
+                args.count_only = true;
+            } else if lookahead.peek(Ident) {
+                let key = input.parse::<Ident>()?;
+                if args.metadata.iter().any(|(existing, _)| *existing == key) {
+                    return Err(syn::Error::new(key.span(), format!("duplicated `{}` metadata", key)));
+                }
+                let _ = input.parse::<Token![=]>()?;
+                let value = input.parse::<LitStr>()?;
+                if key == "level" && !LEVELS.contains(&value.value().to_ascii_lowercase().as_str()) {
+                    return Err(syn::Error::new(value.span(), format!(
+                        "unknown level `{}`, expected one of {}", value.value(), LEVELS.join(", ")
+                    )));
+                }
+                args.metadata.push((key, value));
             } else {
                 return Err(lookahead.error());
             }
+
+            if !input.is_empty() {
+                let _ = input.parse::<Token![,]>()?;
+            }
         }
         Ok(args)
     }
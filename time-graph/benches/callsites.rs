@@ -0,0 +1,28 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+
+/// Repeat the given tokens twice
+macro_rules! twice {
+    ($($tokens: tt)*) => {
+        $($tokens)*
+        $($tokens)*
+    };
+}
+
+/// Enter 256 distinct callsites, one after the other
+#[time_graph::instrument]
+fn many_callsites() {
+    twice! { twice! { twice! { twice! { twice! { twice! { twice! { twice! {
+        time_graph::spanned!("span", {});
+    } } } } } } } }
+}
+
+fn callsites(c: &mut Criterion) {
+    time_graph::enable_data_collection(false);
+    c.bench_function("256 callsites, no collection", |b| b.iter(many_callsites));
+
+    time_graph::enable_data_collection(true);
+    c.bench_function("256 callsites, collection", |b| b.iter(many_callsites));
+}
+
+criterion_group!(benches, callsites);
+criterion_main!(benches);
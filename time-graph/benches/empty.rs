@@ -5,6 +5,11 @@ fn do_nothing(value: usize) -> usize {
     value
 }
 
+#[time_graph::instrument(count_only)]
+fn do_nothing_count_only(value: usize) -> usize {
+    value
+}
+
 fn do_nothing_no_instrument(value: usize) -> usize {
     value
 }
@@ -43,6 +48,7 @@ fn empty_function(c: &mut Criterion) {
 
     time_graph::enable_data_collection(true);
     c.bench_function("empty functions, collection", |b| b.iter(|| do_nothing(black_box(44))));
+    c.bench_function("empty functions, count only collection", |b| b.iter(|| do_nothing_count_only(black_box(44))));
 }
 
 fn basic_calculation(c: &mut Criterion) {
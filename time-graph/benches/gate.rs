@@ -0,0 +1,20 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use criterion::{Criterion, criterion_group, criterion_main, black_box};
+
+static FLAG: AtomicUsize = AtomicUsize::new(0);
+
+fn gate(c: &mut Criterion) {
+    // cost of the check performed when entering a span, with the ordering
+    // used before and after switching to `Relaxed`
+    c.bench_function("gate load, acquire", |b| b.iter(|| black_box(&FLAG).load(Ordering::Acquire) != 0));
+    c.bench_function("gate load, relaxed", |b| b.iter(|| black_box(&FLAG).load(Ordering::Relaxed) != 0));
+
+    time_graph::enable_data_collection(false);
+    c.bench_function("span, no collection", |b| b.iter(|| {
+        time_graph::spanned!("span", black_box(44))
+    }));
+}
+
+criterion_group!(benches, gate);
+criterion_main!(benches);
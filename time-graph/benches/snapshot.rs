@@ -0,0 +1,29 @@
+use criterion::{Criterion, criterion_group, criterion_main};
+
+/// Repeat the given tokens twice
+macro_rules! twice {
+    ($($tokens: tt)*) => {
+        $($tokens)*
+        $($tokens)*
+    };
+}
+
+/// Enter 64 distinct callsites, one after the other
+#[time_graph::instrument]
+fn many_callsites() {
+    twice! { twice! { twice! { twice! { twice! { twice! {
+        time_graph::spanned!("span", {});
+    } } } } } }
+}
+
+fn snapshot(c: &mut Criterion) {
+    time_graph::enable_data_collection(true);
+    many_callsites();
+    time_graph::enable_data_collection(false);
+
+    c.bench_function("get_full_graph, 65 spans", |b| b.iter(time_graph::get_full_graph));
+    c.bench_function("snapshot_totals, 65 spans", |b| b.iter(time_graph::snapshot_totals));
+}
+
+criterion_group!(benches, snapshot);
+criterion_main!(benches);
@@ -0,0 +1,37 @@
+use std::time::{Duration, Instant};
+
+use criterion::{Criterion, criterion_group, criterion_main, black_box};
+
+const N_THREADS: usize = 8;
+
+#[time_graph::instrument]
+fn do_nothing(value: usize) -> usize {
+    value
+}
+
+/// Run `iterations` calls to `do_nothing` in each of `N_THREADS` threads,
+/// and return the total time spent
+fn run_threads(iterations: u64) -> Duration {
+    let start = Instant::now();
+    std::thread::scope(|scope| {
+        for _ in 0..N_THREADS {
+            scope.spawn(|| {
+                for _ in 0..iterations {
+                    do_nothing(black_box(44));
+                }
+            });
+        }
+    });
+    start.elapsed()
+}
+
+fn threads(c: &mut Criterion) {
+    time_graph::enable_data_collection(false);
+    c.bench_function("empty functions, 8 threads, no collection", |b| b.iter_custom(run_threads));
+
+    time_graph::enable_data_collection(true);
+    c.bench_function("empty functions, 8 threads, collection", |b| b.iter_custom(run_threads));
+}
+
+criterion_group!(benches, threads);
+criterion_main!(benches);
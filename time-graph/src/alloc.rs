@@ -0,0 +1,74 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::cell::Cell;
+
+thread_local! {
+    /// Number of allocations and total number of bytes allocated by the
+    /// current thread since it started
+    static LOCAL_ALLOCATIONS: Cell<(u64, u64)> = const { Cell::new((0, 0)) };
+}
+
+/// A [`GlobalAlloc`] wrapper counting the number of allocations and allocated
+/// bytes, to record them in the call graph for each function/span.
+///
+/// This allocator must be installed with `#[global_allocator]` in the final
+/// executable for allocations to be recorded.
+///
+/// This type is only available if the `"alloc-tracking"` cargo feature is
+/// enabled.
+///
+/// # Examples
+/// ```
+/// use time_graph::CountingAllocator;
+///
+/// #[global_allocator]
+/// static ALLOCATOR: CountingAllocator = CountingAllocator::new(std::alloc::System);
+/// ```
+pub struct CountingAllocator<A = System> {
+    inner: A,
+}
+
+impl<A> CountingAllocator<A> {
+    /// Create a new `CountingAllocator` wrapping the `inner` allocator
+    pub const fn new(inner: A) -> CountingAllocator<A> {
+        CountingAllocator {
+            inner: inner,
+        }
+    }
+}
+
+/// Record a single allocation of `size` bytes on the current thread
+fn count_allocation(size: usize) {
+    // this can fail if the thread local was already destroyed, in which case
+    // there is no span to record the allocation anyway
+    let _ = LOCAL_ALLOCATIONS.try_with(|allocations| {
+        let (count, bytes) = allocations.get();
+        allocations.set((count + 1, bytes + size as u64));
+    });
+}
+
+unsafe impl<A: GlobalAlloc> GlobalAlloc for CountingAllocator<A> {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        count_allocation(layout.size());
+        return self.inner.alloc(layout);
+    }
+
+    unsafe fn alloc_zeroed(&self, layout: Layout) -> *mut u8 {
+        count_allocation(layout.size());
+        return self.inner.alloc_zeroed(layout);
+    }
+
+    unsafe fn realloc(&self, ptr: *mut u8, layout: Layout, new_size: usize) -> *mut u8 {
+        count_allocation(new_size);
+        return self.inner.realloc(ptr, layout, new_size);
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        self.inner.dealloc(ptr, layout);
+    }
+}
+
+/// Get the number of allocations and total number of bytes allocated by the
+/// current thread so far
+pub(crate) fn allocations() -> (u64, u64) {
+    LOCAL_ALLOCATIONS.try_with(Cell::get).unwrap_or((0, 0))
+}
@@ -0,0 +1,234 @@
+//! Compact binary encoding of call graphs.
+//!
+//! All integers are stored in little-endian order, and strings are stored as
+//! their length (as `u32`) followed by their UTF-8 bytes. The data starts with
+//! `MAGIC` and a version byte, followed by the number of spans (as `u64`) and
+//! the spans themselves, and then the number of calls (as `u64`) and the
+//! calls themselves.
+
+use std::convert::TryInto;
+use std::time::Duration;
+
+use crate::{FullCallGraph, OwnedCallGraph, OwnedSpan, Calls};
+
+/// Bytes at the start of every encoded graph
+const MAGIC: &[u8; 4] = b"TGRF";
+/// Version of the encoding
+const VERSION: u8 = 1;
+
+/// Borrowed data for a single span, shared by `TimedSpan` and `OwnedSpan`
+struct SpanData<'a> {
+    id: usize,
+    name: &'a str,
+    module_path: &'a str,
+    file: &'a str,
+    line: u32,
+    elapsed: Duration,
+    called: u32,
+    min: Duration,
+    max: Duration,
+    self_time: Duration,
+}
+
+fn write_u32(output: &mut Vec<u8>, value: u32) {
+    output.extend_from_slice(&value.to_le_bytes());
+}
+
+fn write_u64(output: &mut Vec<u8>, value: u64) {
+    output.extend_from_slice(&value.to_le_bytes());
+}
+
+fn write_duration(output: &mut Vec<u8>, value: Duration) {
+    write_u64(output, value.as_nanos().try_into().unwrap_or(u64::MAX));
+}
+
+fn write_str(output: &mut Vec<u8>, value: &str) {
+    write_u32(output, value.len().try_into().expect("string is too long"));
+    output.extend_from_slice(value.as_bytes());
+}
+
+/// Encode the given spans and calls. `span_count` and `call_count` must be the
+/// number of items in the corresponding iterators.
+fn encode<'a>(
+    span_count: usize,
+    spans: impl Iterator<Item = SpanData<'a>>,
+    call_count: usize,
+    calls: impl Iterator<Item = Calls>,
+) -> Vec<u8> {
+    let mut output = Vec::new();
+    output.extend_from_slice(MAGIC);
+    output.push(VERSION);
+
+    write_u64(&mut output, span_count as u64);
+    for span in spans {
+        write_u64(&mut output, span.id as u64);
+        write_str(&mut output, span.name);
+        write_str(&mut output, span.module_path);
+        write_str(&mut output, span.file);
+        write_u32(&mut output, span.line);
+        write_duration(&mut output, span.elapsed);
+        write_u32(&mut output, span.called);
+        write_duration(&mut output, span.min);
+        write_duration(&mut output, span.max);
+        write_duration(&mut output, span.self_time);
+    }
+
+    write_u64(&mut output, call_count as u64);
+    for call in calls {
+        write_u64(&mut output, call.caller as u64);
+        write_u64(&mut output, call.callee as u64);
+        write_u64(&mut output, call.count as u64);
+        write_duration(&mut output, call.elapsed);
+    }
+
+    return output;
+}
+
+/// Reader for data encoded by `encode`, returning `None` on truncated or
+/// invalid data
+struct Reader<'a> {
+    data: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn bytes(&mut self, count: usize) -> Option<&'a [u8]> {
+        if self.data.len() < count {
+            return None;
+        }
+        let (bytes, rest) = self.data.split_at(count);
+        self.data = rest;
+        return Some(bytes);
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        let bytes = self.bytes(4)?;
+        return Some(u32::from_le_bytes(bytes.try_into().ok()?));
+    }
+
+    fn u64(&mut self) -> Option<u64> {
+        let bytes = self.bytes(8)?;
+        return Some(u64::from_le_bytes(bytes.try_into().ok()?));
+    }
+
+    fn usize(&mut self) -> Option<usize> {
+        return self.u64()?.try_into().ok();
+    }
+
+    fn duration(&mut self) -> Option<Duration> {
+        return Some(Duration::from_nanos(self.u64()?));
+    }
+
+    fn string(&mut self) -> Option<String> {
+        let length = self.u32()? as usize;
+        let bytes = self.bytes(length)?;
+        return String::from_utf8(bytes.to_vec()).ok();
+    }
+}
+
+fn decode(data: &[u8]) -> Option<OwnedCallGraph> {
+    let mut reader = Reader { data: data };
+    if reader.bytes(MAGIC.len())? != MAGIC || reader.bytes(1)? != [VERSION] {
+        return None;
+    }
+
+    // do not trust the counts for pre-allocation, since they could be invalid
+    let span_count = reader.u64()?;
+    let mut spans = Vec::new();
+    for _ in 0..span_count {
+        spans.push(OwnedSpan {
+            id: reader.usize()?,
+            name: reader.string()?,
+            module_path: reader.string()?,
+            file: reader.string()?,
+            line: reader.u32()?,
+            elapsed: reader.duration()?,
+            called: reader.u32()?,
+            min: reader.duration()?,
+            max: reader.duration()?,
+            self_time: reader.duration()?,
+        });
+    }
+
+    let call_count = reader.u64()?;
+    let mut calls = Vec::new();
+    for _ in 0..call_count {
+        calls.push(Calls {
+            caller: reader.usize()?,
+            callee: reader.usize()?,
+            count: reader.usize()?,
+            elapsed: reader.duration()?,
+        });
+    }
+
+    if !reader.data.is_empty() {
+        return None;
+    }
+
+    return OwnedCallGraph::new(spans, calls);
+}
+
+impl FullCallGraph {
+    /// Encode all the data in this graph in a compact binary format, which
+    /// can be decoded with [`OwnedCallGraph::from_bytes`]. This is much faster
+    /// and smaller than JSON, and intended to send graphs between processes.
+    ///
+    /// Durations are stored with nanosecond precision, and saturate after
+    /// 584 years.
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let spans = self.spans().map(|span| SpanData {
+            id: span.id,
+            name: self.span_name(span),
+            module_path: span.callsite.module_path(),
+            file: span.callsite.file(),
+            line: span.callsite.line(),
+            elapsed: span.elapsed,
+            called: span.called,
+            min: span.min,
+            max: span.max,
+            self_time: span.self_time,
+        });
+
+        return encode(self.spans().count(), spans, self.calls().count(), self.calls());
+    }
+}
+
+impl OwnedCallGraph {
+    /// Encode all the data in this graph in the same binary format as
+    /// [`FullCallGraph::to_bytes`].
+    pub fn to_bytes(&self) -> Vec<u8> {
+        let spans = self.spans().map(|span| SpanData {
+            id: span.id,
+            name: &span.name,
+            module_path: &span.module_path,
+            file: &span.file,
+            line: span.line,
+            elapsed: span.elapsed,
+            called: span.called,
+            min: span.min,
+            max: span.max,
+            self_time: span.self_time,
+        });
+
+        return encode(self.spans().count(), spans, self.calls().count(), self.calls());
+    }
+
+    /// Decode a graph encoded with [`FullCallGraph::to_bytes`] or
+    /// [`OwnedCallGraph::to_bytes`], returning `None` if the data is not a
+    /// valid encoded graph.
+    ///
+    /// # Examples
+    /// ```
+    /// # use time_graph::OwnedCallGraph;
+    /// time_graph::enable_data_collection(true);
+    /// time_graph::spanned!("work", {
+    ///     // ...
+    /// });
+    ///
+    /// let bytes = time_graph::get_full_graph().to_bytes();
+    /// let graph = OwnedCallGraph::from_bytes(&bytes).unwrap();
+    /// assert!(graph.spans().any(|span| span.name == "work"));
+    /// ```
+    pub fn from_bytes(data: &[u8]) -> Option<OwnedCallGraph> {
+        return decode(data);
+    }
+}
@@ -0,0 +1,52 @@
+use std::sync::{Arc, RwLock, PoisonError};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use crate::{CallSite, CallSiteId};
+
+type SpanCallback = dyn Fn(&'static CallSite, Duration, Option<CallSiteId>) + Send + Sync;
+
+/// User-provided callback, set with [`on_span_complete`]
+static SPAN_CALLBACK: RwLock<Option<Arc<SpanCallback>>> = RwLock::new(None);
+
+/// Is there a user-provided callback in `SPAN_CALLBACK`? This is used to skip
+/// locking `SPAN_CALLBACK` when no callback is set.
+static HAS_SPAN_CALLBACK: AtomicBool = AtomicBool::new(false);
+
+/// Register a `callback` to be called every time a span completes while data
+/// collection is enabled, replacing any previously registered callback.
+///
+/// The callback receives the [`CallSite`] of the span, the elapsed time for
+/// this single execution of the span, and the id of the parent span if any.
+///
+/// The callback runs on the hot path, on the thread where the span executed,
+/// and should be as fast as possible. It must not enter spans itself.
+///
+/// # Examples
+/// ```
+/// time_graph::on_span_complete(|callsite, elapsed, _| {
+///     println!("{} took {:?}", callsite.name(), elapsed);
+/// });
+/// ```
+pub fn on_span_complete(callback: impl Fn(&'static CallSite, Duration, Option<CallSiteId>) + Send + Sync + 'static) {
+    let mut current = SPAN_CALLBACK.write().unwrap_or_else(PoisonError::into_inner);
+    *current = Some(Arc::new(callback));
+    HAS_SPAN_CALLBACK.store(true, Ordering::Release);
+}
+
+/// Remove the callback registered with [`on_span_complete`], if any.
+pub fn remove_span_complete_callback() {
+    let mut current = SPAN_CALLBACK.write().unwrap_or_else(PoisonError::into_inner);
+    *current = None;
+    HAS_SPAN_CALLBACK.store(false, Ordering::Release);
+}
+
+/// Notify the user-provided callback that a span completed
+pub(crate) fn span_completed(callsite: &'static CallSite, elapsed: Duration, parent: Option<CallSiteId>) {
+    if HAS_SPAN_CALLBACK.load(Ordering::Acquire) {
+        let callback = SPAN_CALLBACK.read().unwrap_or_else(PoisonError::into_inner).clone();
+        if let Some(callback) = callback {
+            callback(callsite, elapsed, parent);
+        }
+    }
+}
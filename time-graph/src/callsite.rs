@@ -1,5 +1,7 @@
+use std::collections::HashMap;
 use std::num::NonZeroU64;
-use std::sync::atomic::{Ordering, AtomicU64, AtomicPtr};
+use std::sync::{Mutex, PoisonError};
+use std::sync::atomic::{Ordering, AtomicBool, AtomicU32, AtomicU64, AtomicPtr};
 
 use once_cell::sync::Lazy;
 
@@ -12,14 +14,31 @@ static REGISTRY: Lazy<Registry> = Lazy::new(|| {
     }
 });
 
+/// Call sites created at runtime by [`dynamic_callsite`], indexed by the id of
+/// the corresponding static call site and their name
+static DYNAMIC_CALLSITES: Lazy<Mutex<HashMap<CallSiteId, HashMap<String, &'static CallSite>>>> = Lazy::new(|| {
+    Mutex::new(HashMap::new())
+});
+
 /// Unique identifier of a [`CallSite`], attributed the first time the call site
 /// is entered.
-#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct CallSiteId(NonZeroU64);
 
 impl CallSiteId {
-    pub(crate) fn new(value: u64) -> CallSiteId {
-        CallSiteId(std::num::NonZeroU64::new(value).expect("got a zero value for span id"))
+    /// Create a new [`CallSiteId`], returning `None` if `value` is zero
+    pub(crate) fn new(value: u64) -> Option<CallSiteId> {
+        NonZeroU64::new(value).map(CallSiteId)
+    }
+
+    /// Get the next id from the `counter`, skipping zero if the counter wraps
+    /// around
+    fn next(counter: &AtomicU64) -> CallSiteId {
+        loop {
+            if let Some(id) = CallSiteId::new(counter.fetch_add(1, Ordering::SeqCst)) {
+                return id;
+            }
+        }
     }
 }
 
@@ -28,6 +47,12 @@ impl CallSiteId {
 ///
 /// The only way to create a [`CallSite`] is with the [`macro@callsite`] macro,
 /// which also takes care of registering the call site globally.
+///
+/// Call sites are compared, ordered and hashed using their [`CallSiteId`], so
+/// `&'static CallSite` can be used as keys in `HashMap` or `BTreeMap`. Two
+/// call sites with the same name at different locations are different. The
+/// `clippy::mutable_key_type` lint can be safely ignored for these keys, since
+/// the id of a call site never changes.
 pub struct CallSite {
     /// Unique identifier of this call site
     id: CallSiteId,
@@ -39,10 +64,23 @@ pub struct CallSite {
     file: &'static str,
     /// The line number in the source code file where the call site occurred
     line: u32,
+    /// User-provided key/value metadata associated with the call site
+    metadata: &'static [(&'static str, &'static str)],
     /// Call sites are registered using an atomic, append only intrusive linked
     /// list. If more than one call site are registered, this will be set to the
     /// last registered call site.
     next: AtomicPtr<CallSite>,
+    /// Is this call site currently part of the registry? This is reset by
+    /// [`reset_registry`], allowing call sites to be registered again.
+    registered: AtomicBool,
+    /// Only record one in `sampling_rate` executions of this call site, or use
+    /// the global sampling rate if this is 0
+    pub(crate) sampling_rate: AtomicU32,
+    /// Number of executions of this call site, used for sampling
+    pub(crate) sample_counter: AtomicU32,
+    /// Should executions of this call site only be counted, without measuring
+    /// their duration?
+    pub(crate) count_only: AtomicBool,
 }
 
 impl CallSite {
@@ -51,12 +89,31 @@ impl CallSite {
     /// from inside macros.
     #[doc(hidden)]
     pub fn new(name: &'static str, module_path: &'static str, file: &'static str, line: u32) -> CallSite {
-        let id = CallSiteId::new(NEXT_CALL_SITE_ID.fetch_add(1, Ordering::SeqCst));
+        CallSite::with_metadata(name, module_path, file, line, &[])
+    }
+
+    /// Create a new `CallSite` with the given source location and additional
+    /// key/value metadata. This function is private to this crate, and is only
+    /// marked `pub` to be able to call it from inside macros.
+    #[doc(hidden)]
+    pub fn with_metadata(
+        name: &'static str,
+        module_path: &'static str,
+        file: &'static str,
+        line: u32,
+        metadata: &'static [(&'static str, &'static str)],
+    ) -> CallSite {
+        let id = CallSiteId::next(&NEXT_CALL_SITE_ID);
         let next = AtomicPtr::new(std::ptr::null_mut());
-        CallSite { id, name, module_path, file, line, next }
+        let registered = AtomicBool::new(false);
+        let sampling_rate = AtomicU32::new(0);
+        let sample_counter = AtomicU32::new(0);
+        let count_only = AtomicBool::new(metadata.contains(&("count_only", "true")));
+        CallSite { id, name, module_path, file, line, metadata, next, registered, sampling_rate, sample_counter, count_only }
     }
 
-    pub(crate) fn id(&self) -> CallSiteId {
+    /// Get the unique identifier of this call site
+    pub fn id(&self) -> CallSiteId {
         self.id
     }
 
@@ -80,24 +137,178 @@ impl CallSite {
         self.line
     }
 
+    /// Get the user-provided key/value metadata associated with this call
+    /// site, in the order they were given to the [`macro@callsite`] macro or
+    /// the [`instrument`](attr.instrument.html) attribute.
+    pub fn metadata(&self) -> &'static [(&'static str, &'static str)] {
+        self.metadata
+    }
+
+    /// Get the verbosity level of this call site, given by the `level`
+    /// metadata (e.g. `#[instrument(level = "debug")]`). Call sites without
+    /// this metadata, or with an unknown level, are at [`Level::Info`].
+    ///
+    /// # Examples
+    /// ```
+    /// # use time_graph::{callsite, Level};
+    /// assert_eq!(callsite!("here", level = "debug").level(), Level::Debug);
+    /// assert_eq!(callsite!("there").level(), Level::Info);
+    /// ```
+    pub fn level(&self) -> Level {
+        self.metadata.iter()
+            .find(|(key, _)| *key == "level")
+            .and_then(|(_, value)| Level::from_name(value))
+            .unwrap_or(Level::Info)
+    }
+
     /// Get the full name of this call site, containing both the name and the
     /// module path.
     pub fn full_name(&self) -> String {
-        let mut name = self.module_path.to_owned();
-        name += "::";
+        full_name(self.module_path, self.name)
+    }
 
-        if self.name.contains(' ') {
-            name += "{";
-            name += self.name;
-            name += "}";
-        } else {
-            name += self.name;
+    /// Get the name of this call site, displayed with the given `style`.
+    ///
+    /// # Examples
+    /// ```
+    /// # use time_graph::{callsite, NameStyle};
+    /// let callsite = callsite!("here");
+    /// assert_eq!(callsite.display_name(NameStyle::NameOnly), "here");
+    /// ```
+    pub fn display_name(&self, style: NameStyle) -> String {
+        display_name(self.module_path, self.name, style)
+    }
+
+    /// Get the name of this call site, displayed with the given `style`, with
+    /// all control characters (new lines, tabs, …) replaced by spaces. This
+    /// is the name used in text reports such as tables, trees and CSV.
+    ///
+    /// # Examples
+    /// ```
+    /// # use time_graph::{callsite, NameStyle};
+    /// let callsite = callsite!("two\nlines");
+    /// assert_eq!(callsite.sanitized_name(NameStyle::NameOnly), "two lines");
+    /// ```
+    pub fn sanitized_name(&self, style: NameStyle) -> String {
+        sanitize(&self.display_name(style)).into_owned()
+    }
+}
+
+// Call sites are compared and hashed by identity, using their unique id
+impl PartialEq for CallSite {
+    fn eq(&self, other: &CallSite) -> bool {
+        self.id == other.id
+    }
+}
+
+impl Eq for CallSite {}
+
+impl PartialOrd for CallSite {
+    fn partial_cmp(&self, other: &CallSite) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for CallSite {
+    fn cmp(&self, other: &CallSite) -> std::cmp::Ordering {
+        self.id.cmp(&other.id)
+    }
+}
+
+impl std::hash::Hash for CallSite {
+    fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+        self.id.hash(state);
+    }
+}
+
+/// Verbosity level of a [`CallSite`], used to only report the most important
+/// spans with [`crate::FullCallGraph::filter_by_level`]. Levels are ordered
+/// from the most verbose (`Trace`) to the least verbose (`Error`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Level {
+    /// Very fine-grained spans
+    Trace,
+    /// Spans useful when debugging performance issues
+    Debug,
+    /// Default level for spans
+    Info,
+    /// Important spans
+    Warn,
+    /// Most important spans
+    Error,
+}
+
+impl Level {
+    /// Get the level corresponding to the given `name` (`"trace"`,
+    /// `"debug"`, `"info"`, `"warn"` or `"error"`, ignoring case), or `None`
+    /// if the name does not correspond to any level.
+    pub fn from_name(name: &str) -> Option<Level> {
+        match name.to_ascii_lowercase().as_str() {
+            "trace" => Some(Level::Trace),
+            "debug" => Some(Level::Debug),
+            "info" => Some(Level::Info),
+            "warn" => Some(Level::Warn),
+            "error" => Some(Level::Error),
+            _ => None,
         }
+    }
+}
+
+/// Different ways to display the name of a [`CallSite`], used by
+/// [`CallSite::display_name`].
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NameStyle {
+    /// Full module path and name, as returned by [`CallSite::full_name`], e.g.
+    /// `my_crate::module::submodule::function`
+    Full,
+    /// Last segment of the module path and name, e.g. `submodule::function`
+    ModuleLast,
+    /// Only the name of the call site, e.g. `function`
+    NameOnly,
+}
 
-        return name;
+/// Get the name of a call site with the given `module_path` and `name`,
+/// displayed with the given `style`
+pub(crate) fn display_name(module_path: &str, name: &str, style: NameStyle) -> String {
+    match style {
+        NameStyle::Full => full_name(module_path, name),
+        NameStyle::ModuleLast => {
+            let module = module_path.rsplit("::").next().unwrap_or(module_path);
+            full_name(module, name)
+        }
+        NameStyle::NameOnly => name.to_owned(),
+    }
+}
+
+/// Replace all control characters in `name` by spaces
+pub(crate) fn sanitize(name: &str) -> std::borrow::Cow<'_, str> {
+    if name.contains(char::is_control) {
+        name.replace(char::is_control, " ").into()
+    } else {
+        name.into()
     }
 }
 
+/// Get the full name of a call site with the given `module_path` and `name`
+pub(crate) fn full_name(module_path: &str, name: &str) -> String {
+    if module_path.is_empty() {
+        return name.to_owned();
+    }
+
+    let mut full_name = module_path.to_owned();
+    full_name += "::";
+
+    if name.contains(' ') {
+        full_name += "{";
+        full_name += name;
+        full_name += "}";
+    } else {
+        full_name += name;
+    }
+
+    return full_name;
+}
+
 /// Registry of CallSite, as the head pointer of an atomic, append-only linked
 /// list.
 struct Registry {
@@ -105,19 +316,22 @@ struct Registry {
 }
 
 impl Registry {
-    /// Register a new callsite within the list
+    /// Register a new callsite within the list. Registering a callsite which
+    /// is already part of the list does nothing, since it would create a cycle
+    /// in the list.
     fn register(&self, callsite: &'static CallSite) {
+        if callsite.registered.load(Ordering::Acquire) {
+            return;
+        }
+
         let mut head = self.head.load(Ordering::Acquire);
 
         loop {
-            callsite.next.store(head, Ordering::Release);
+            if Registry::contains(head, callsite) {
+                return;
+            }
 
-            assert_ne!(
-                callsite as *const _, head,
-                "Attempted to register a `Callsite` that already exists! \
-                This will cause an infinite loop when attempting to read from the \
-                callsite registry."
-            );
+            callsite.next.store(head, Ordering::Release);
 
             match self.head.compare_exchange(
                 head,
@@ -126,6 +340,7 @@ impl Registry {
                 Ordering::Acquire,
             ) {
                 Ok(_) => {
+                    callsite.registered.store(true, Ordering::Release);
                     break;
                 }
                 Err(current) => head = current,
@@ -133,6 +348,26 @@ impl Registry {
         }
     }
 
+    /// Check if `callsite` is part of the list starting at `head`
+    fn contains(mut head: *mut CallSite, callsite: &'static CallSite) -> bool {
+        while let Some(registered) = unsafe { head.as_ref() } {
+            if std::ptr::eq(registered, callsite) {
+                return true;
+            }
+            head = registered.next.load(Ordering::Acquire);
+        }
+        return false;
+    }
+
+    /// Remove all elements from the list, marking them as not registered
+    fn clear(&self) {
+        let mut head = self.head.swap(std::ptr::null_mut(), Ordering::AcqRel);
+        while let Some(registered) = unsafe { head.as_ref() } {
+            registered.registered.store(false, Ordering::Release);
+            head = registered.next.load(Ordering::Acquire);
+        }
+    }
+
     /// Execute the provided function on all elements of the list
     fn for_each(&self, mut f: impl FnMut(&'static CallSite)) {
         let mut head = self.head.load(Ordering::Acquire);
@@ -144,13 +379,77 @@ impl Registry {
     }
 }
 
-/// Register a call site. This function is a private function of this crate. It
-/// is only marked `pub` to be able to call it from inside macros.
+/// Register a call site. Registering the same call site multiple times is
+/// allowed, and only the first registration is taken into account. This
+/// function is a private function of this crate. It is only marked `pub` to
+/// be able to call it from inside macros.
 #[doc(hidden)]
 pub fn register_callsite(callsite: &'static CallSite) {
     REGISTRY.register(callsite);
 }
 
+/// Register `callsite` again if it was removed from the registry by
+/// [`reset_registry`], before recording data for it
+pub(crate) fn ensure_registered(callsite: &'static CallSite) {
+    if !callsite.registered.load(Ordering::Acquire) {
+        REGISTRY.register(callsite);
+    }
+}
+
+/// Get a call site with the given `name`, sharing all other metadata with the
+/// `base` call site. The call site is created and registered the first time
+/// this function is called with a given `name`, and re-used afterward. This
+/// function is a private function of this crate. It is only marked `pub` to
+/// be able to call it from inside macros.
+///
+/// Call sites created by this function are never deallocated, so the number
+/// of different names should stay small.
+#[doc(hidden)]
+pub fn dynamic_callsite(base: &'static CallSite, name: &str) -> &'static CallSite {
+    let mut all_callsites = DYNAMIC_CALLSITES.lock().unwrap_or_else(PoisonError::into_inner);
+    let callsites = all_callsites.entry(base.id()).or_default();
+    if let Some(callsite) = callsites.get(name) {
+        return callsite;
+    }
+
+    let callsite: &'static CallSite = Box::leak(Box::new(CallSite::with_metadata(
+        Box::leak(name.to_owned().into_boxed_str()),
+        base.module_path,
+        base.file,
+        base.line,
+        base.metadata,
+    )));
+    register_callsite(callsite);
+    callsites.insert(name.to_owned(), callsite);
+
+    return callsite;
+}
+
+/// Remove the module paths from a type name as returned by
+/// [`std::any::type_name`], turning `alloc::vec::Vec<my_crate::Foo>` into
+/// `Vec<Foo>`. This function is a private function of this crate. It is only
+/// marked `pub` to be able to call it from inside macros.
+#[doc(hidden)]
+pub fn short_type_name(name: &str) -> String {
+    let mut result = String::with_capacity(name.len());
+    // start of the current path in `result`
+    let mut path_start = 0;
+    let mut chars = name.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c == ':' && chars.peek() == Some(&':') {
+            let _ = chars.next();
+            result.truncate(path_start);
+        } else if c.is_alphanumeric() || c == '_' {
+            result.push(c);
+        } else {
+            result.push(c);
+            path_start = result.len();
+        }
+    }
+
+    return result;
+}
+
 /// Execute the given function on all call sites we know about.
 ///
 /// # Examples
@@ -164,3 +463,85 @@ pub fn register_callsite(callsite: &'static CallSite) {
 pub fn traverse_registered_callsite(function: impl FnMut(&'static CallSite)) {
     REGISTRY.for_each(function);
 }
+
+/// Get the number of call sites currently registered.
+///
+/// # Examples
+/// ```
+/// # use time_graph::{callsite, registered_callsite_count};
+/// let before = registered_callsite_count();
+/// let _ = callsite!("new callsite");
+/// assert!(registered_callsite_count() > before);
+/// ```
+pub fn registered_callsite_count() -> usize {
+    let mut count = 0;
+    REGISTRY.for_each(|_| count += 1);
+    return count;
+}
+
+/// Get all the call sites currently registered, starting with the most
+/// recently registered one.
+pub fn collect_callsites() -> Vec<&'static CallSite> {
+    let mut callsites = Vec::new();
+    REGISTRY.for_each(|callsite| callsites.push(callsite));
+    return callsites;
+}
+
+/// Get all the call sites currently registered, sorted by source file, line
+/// and name. Contrary to [`collect_callsites`], the order does not depend on
+/// the order in which call sites were first used.
+pub fn collect_callsites_sorted() -> Vec<&'static CallSite> {
+    let mut callsites = collect_callsites();
+    callsites.sort_by_key(|callsite| (callsite.file(), callsite.line(), callsite.name()));
+    return callsites;
+}
+
+/// Remove all call sites from the global registry, and clear all collected
+/// data. This is mainly intended to isolate tests from one another.
+///
+/// Call sites used before calling this function will not be visible to
+/// [`traverse_registered_callsite`] anymore, until data is recorded for them
+/// again, which registers them again. Call sites are never deallocated, so
+/// references obtained before calling this function remain valid.
+///
+/// Call sites registered concurrently with this function might be lost, so
+/// this function should only be called when no other thread is registering
+/// new call sites.
+pub fn reset_registry() {
+    REGISTRY.clear();
+    crate::clear_collected_data();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn short_type_name() {
+        assert_eq!(super::short_type_name("Foo"), "Foo");
+        assert_eq!(super::short_type_name("my_crate::module::Foo"), "Foo");
+        assert_eq!(
+            super::short_type_name("alloc::vec::Vec<my_crate::Foo<u8>>"),
+            "Vec<Foo<u8>>"
+        );
+        assert_eq!(
+            super::short_type_name("(&my_crate::Foo, [core::option::Option<i32>; 4])"),
+            "(&Foo, [Option<i32>; 4])"
+        );
+    }
+
+    #[test]
+    fn zero_id() {
+        assert_eq!(CallSiteId::new(0), None);
+        assert_eq!(CallSiteId::new(3).map(|id| id.0.get()), Some(3));
+    }
+
+    #[test]
+    fn id_wraparound() {
+        let counter = AtomicU64::new(u64::MAX);
+        assert_eq!(CallSiteId::next(&counter).0.get(), u64::MAX);
+        // zero is skipped after wrapping around
+        assert_eq!(CallSiteId::next(&counter).0.get(), 1);
+        assert_eq!(CallSiteId::next(&counter).0.get(), 2);
+    }
+}
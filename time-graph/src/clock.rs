@@ -0,0 +1,120 @@
+use std::sync::{Arc, RwLock, PoisonError};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+use once_cell::sync::Lazy;
+
+/// Default clock to record start/end times, using the TSC where available
+#[cfg(not(feature = "std-clock"))]
+static DEFAULT_CLOCK: Lazy<quanta::Clock> = Lazy::new(quanta::Clock::new);
+
+/// Origin of the times returned by the default clock when using
+/// `std::time::Instant`
+#[cfg(feature = "std-clock")]
+static DEFAULT_CLOCK_ORIGIN: Lazy<std::time::Instant> = Lazy::new(std::time::Instant::now);
+
+/// User-provided time source, set with [`set_clock`]
+static CUSTOM_CLOCK: RwLock<Option<Arc<dyn TimeSource>>> = RwLock::new(None);
+
+/// Is there a user-provided time source in `CUSTOM_CLOCK`? This is used to
+/// skip locking `CUSTOM_CLOCK` when using the default clock.
+static USE_CUSTOM_CLOCK: AtomicBool = AtomicBool::new(false);
+
+/// A [`TimeSource`] gives the current time to measure the duration of spans.
+///
+/// The default time source uses a monotonic, high resolution clock. A custom
+/// time source can be used with [`set_clock`], for example to get
+/// deterministic timings in tests.
+pub trait TimeSource: Send + Sync + 'static {
+    /// Get the current time, in nanoseconds since an arbitrary origin. The
+    /// returned values should never decrease.
+    fn now(&self) -> u64;
+}
+
+/// Use the given `clock` to measure the duration of all spans, instead of the
+/// default monotonic clock.
+///
+/// This should be called while data collection is disabled, since spans
+/// running while the clock is changed will record incorrect durations.
+///
+/// # Examples
+/// ```
+/// use std::sync::atomic::{AtomicU64, Ordering};
+///
+/// struct FakeClock(AtomicU64);
+///
+/// impl time_graph::TimeSource for FakeClock {
+///     fn now(&self) -> u64 {
+///         // time advances by 1µs every time it is read
+///         self.0.fetch_add(1000, Ordering::SeqCst)
+///     }
+/// }
+///
+/// time_graph::set_clock(FakeClock(AtomicU64::new(0)));
+/// ```
+pub fn set_clock(clock: impl TimeSource) {
+    let mut custom = CUSTOM_CLOCK.write().unwrap_or_else(PoisonError::into_inner);
+    *custom = Some(Arc::new(clock));
+    USE_CUSTOM_CLOCK.store(true, Ordering::Release);
+}
+
+/// Go back to using the default monotonic clock to measure the duration of
+/// spans, after a call to [`set_clock`].
+pub fn reset_clock() {
+    let mut custom = CUSTOM_CLOCK.write().unwrap_or_else(PoisonError::into_inner);
+    *custom = None;
+    USE_CUSTOM_CLOCK.store(false, Ordering::Release);
+}
+
+/// Get the current time, in an unspecified unit. Use [`elapsed`] to convert
+/// the difference between two times to a [`Duration`].
+pub(crate) fn now() -> u64 {
+    if USE_CUSTOM_CLOCK.load(Ordering::Acquire) {
+        if let Some(clock) = &*CUSTOM_CLOCK.read().unwrap_or_else(PoisonError::into_inner) {
+            return clock.now();
+        }
+    }
+    return default_now();
+}
+
+/// Get the time elapsed between `start` and `end`, as returned by [`now`].
+pub(crate) fn elapsed(start: u64, end: u64) -> Duration {
+    if USE_CUSTOM_CLOCK.load(Ordering::Acquire) {
+        return Duration::from_nanos(end.saturating_sub(start));
+    }
+    return default_elapsed(start, end);
+}
+
+/// Get the current time from the default clock
+#[cfg(not(feature = "std-clock"))]
+#[inline]
+fn default_now() -> u64 {
+    return DEFAULT_CLOCK.raw();
+}
+
+/// Get the time elapsed between `start` and `end`, as returned by
+/// [`default_now`]
+#[cfg(not(feature = "std-clock"))]
+#[inline]
+fn default_elapsed(start: u64, end: u64) -> Duration {
+    return DEFAULT_CLOCK.delta(start, end);
+}
+
+/// Get the current time from the default clock, in nanoseconds since
+/// `DEFAULT_CLOCK_ORIGIN`
+#[cfg(feature = "std-clock")]
+#[inline]
+fn default_now() -> u64 {
+    use std::convert::TryFrom;
+
+    let elapsed = DEFAULT_CLOCK_ORIGIN.elapsed().as_nanos();
+    return u64::try_from(elapsed).unwrap_or(u64::MAX);
+}
+
+/// Get the time elapsed between `start` and `end`, as returned by
+/// [`default_now`]
+#[cfg(feature = "std-clock")]
+#[inline]
+fn default_elapsed(start: u64, end: u64) -> Duration {
+    return Duration::from_nanos(end.saturating_sub(start));
+}
@@ -1,30 +1,396 @@
-use std::sync::Mutex;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::collections::BTreeMap;
-use std::time::Duration;
-use std::cell::RefCell;
+use std::sync::{Arc, Mutex, RwLock, PoisonError};
+use std::sync::atomic::{AtomicBool, AtomicU32, AtomicU64, AtomicUsize, Ordering};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, SystemTime};
+use std::cell::{Cell, RefCell};
+use std::path::Path;
 
 use once_cell::sync::Lazy;
-use quanta::Clock;
 use petgraph::graph::{Graph, NodeIndex};
+use petgraph::visit::EdgeRef;
 
-use crate::{CallSite, CallSiteId};
+use crate::{CallSite, CallSiteId, Level, NameStyle, OwnedCallGraph, OwnedSpan};
+#[cfg(feature = "table")]
+use crate::TableStyle;
 
-/// Global clock to record start/end times
-static CLOCK: Lazy<Clock> = Lazy::new(Clock::new);
+/// Call graphs of all the threads currently running, including recorded
+/// timings and calls count.
+///
+/// All the locks in this crate recover from poisoning instead of panicking,
+/// since spans can be dropped while unwinding from a panic, and profiling
+/// should never crash otherwise healthy code.
+static THREAD_CALL_GRAPHS: Lazy<Mutex<Vec<Arc<RwLock<LightCallGraph>>>>> = Lazy::new(|| {
+    Mutex::new(Vec::new())
+});
+
+/// Names of all the threads which recorded data, used to label them in the
+/// exported data. Threads without a name use the debug representation of
+/// their id.
+#[cfg(feature = "per-thread")]
+static THREAD_NAMES: Lazy<Mutex<HashMap<std::thread::ThreadId, String>>> = Lazy::new(|| {
+    Mutex::new(HashMap::new())
+});
 
-/// Global call graph, including recorded timings and calls count
-static CALL_GRAPH: Lazy<Mutex<LightCallGraph>> = Lazy::new(|| {
+/// Call graph containing data from all the threads which finished running
+static FINISHED_THREADS_CALL_GRAPH: Lazy<Mutex<LightCallGraph>> = Lazy::new(|| {
     Mutex::new(LightCallGraph::new())
 });
 
+/// Number of active requests to collect data. Data is collected as long as
+/// this is larger than zero.
+static COLLECTION_ENABLED: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of active [`CollectionState`] scopes disabling data collection. No
+/// data is collected while this is larger than zero, regardless of
+/// `COLLECTION_ENABLED`.
+static COLLECTION_FORCED_OFF: AtomicUsize = AtomicUsize::new(0);
+
+/// Time during which data collection was enabled since the last call to
+/// [`clear_collected_data`]. This is always locked before updating
+/// `COLLECTION_ENABLED` or `COLLECTION_FORCED_OFF`, to keep them in sync.
+static COLLECTION_WINDOW: Mutex<CollectionWindow> = Mutex::new(CollectionWindow {
+    active_since: None,
+    previous: Duration::ZERO,
+});
+
+/// Spans entered when this many spans are already executing on the current
+/// thread are not recorded. `usize::MAX` means there is no limit.
+static MAX_RECORDING_DEPTH: AtomicUsize = AtomicUsize::new(usize::MAX);
+
+/// Module path prefixes of the spans to record, or an empty list to record
+/// all spans
+static MODULE_FILTER: RwLock<Vec<String>> = RwLock::new(Vec::new());
+
+/// Only record one in `SAMPLING_RATE` executions of call sites without their
+/// own sampling rate
+static SAMPLING_RATE: AtomicU32 = AtomicU32::new(1);
+
+/// Should all spans only be counted, without measuring their duration?
+static COUNT_ONLY: AtomicBool = AtomicBool::new(false);
+
+/// Is `MODULE_FILTER` non-empty? This is checked first when entering a span,
+/// to avoid taking the lock when there is no filter.
+static MODULE_FILTER_ENABLED: AtomicBool = AtomicBool::new(false);
+
 /// Should we collect data?
-static COLLECTION_ENABLED: AtomicBool = AtomicBool::new(false);
+///
+/// This is checked every time a span is entered, so it uses `Relaxed` loads.
+/// `COLLECTION_ENABLED` and `COLLECTION_FORCED_OFF` do not publish any other
+/// data: the call graphs and
+/// call sites are protected by their own locks and atomics, which provide the
+/// required synchronization. The only consequence is that another thread can
+/// observe a change to data collection slightly later, which could already
+/// happen with a stronger ordering since spans do not synchronize with
+/// [`enable_data_collection`].
+#[inline]
+pub(crate) fn collection_enabled() -> bool {
+    COLLECTION_ENABLED.load(Ordering::Relaxed) != 0 && COLLECTION_FORCED_OFF.load(Ordering::Relaxed) == 0
+}
+
+/// Get the number of requests to collect data that are in effect, given
+/// `count` requests in `COLLECTION_ENABLED`. This is zero if a scope is
+/// disabling data collection. `COLLECTION_WINDOW` should be locked when
+/// calling this function.
+fn effective_requests(count: usize) -> usize {
+    if COLLECTION_FORCED_OFF.load(Ordering::Acquire) != 0 {
+        return 0;
+    }
+    return count;
+}
+
+/// Span currently executing on a thread
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct CurrentSpan {
+    /// Id of the span call site
+    pub id: CallSiteId,
+    /// Clock value when the span was entered
+    pub start: u64,
+    /// Time already spent in the span before `start`, for spans entered
+    /// multiple times such as `Instrumented` futures
+    pub before: Duration,
+    /// Sampling rate of the current execution of the span
+    pub sampling_rate: u32,
+    /// Is the span only counted, without measuring its duration? `start` is
+    /// meaningless in this case.
+    pub count_only: bool,
+}
+
+impl CurrentSpan {
+    /// Get the time spent in this span until now, if it is measured
+    pub fn elapsed(&self) -> Option<Duration> {
+        if self.count_only {
+            return None;
+        }
+        return Some(self.before + crate::clock::elapsed(self.start, crate::clock::now()));
+    }
+}
 
 thread_local! {
     /// For each thread, which span is currently executing? This will become the
     /// parent of new spans.
-    pub static LOCAL_CURRENT_SPAN: RefCell<Option<CallSiteId>> = const { RefCell::new(None) };
+    pub static LOCAL_CURRENT_SPAN: RefCell<Option<CurrentSpan>> = const { RefCell::new(None) };
+
+    /// For each thread, how many times is each span currently executing? This
+    /// is used to only record the time of the outermost execution of recursive
+    /// spans.
+    pub static LOCAL_ACTIVE_SPANS: RefCell<HashMap<CallSiteId, usize>> = RefCell::new(HashMap::new());
+
+    /// For each thread, how many spans are currently executing? This is
+    /// compared to `MAX_RECORDING_DEPTH`.
+    static LOCAL_DEPTH: Cell<usize> = const { Cell::new(0) };
+
+    /// Call graph for the current thread. Using one graph per thread means the
+    /// corresponding lock is only contended when extracting data.
+    static LOCAL_CALL_GRAPH: ThreadCallGraph = ThreadCallGraph::new();
+}
+
+/// Call graph for a single thread, registered in `THREAD_CALL_GRAPHS` during
+/// the lifetime of the thread.
+struct ThreadCallGraph {
+    graph: Arc<RwLock<LightCallGraph>>,
+}
+
+impl ThreadCallGraph {
+    fn new() -> ThreadCallGraph {
+        #[allow(unused_mut)]
+        let mut graph = LightCallGraph::new();
+        #[cfg(feature = "per-thread")]
+        {
+            let thread = std::thread::current();
+            graph.thread = Some(thread.id());
+
+            let name = thread.name().map_or_else(|| format!("{:?}", thread.id()), String::from);
+            THREAD_NAMES.lock().unwrap_or_else(PoisonError::into_inner).insert(thread.id(), name);
+        }
+
+        let graph = Arc::new(RwLock::new(graph));
+        THREAD_CALL_GRAPHS.lock().unwrap_or_else(PoisonError::into_inner).push(Arc::clone(&graph));
+        ThreadCallGraph {
+            graph: graph,
+        }
+    }
+}
+
+impl Drop for ThreadCallGraph {
+    fn drop(&mut self) {
+        // keep the data from this thread around after it finished running
+        let mut all_graphs = THREAD_CALL_GRAPHS.lock().unwrap_or_else(PoisonError::into_inner);
+        let mut finished = FINISHED_THREADS_CALL_GRAPH.lock().unwrap_or_else(PoisonError::into_inner);
+        finished.merge(&self.graph.read().unwrap_or_else(PoisonError::into_inner));
+        all_graphs.retain(|graph| !Arc::ptr_eq(graph, &self.graph));
+    }
+}
+
+/// Only record the spans executing at most `depth` levels deep on each thread,
+/// or all spans if `depth` is `None`. Spans entered deeper than this are not
+/// recorded, and have the same overhead as when data collection is disabled.
+/// This makes it possible to instrument recursive functions while only
+/// recording the top calls.
+///
+/// # Examples
+/// ```
+/// # use time_graph::{spanned, set_max_recording_depth};
+/// set_max_recording_depth(Some(1));
+/// time_graph::enable_data_collection(true);
+///
+/// spanned!("recorded", {
+///     spanned!("not recorded", {});
+/// });
+///
+/// let graph = time_graph::get_full_graph();
+/// assert!(graph.span_by_name("recorded").is_some());
+/// assert!(graph.span_by_name("not recorded").is_none());
+/// ```
+pub fn set_max_recording_depth(depth: Option<usize>) {
+    MAX_RECORDING_DEPTH.store(depth.unwrap_or(usize::MAX), Ordering::Release);
+}
+
+/// Only record the spans created in a module starting with one of the given
+/// `prefixes`, or all spans if `prefixes` is empty. A prefix matches a module
+/// path if they are equal, or if the module path continues with `::` after
+/// the prefix: `my_crate` matches `my_crate::io` but not `my_crate_macros`.
+///
+/// Spans created in other modules are not recorded, and have the same
+/// overhead as when data collection is disabled. Spans created inside them are
+/// recorded as called by the closest recorded span. This allows to only
+/// profile your own code when dependencies also use this crate.
+///
+/// # Examples
+/// ```
+/// # use time_graph::{spanned, set_module_filter};
+/// set_module_filter(vec!["my_crate".into()]);
+/// time_graph::enable_data_collection(true);
+///
+/// spanned!("not recorded", {});
+///
+/// let graph = time_graph::get_full_graph();
+/// assert!(graph.span_by_name("not recorded").is_none());
+/// ```
+pub fn set_module_filter(prefixes: Vec<String>) {
+    let mut filter = MODULE_FILTER.write().unwrap_or_else(PoisonError::into_inner);
+    MODULE_FILTER_ENABLED.store(!prefixes.is_empty(), Ordering::Release);
+    *filter = prefixes;
+}
+
+/// Check if spans from the given `callsite` should be recorded according to
+/// the filter set with [`set_module_filter`]
+pub(crate) fn module_allowed(callsite: &CallSite) -> bool {
+    if !MODULE_FILTER_ENABLED.load(Ordering::Acquire) {
+        return true;
+    }
+
+    let module_path = callsite.module_path();
+    let filter = MODULE_FILTER.read().unwrap_or_else(PoisonError::into_inner);
+    return filter.is_empty() || filter.iter().any(|prefix| module_matches(module_path, prefix));
+}
+
+/// Check if `module_path` is the module `prefix` or one of its submodules. A
+/// trailing `::` in `prefix` is ignored.
+pub(crate) fn module_matches(module_path: &str, prefix: &str) -> bool {
+    match module_path.strip_prefix(prefix.trim_end_matches("::")) {
+        Some(rest) => return rest.is_empty() || rest.starts_with("::"),
+        None => return false,
+    }
+}
+
+/// Only record one in `rate` executions of the spans from the given
+/// `callsite`, or of all spans if `callsite` is `None`. The rate of a call
+/// site takes precedence over the global one, and setting it to 0 makes the
+/// call site use the global rate again. A global rate of 0 or 1 records all
+/// executions.
+///
+/// The executions which are not sampled have the same overhead as when data
+/// collection is disabled, and the data recorded for sampled ones is scaled
+/// up by `rate`: each of them counts as `rate` calls, with `rate` times its
+/// elapsed time, allocations and metrics. Spans created inside executions
+/// which are not sampled are recorded as called by the closest recorded span,
+/// so sampling works best for hot spans without children.
+///
+/// # Examples
+/// ```
+/// # use time_graph::{Span, callsite, set_sampling};
+/// let callsite = callsite!("hot");
+/// set_sampling(Some(callsite), 10);
+///
+/// time_graph::enable_data_collection(true);
+/// let span = Span::new(callsite);
+/// for _ in 0..100 {
+///     let _guard = span.enter();
+/// }
+///
+/// let graph = time_graph::get_full_graph();
+/// let span = graph.span_by_name("hot").unwrap();
+/// assert_eq!(span.called, 100);
+/// assert_eq!(span.sampling_rate, 10);
+/// ```
+pub fn set_sampling(callsite: Option<&CallSite>, rate: u32) {
+    match callsite {
+        Some(callsite) => callsite.sampling_rate.store(rate, Ordering::Relaxed),
+        None => SAMPLING_RATE.store(rate.max(1), Ordering::Relaxed),
+    }
+}
+
+/// Only count the executions of spans from the given `callsite`, or of all
+/// spans if `callsite` is `None`, without measuring their duration. This
+/// removes the clock reads when entering and exiting these spans, reducing
+/// the overhead of spans executed very often when only their number of calls
+/// is interesting. A span is only counted if either the global setting or the
+/// setting of its call site is `true`.
+///
+/// The elapsed time and allocations of counted spans are recorded as zero.
+/// Counting can also be enabled for a single function with
+/// `#[instrument(count_only)]`.
+///
+/// # Examples
+/// ```
+/// # use time_graph::{Span, callsite, set_count_only};
+/// let callsite = callsite!("counted");
+/// set_count_only(Some(callsite), true);
+///
+/// time_graph::enable_data_collection(true);
+/// let span = Span::new(callsite);
+/// for _ in 0..10 {
+///     let _guard = span.enter();
+/// }
+///
+/// let graph = time_graph::get_full_graph();
+/// let span = graph.span_by_name("counted").unwrap();
+/// assert_eq!(span.called, 10);
+/// assert_eq!(span.elapsed, std::time::Duration::ZERO);
+/// ```
+pub fn set_count_only(callsite: Option<&CallSite>, count_only: bool) {
+    match callsite {
+        Some(callsite) => callsite.count_only.store(count_only, Ordering::Relaxed),
+        None => COUNT_ONLY.store(count_only, Ordering::Relaxed),
+    }
+}
+
+/// Should executions of `callsite` only be counted, without measuring their
+/// duration?
+pub(crate) fn is_count_only(callsite: &CallSite) -> bool {
+    return COUNT_ONLY.load(Ordering::Relaxed) || callsite.count_only.load(Ordering::Relaxed);
+}
+
+/// Decide if the current execution of `callsite` should be recorded, returning
+/// the sampling rate used to scale the recorded data if it should.
+pub(crate) fn sample(callsite: &CallSite) -> Option<u32> {
+    let rate = match callsite.sampling_rate.load(Ordering::Relaxed) {
+        0 => SAMPLING_RATE.load(Ordering::Relaxed),
+        rate => rate,
+    };
+
+    if rate <= 1 {
+        return Some(1);
+    }
+
+    let count = callsite.sample_counter.fetch_add(1, Ordering::Relaxed);
+    if count.is_multiple_of(rate) {
+        return Some(rate);
+    } else {
+        return None;
+    }
+}
+
+/// Go one level deeper in the spans executing on the current thread, and
+/// check if spans at this level should be recorded.
+pub(crate) fn enter_depth() -> bool {
+    let depth = LOCAL_DEPTH.with(|depth| {
+        let current = depth.get();
+        depth.set(current + 1);
+        return current;
+    });
+    return depth < MAX_RECORDING_DEPTH.load(Ordering::Acquire);
+}
+
+/// Go back one level in the spans executing on the current thread
+pub(crate) fn exit_depth() {
+    LOCAL_DEPTH.with(|depth| depth.set(depth.get().saturating_sub(1)));
+}
+
+/// Mark the span with the given `callsite` as executing on the current thread,
+/// and return whether it was already executing, i.e. if this is a recursive
+/// execution of the span.
+pub(crate) fn enter_active_span(callsite: CallSiteId) -> bool {
+    LOCAL_ACTIVE_SPANS.with(|active| {
+        let mut active = active.borrow_mut();
+        let depth = active.entry(callsite).or_insert(0);
+        *depth += 1;
+        return *depth > 1;
+    })
+}
+
+/// Mark one execution of the span with the given `callsite` as finished on the
+/// current thread.
+pub(crate) fn exit_active_span(callsite: CallSiteId) {
+    LOCAL_ACTIVE_SPANS.with(|active| {
+        let mut active = active.borrow_mut();
+        if let Some(depth) = active.get_mut(&callsite) {
+            *depth -= 1;
+            if *depth == 0 {
+                active.remove(&callsite);
+            }
+        }
+    });
 }
 
 /// A [`Span`] records a single execution of code associated with a
@@ -48,188 +414,1629 @@ impl Span {
     /// [`SpanGuard`] is dropped.
     #[must_use]
     pub fn enter(&self) -> SpanGuard<'_> {
-        if !COLLECTION_ENABLED.load(Ordering::Acquire) {
-            return SpanGuard {
-                span: self,
-                parent: None,
-                start: 0,
-            };
+        if !collection_enabled() {
+            return self.disabled_guard();
         }
 
-        let id = self.callsite.id();
-        let parent = LOCAL_CURRENT_SPAN.with(|parent| {
-            let mut parent = parent.borrow_mut();
+        return self.enter_as_child_of(current_span_id());
+    }
 
-            let previous = *parent;
-            *parent = Some(id);
-            return previous;
-        });
+    /// Enter the span as a child of the span with the given `parent` id,
+    /// instead of the span currently executing on this thread. This allows to
+    /// attach work running on another thread to the span which created it,
+    /// using [`current_span_id`] to get the id of the parent span.
+    ///
+    /// # Examples
+    /// ```
+    /// # use time_graph::{Span, callsite, current_span_id};
+    /// let outer = Span::new(callsite!("outer"));
+    /// let _guard = outer.enter();
+    ///
+    /// let parent = current_span_id();
+    /// std::thread::spawn(move || {
+    ///     let inner = Span::new(callsite!("inner"));
+    ///     let _guard = inner.enter_as_child_of(parent);
+    ///     // ...
+    /// }).join().unwrap();
+    /// ```
+    #[must_use]
+    pub fn enter_as_child_of(&self, parent: Option<CallSiteId>) -> SpanGuard<'_> {
+        if !collection_enabled() || !module_allowed(self.callsite) {
+            return self.disabled_guard();
+        }
+
+        let sampling_rate = match sample(self.callsite) {
+            Some(rate) => rate,
+            None => return self.disabled_guard(),
+        };
+
+        if !enter_depth() {
+            let mut guard = self.disabled_guard();
+            guard.depth = true;
+            return guard;
+        }
+
+        let id = self.callsite.id();
+        let count_only = is_count_only(self.callsite);
+        let start = if count_only { 0 } else { crate::clock::now() };
+        let previous = LOCAL_CURRENT_SPAN.with(|current| current.borrow_mut().replace(CurrentSpan {
+            id: id,
+            start: start,
+            before: Duration::new(0, 0),
+            sampling_rate: sampling_rate,
+            count_only: count_only,
+        }));
 
         SpanGuard {
             span: self,
+            recording: true,
+            depth: true,
+            recursive: enter_active_span(id),
             parent: parent,
-            start: CLOCK.raw(),
+            previous: previous,
+            start: start,
+            sampling_rate: sampling_rate,
+            count_only: count_only,
+            #[cfg(feature = "alloc-tracking")]
+            allocations: crate::alloc::allocations(),
+        }
+    }
+
+    /// Run `function` inside this span, returning both its result and the
+    /// time it took to run. The duration is always measured, even when data
+    /// collection is disabled and nothing is recorded in the call graph.
+    ///
+    /// # Examples
+    /// ```
+    /// # use time_graph::{Span, callsite};
+    /// let span = Span::new(callsite!("sleep"));
+    /// let (value, elapsed) = span.measure(|| {
+    ///     std::thread::sleep(std::time::Duration::from_millis(2));
+    ///     42
+    /// });
+    /// assert_eq!(value, 42);
+    /// assert!(elapsed >= std::time::Duration::from_millis(2));
+    /// ```
+    pub fn measure<T>(&self, function: impl FnOnce() -> T) -> (T, Duration) {
+        let _guard = self.enter();
+        let start = crate::clock::now();
+        let result = function();
+        let elapsed = crate::clock::elapsed(start, crate::clock::now());
+        return (result, elapsed);
+    }
+
+    /// Get a guard which does not record anything
+    fn disabled_guard(&self) -> SpanGuard<'_> {
+        SpanGuard {
+            span: self,
+            recording: false,
+            depth: false,
+            recursive: false,
+            parent: None,
+            previous: None,
+            start: 0,
+            sampling_rate: 1,
+            count_only: false,
+            #[cfg(feature = "alloc-tracking")]
+            allocations: (0, 0),
+        }
+    }
+}
+
+/// Get the id of the span currently executing on this thread, if any. This can
+/// be passed to [`Span::enter_as_child_of`] to record spans executing on
+/// another thread as children of the current span.
+pub fn current_span_id() -> Option<CallSiteId> {
+    LOCAL_CURRENT_SPAN.with(|current| current.borrow().map(|span| span.id))
+}
+
+/// Get the time spent in the span currently executing on this thread, if any.
+/// This allows to change behavior depending on how long the current span has
+/// been running, for example to bail out early from slow operations.
+///
+/// Spans which are not recorded (for example because data collection is
+/// disabled) are ignored, and this returns the elapsed time of the closest
+/// recorded span instead. For spans around `async` code, this is the total
+/// time spent polling the future until now. This returns `None` if the current
+/// span only counts calls (see [`set_count_only`]).
+///
+/// # Examples
+/// ```
+/// # use std::time::Duration;
+/// # use time_graph::spanned;
+/// time_graph::enable_data_collection(true);
+/// spanned!("slow", {
+///     std::thread::sleep(Duration::from_millis(2));
+///     let elapsed = time_graph::current_span_elapsed().unwrap();
+///     assert!(elapsed >= Duration::from_millis(2));
+/// });
+/// ```
+pub fn current_span_elapsed() -> Option<Duration> {
+    LOCAL_CURRENT_SPAN.with(|current| current.borrow().and_then(|span| span.elapsed()))
+}
+
+/// Spawn a new thread running `function`, like [`std::thread::spawn`]. The
+/// span currently executing on this thread becomes the parent of the spans
+/// entered by `function`, instead of them being recorded as new roots in the
+/// call graph.
+///
+/// # Examples
+/// ```
+/// # use time_graph::spanned;
+/// spanned!("outer", {
+///     let handle = time_graph::spawn(|| {
+///         // recorded as a child of "outer"
+///         spanned!("inner", {});
+///     });
+///     handle.join().unwrap();
+/// });
+/// ```
+pub fn spawn<F, T>(function: F) -> std::thread::JoinHandle<T>
+where
+    F: FnOnce() -> T + Send + 'static,
+    T: Send + 'static,
+{
+    let parent = LOCAL_CURRENT_SPAN.with(|current| *current.borrow());
+    std::thread::spawn(move || {
+        let previous = LOCAL_CURRENT_SPAN.with(|current| current.replace(parent));
+        let result = function();
+        LOCAL_CURRENT_SPAN.with(|current| *current.borrow_mut() = previous);
+        return result;
+    })
+}
+
+/// Spans created by [`SpanBuilder`], indexed by name, module path and tags
+static RUNTIME_SPANS: Lazy<Mutex<HashMap<RuntimeSpanKey, &'static Span>>> = Lazy::new(|| {
+    Mutex::new(HashMap::new())
+});
+
+type RuntimeSpanKey = (String, String, Vec<(String, String)>);
+
+/// Builder for spans created at runtime, without the [`instrument`] or
+/// [`spanned`] macros, for example when the instrumentation is driven by a
+/// configuration file.
+///
+/// A call site is created and registered the first time a span with a given
+/// name, module path and set of tags is entered, and re-used afterward. Call
+/// sites are never deallocated, so the number of different spans should stay
+/// small. The macros should be preferred when the span is known at compile
+/// time, since they avoid the lookup of the call site.
+///
+/// [`instrument`]: crate::instrument
+/// [`spanned`]: crate::spanned
+///
+/// # Examples
+/// ```
+/// # use time_graph::SpanBuilder;
+/// time_graph::enable_data_collection(true);
+/// {
+///     let _guard = SpanBuilder::new("load").module("config").tag("format", "toml").enter();
+///     // ...
+/// }
+///
+/// let graph = time_graph::get_full_graph();
+/// assert_eq!(graph.spans_with_tag("format", "toml").count(), 1);
+/// ```
+pub struct SpanBuilder {
+    name: String,
+    module_path: String,
+    tags: Vec<(String, String)>,
+    file: &'static str,
+    line: u32,
+}
+
+impl SpanBuilder {
+    /// Start building a span with the given `name`. The span file and line
+    /// are set to the location of the first call to this function for a
+    /// given span, and its module path is empty unless set with
+    /// [`SpanBuilder::module`].
+    #[track_caller]
+    pub fn new(name: &str) -> SpanBuilder {
+        let location = std::panic::Location::caller();
+        SpanBuilder {
+            name: name.to_owned(),
+            module_path: String::new(),
+            tags: Vec::new(),
+            file: location.file(),
+            line: location.line(),
+        }
+    }
+
+    /// Set the module path of this span
+    pub fn module(mut self, module_path: &str) -> SpanBuilder {
+        self.module_path = module_path.to_owned();
+        return self;
+    }
+
+    /// Add a `key = value` tag to this span, available through
+    /// [`CallSite::metadata`]
+    pub fn tag(mut self, key: &str, value: &str) -> SpanBuilder {
+        self.tags.push((key.to_owned(), value.to_owned()));
+        return self;
+    }
+
+    /// Get the span corresponding to this builder, creating and registering
+    /// its call site if needed
+    pub fn build(self) -> &'static Span {
+        let key = (self.name, self.module_path, self.tags);
+        let mut spans = RUNTIME_SPANS.lock().unwrap_or_else(PoisonError::into_inner);
+        if let Some(span) = spans.get(&key) {
+            return span;
         }
+
+        let leak = |string: &str| -> &'static str { Box::leak(string.to_owned().into_boxed_str()) };
+        let metadata = key.2.iter().map(|(k, v)| (leak(k), leak(v))).collect::<Vec<_>>();
+        let callsite: &'static CallSite = Box::leak(Box::new(CallSite::with_metadata(
+            leak(&key.0),
+            leak(&key.1),
+            self.file,
+            self.line,
+            Box::leak(metadata.into_boxed_slice()),
+        )));
+        crate::register_callsite(callsite);
+
+        let span: &'static Span = Box::leak(Box::new(Span::new(callsite)));
+        spans.insert(key, span);
+        return span;
+    }
+
+    /// Enter the span corresponding to this builder, see [`Span::enter`]
+    #[must_use]
+    pub fn enter(self) -> SpanGuard<'static> {
+        return self.build().enter();
     }
 }
 
-/// When a [`SpanGuard`] is dropped, it saves the execution time of the
-/// corresponding span in the global call graph.
+/// When a [`SpanGuard`] is dropped (or [`SpanGuard::finish`] is called), it
+/// saves the execution time of the corresponding span in the global call
+/// graph.
 pub struct SpanGuard<'a> {
     span: &'a Span,
+    /// Was data collection enabled when entering the span, and the span not
+    /// too deep to be recorded?
+    recording: bool,
+    /// Was this span counted in the depth of the current thread?
+    depth: bool,
+    /// Was the same span already executing when entering this one?
+    recursive: bool,
+    /// Parent span, which will be recorded as calling this one
     parent: Option<CallSiteId>,
+    /// Span which was executing on this thread before entering this one
+    previous: Option<CurrentSpan>,
     start: u64,
+    /// Sampling rate used to scale the recorded data
+    sampling_rate: u32,
+    /// Is this span only counted, without measuring its duration?
+    count_only: bool,
+    /// Number of allocations and allocated bytes when entering the span
+    #[cfg(feature = "alloc-tracking")]
+    allocations: (u64, u64),
+}
+
+impl<'a> SpanGuard<'a> {
+    /// Add `value` to the `key` custom metric of this span, for example the
+    /// number of bytes or rows processed. All the values recorded with the
+    /// same key are summed, and can be retrieved with [`TimedSpan::metric`].
+    /// Nothing is recorded if this span is not being recorded.
+    ///
+    /// # Examples
+    /// ```
+    /// # use time_graph::{Span, callsite};
+    /// let span = Span::new(callsite!("read"));
+    /// let guard = span.enter();
+    /// // ...
+    /// guard.record_metric("bytes", 1024);
+    /// ```
+    pub fn record_metric(&self, key: &'static str, value: u64) {
+        if self.recording && collection_enabled() {
+            record_metric_for(self.span.callsite.id(), key, value.saturating_mul(self.sampling_rate as u64));
+        }
+    }
+
+    /// Exit the span now instead of when the guard is dropped, recording its
+    /// execution time and returning it. This allows to exclude some cleanup
+    /// code running before the end of the scope from the span. If the span is
+    /// not being recorded or only counted (see [`set_count_only`]), this
+    /// returns a duration of zero.
+    ///
+    /// # Examples
+    /// ```
+    /// # use time_graph::{Span, callsite};
+    /// let span = Span::new(callsite!("work"));
+    /// let guard = span.enter();
+    /// // ...
+    /// let elapsed = guard.finish();
+    /// // cleanup, not included in the span
+    /// ```
+    pub fn finish(mut self) -> Duration {
+        return self.exit();
+    }
 }
 
 impl<'a> Drop for SpanGuard<'a>  {
     fn drop(&mut self) {
-        if !COLLECTION_ENABLED.load(Ordering::Acquire) {
-            return;
+        self.exit();
+    }
+}
+
+impl<'a> SpanGuard<'a> {
+    /// Exit the span and record its execution time if needed, returning the
+    /// time spent in the span. Calling this function again does nothing.
+    fn exit(&mut self) -> Duration {
+        if self.depth {
+            exit_depth();
+            self.depth = false;
         }
-        let elapsed = CLOCK.delta(self.start, CLOCK.raw());
+        if !self.recording {
+            return Duration::new(0, 0);
+        }
+        self.recording = false;
+        let elapsed = if self.count_only {
+            Duration::new(0, 0)
+        } else {
+            crate::clock::elapsed(self.start, crate::clock::now())
+        };
 
-        LOCAL_CURRENT_SPAN.with(|parent| {
-            let mut parent = parent.borrow_mut();
-            *parent = self.parent;
+        let callsite = self.span.callsite.id();
+        LOCAL_CURRENT_SPAN.with(|current| {
+            let mut current = current.borrow_mut();
+            *current = self.previous;
         });
+        exit_active_span(callsite);
 
+        if !collection_enabled() {
+            return elapsed;
+        }
 
-        let mut graph = CALL_GRAPH.lock().expect("poisoned mutex");
-        let callsite = self.span.callsite.id();
-        graph.add_node(callsite);
-        graph.increase_timing(callsite, elapsed);
+        crate::callback::span_completed(self.span.callsite, elapsed, self.parent);
 
-        if let Some(parent) = self.parent {
-            graph.add_node(parent);
-            graph.increase_call_count(parent, callsite);
-        }
+        // the time of recursive executions is already included in the time of
+        // the outermost execution
+        let measurement = if self.recursive || self.count_only {
+            None
+        } else {
+            Some(Measurement {
+                elapsed: elapsed,
+                #[cfg(feature = "alloc-tracking")]
+                allocations: {
+                    // the guard might be dropped on a different thread than
+                    // the one which created it (e.g. when held across an
+                    // `.await`), where the allocation counters can be lower
+                    let (allocations, bytes) = crate::alloc::allocations();
+                    (allocations.saturating_sub(self.allocations.0), bytes.saturating_sub(self.allocations.1))
+                },
+            })
+        };
+        crate::callsite::ensure_registered(self.span.callsite);
+        record_span(callsite, self.parent, measurement, self.sampling_rate);
+        return elapsed;
     }
 }
 
-/// Call graph node identifying their call site with its `CallSiteId`.
-struct LightGraphNode {
-    callsite: CallSiteId,
-    elapsed: Duration,
-    called: u32,
+/// Data measured during a single execution of a span
+pub(crate) struct Measurement {
+    /// Time spent executing the span
+    pub elapsed: Duration,
+    /// Number of allocations and allocated bytes while executing the span
+    #[cfg(feature = "alloc-tracking")]
+    pub allocations: (u64, u64),
 }
 
-impl LightGraphNode {
-    fn new(callsite: CallSiteId) -> LightGraphNode {
-        LightGraphNode {
-            callsite: callsite,
-            elapsed: Duration::new(0, 0),
-            called: 0,
+/// Estimated time spent by this crate to record a single execution of a span,
+/// measured the first time it is needed by repeatedly running the same
+/// bookkeeping as `Span::enter` and `SpanGuard::drop`.
+static PER_CALL_OVERHEAD: Lazy<Duration> = Lazy::new(|| {
+    const ITERATIONS: u32 = 10_000;
+    // this id is never attributed to a real call site in practice
+    let callsite = CallSiteId::new(u64::MAX).expect("got a zero id");
+    let mut graph = LightCallGraph::new();
+    graph.prepare(callsite, None);
+    let graph = RwLock::new(graph);
+
+    let start = std::time::Instant::now();
+    for _ in 0..ITERATIONS {
+        let recursive = enter_active_span(callsite);
+        let span_start = crate::clock::now();
+        let elapsed = crate::clock::elapsed(span_start, crate::clock::now());
+        exit_active_span(callsite);
+
+        let measurement = Measurement {
+            elapsed: elapsed,
+            #[cfg(feature = "alloc-tracking")]
+            allocations: (0, 0),
+        };
+        let graph = graph.read().unwrap_or_else(PoisonError::into_inner);
+        graph.record(callsite, None, if recursive { None } else { Some(&measurement) }, 1);
+    }
+    return start.elapsed() / ITERATIONS;
+});
+
+/// Record a single execution of the `callsite` span, called from `parent`, in
+/// the call graph of the current thread. `measurement` should be `None` for
+/// recursive executions of a span, which only count as a call.
+///
+/// Once a span has been recorded, the elapsed time and number of calls are
+/// updated with atomic counters while holding a shared lock on the graph of
+/// the current thread, which does not block functions extracting data such as
+/// [`get_full_graph`]. The exclusive lock is only taken to add new nodes and
+/// edges to the graph.
+pub(crate) fn record_span(
+    callsite: CallSiteId,
+    parent: Option<CallSiteId>,
+    measurement: Option<Measurement>,
+    sampling_rate: u32,
+) {
+    let recorded = LOCAL_CALL_GRAPH.try_with(|graph| {
+        let fast = graph.graph.read().unwrap_or_else(PoisonError::into_inner)
+            .record(callsite, parent, measurement.as_ref(), sampling_rate);
+
+        if !fast {
+            let mut graph = graph.graph.write().unwrap_or_else(PoisonError::into_inner);
+            graph.prepare(callsite, parent);
+            graph.record(callsite, parent, measurement.as_ref(), sampling_rate);
         }
+    });
+
+    if recorded.is_err() {
+        // the thread local call graph was already destroyed, this happens for
+        // spans executed inside other thread local destructors
+        let mut graph = FINISHED_THREADS_CALL_GRAPH.lock().unwrap_or_else(PoisonError::into_inner);
+        graph.prepare(callsite, parent);
+        graph.record(callsite, parent, measurement.as_ref(), sampling_rate);
     }
 }
 
-/// Simple Call graph, identifying call site with their `CallSiteId`.
-///
-/// The graph nodes are spans with associated timings, while the edges represent
-/// the number of calls from one node to the other.
-struct LightCallGraph {
-    graph: Graph<LightGraphNode, usize>
+/// Add the metrics in `other` to the ones in `metrics`
+fn merge_metrics(metrics: &mut BTreeMap<&'static str, u64>, other: &BTreeMap<&'static str, u64>) {
+    for (&key, &value) in other {
+        let metric = metrics.entry(key).or_insert(0);
+        *metric = metric.saturating_add(value);
+    }
 }
 
-impl LightCallGraph {
-    fn new() -> LightCallGraph {
-        LightCallGraph {
-            graph: Graph::new(),
+/// Add `value` to the `key` metric of the `callsite` span, in the call graph of
+/// the current thread.
+fn record_metric_for(callsite: CallSiteId, key: &'static str, value: u64) {
+    let recorded = LOCAL_CALL_GRAPH.try_with(|graph| {
+        let fast = graph.graph.read().unwrap_or_else(PoisonError::into_inner).increase_metric(callsite, key, value);
+        if !fast {
+            let mut graph = graph.graph.write().unwrap_or_else(PoisonError::into_inner);
+            graph.prepare(callsite, None);
+            graph.increase_metric(callsite, key, value);
         }
+    });
+
+    if recorded.is_err() {
+        let mut graph = FINISHED_THREADS_CALL_GRAPH.lock().unwrap_or_else(PoisonError::into_inner);
+        graph.prepare(callsite, None);
+        graph.increase_metric(callsite, key, value);
     }
+}
 
-    pub fn clear(&mut self) {
-        self.graph.clear()
+/// Add `value` to the `key` custom metric of the span currently executing on
+/// this thread, if any. See [`SpanGuard::record_metric`] for more information.
+///
+/// # Examples
+/// ```
+/// #[time_graph::instrument]
+/// fn process(data: &[u8]) {
+///     time_graph::record_metric("bytes", data.len() as u64);
+///     // ...
+/// }
+/// ```
+pub fn record_metric(key: &'static str, value: u64) {
+    if !collection_enabled() {
+        return;
     }
 
-    /// Find a node in the graph with its `CallSiteId`.
-    fn find(&mut self, callsite: CallSiteId) -> Option<NodeIndex> {
-        self.graph.node_indices().find(|&id| self.graph[id].callsite == callsite)
+    let current = LOCAL_CURRENT_SPAN.with(|current| *current.borrow());
+    if let Some(span) = current {
+        record_metric_for(span.id, key, value.saturating_mul(span.sampling_rate as u64));
     }
+}
 
-    /// Add a node for the given callsite to the graph, do nothing if there is
-    /// already such a node
-    pub fn add_node(&mut self, callsite: CallSiteId) {
-        if self.find(callsite).is_none() {
-            self.graph.add_node(LightGraphNode::new(callsite));
-        }
+/// Merge the nodes and edges of `other` into `graph`. Nodes are matched using
+/// the value returned by the `key` function, and merged with `merge_node`.
+/// Nodes only present in `other` are added to `graph` using `new_node`, which
+/// gets the node from `other` and the index of the new node. Edges weights are
+/// summed.
+fn merge_graphs<N, E: Clone + std::ops::AddAssign, K: std::hash::Hash + Eq>(
+    graph: &mut Graph<N, E>,
+    other: &Graph<N, E>,
+    key: impl Fn(&N) -> K,
+    mut merge_node: impl FnMut(&mut N, &N),
+    mut new_node: impl FnMut(&N, usize) -> N,
+) {
+    let mut nodes = HashMap::new();
+    for node in graph.node_indices() {
+        nodes.insert(key(&graph[node]), node);
     }
 
-    /// Increase the number of time the `parent` span called the `child` span
-    /// by one.
-    pub fn increase_call_count(&mut self, parent: CallSiteId, child: CallSiteId) {
-        let parent = self.find(parent).expect("missing node for parent");
-        let child = self.find(child).expect("missing node for child");
-        if let Some(edge) = self.graph.find_edge(parent, child) {
-            let count = self
-                .graph
-                .edge_weight_mut(edge)
-                .expect("failed to get edge weights");
-            *count += 1;
+    let mut other_to_self = HashMap::new();
+    for other_node in other.node_indices() {
+        let other_weight = &other[other_node];
+        let node = if let Some(&node) = nodes.get(&key(other_weight)) {
+            merge_node(&mut graph[node], other_weight);
+            node
         } else {
-            // initialize edge count to 1
-            self.graph.add_edge(parent, child, 1);
-        }
+            let node = graph.add_node(new_node(other_weight, graph.node_count()));
+            nodes.insert(key(other_weight), node);
+            node
+        };
+        other_to_self.insert(other_node, node);
     }
 
-    /// Increase the timing associated with a span by `time`, and the number of
-    /// time this span has been called by one.
-    pub fn increase_timing(&mut self, span: CallSiteId, time: Duration) {
-        let id = self.find(span).expect("missing node");
-        self.graph[id].elapsed += time;
-        self.graph[id].called += 1;
+    for edge in other.raw_edges() {
+        let source = other_to_self[&edge.source()];
+        let target = other_to_self[&edge.target()];
+        if let Some(existing) = graph.find_edge(source, target) {
+            graph[existing] += edge.weight.clone();
+        } else {
+            graph.add_edge(source, target, edge.weight.clone());
+        }
     }
 }
 
-/// Clear the global call graph from all data
-pub fn clear_collected_data() {
-    CALL_GRAPH.lock().expect("poisoned mutex").clear();
+/// Weight of the edges in the call graphs, recording the calls from a parent
+/// span to a child span
+#[derive(Debug, Clone, Copy, Default)]
+pub(crate) struct CallEdge {
+    /// Number of calls from the parent to the child
+    pub count: usize,
+    /// Total time spent in the child when called from the parent, excluding
+    /// recursive calls
+    pub elapsed: Duration,
 }
 
-/// Enable/disable data collection
-pub fn enable_data_collection(enabled: bool) {
-    COLLECTION_ENABLED.store(enabled, Ordering::Release);
+impl std::fmt::Display for CallEdge {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.count)
+    }
 }
 
-/// Get a copy of the call graph as currently known
-pub fn get_full_graph() -> FullCallGraph {
-    let graph = CALL_GRAPH.lock().expect("poisoned mutex");
-
-    let mut all_callsites = BTreeMap::new();
-    crate::traverse_registered_callsite(|callsite| {
-        all_callsites.insert(callsite.id(), callsite);
-    });
-
-    let graph = graph.graph.map(|index, node| {
-        TimedSpan::new(node, index.index(), all_callsites[&node.callsite])
-    }, |_, &edge| edge);
+impl std::ops::AddAssign for CallEdge {
+    fn add_assign(&mut self, other: CallEdge) {
+        self.count += other.count;
+        self.elapsed += other.elapsed;
+    }
+}
 
-    return FullCallGraph {
-        graph: graph
-    };
+/// Edge in the call graph of a single thread. The counters are updated
+/// without exclusive access to the graph, see [`LightCallGraph::record`].
+#[derive(Default)]
+struct LightCallEdge {
+    /// Number of calls from the parent to the child
+    count: AtomicUsize,
+    /// Total time spent in the child when called from the parent, in
+    /// nanoseconds
+    elapsed: AtomicU64,
 }
 
-/// [`TimedSpan`] contains all data related to a single function or span inside
+impl LightCallEdge {
+    /// Get the current value of the counters in this edge
+    fn load(&self) -> CallEdge {
+        CallEdge {
+            count: self.count.load(Ordering::Relaxed),
+            elapsed: Duration::from_nanos(self.elapsed.load(Ordering::Relaxed)),
+        }
+    }
+}
+
+impl Clone for LightCallEdge {
+    fn clone(&self) -> LightCallEdge {
+        let edge = self.load();
+        LightCallEdge {
+            count: AtomicUsize::new(edge.count),
+            elapsed: AtomicU64::new(duration_nanos(edge.elapsed)),
+        }
+    }
+}
+
+impl std::ops::AddAssign for LightCallEdge {
+    fn add_assign(&mut self, other: LightCallEdge) {
+        *self.count.get_mut() += other.count.into_inner();
+        *self.elapsed.get_mut() += other.elapsed.into_inner();
+    }
+}
+
+/// Get the number of nanoseconds in `duration`, saturating after 584 years
+fn duration_nanos(duration: Duration) -> u64 {
+    use std::convert::TryInto;
+    return duration.as_nanos().try_into().unwrap_or(u64::MAX);
+}
+
+/// Call graph node identifying their call site with its `CallSiteId`.
+///
+/// The elapsed time and number of calls are atomic counters, updated without
+/// exclusive access to the graph. All other data is behind a lock specific to
+/// this node.
+struct LightGraphNode {
+    callsite: CallSiteId,
+    /// Total elapsed time, in nanoseconds
+    elapsed: AtomicU64,
+    called: AtomicU32,
+    /// Largest sampling rate used to record this span
+    sampling_rate: AtomicU32,
+    stats: Mutex<NodeStats>,
+}
+
+/// Data associated with a [`LightGraphNode`] which can not be updated
+/// atomically
+#[derive(Clone)]
+struct NodeStats {
+    min: Duration,
+    max: Duration,
+    /// Variance of the elapsed time of single calls
+    variance: RunningVariance,
+    /// Wall-clock time at which the first recorded call started
+    first_seen: Option<SystemTime>,
+    /// Sum of the custom metrics recorded inside this span
+    metrics: BTreeMap<&'static str, u64>,
+    /// Distribution of the elapsed time of single calls, in nanoseconds
+    #[cfg(feature = "histogram")]
+    histogram: hdrhistogram::Histogram<u64>,
+    /// Elapsed time and number of calls for each thread. This is only filled
+    /// when merging the graphs from multiple threads together.
+    #[cfg(feature = "per-thread")]
+    per_thread: HashMap<std::thread::ThreadId, (Duration, u32)>,
+    /// Total number of allocations
+    #[cfg(feature = "alloc-tracking")]
+    allocations: u64,
+    /// Total number of allocated bytes
+    #[cfg(feature = "alloc-tracking")]
+    bytes_allocated: u64,
+}
+
+impl LightGraphNode {
+    fn new(callsite: CallSiteId) -> LightGraphNode {
+        LightGraphNode {
+            callsite: callsite,
+            elapsed: AtomicU64::new(0),
+            called: AtomicU32::new(0),
+            sampling_rate: AtomicU32::new(1),
+            stats: Mutex::new(NodeStats {
+                min: Duration::MAX,
+                max: Duration::new(0, 0),
+                variance: RunningVariance::new(),
+                first_seen: None,
+                metrics: BTreeMap::new(),
+                #[cfg(feature = "histogram")]
+                histogram: new_histogram(),
+                #[cfg(feature = "per-thread")]
+                per_thread: HashMap::new(),
+                #[cfg(feature = "alloc-tracking")]
+                allocations: 0,
+                #[cfg(feature = "alloc-tracking")]
+                bytes_allocated: 0,
+            }),
+        }
+    }
+
+    /// Get the total elapsed time in this node
+    fn elapsed(&self) -> Duration {
+        Duration::from_nanos(self.elapsed.load(Ordering::Relaxed))
+    }
+
+    /// Get the number of calls to this node
+    fn called(&self) -> u32 {
+        self.called.load(Ordering::Relaxed)
+    }
+
+    /// Lock the non-atomic data of this node
+    fn stats(&self) -> std::sync::MutexGuard<'_, NodeStats> {
+        self.stats.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Add the per-thread data of `other` to this node. If `thread` is `Some`,
+    /// all the data in `other` comes from this thread.
+    #[cfg(feature = "per-thread")]
+    fn merge_per_thread(&mut self, other: &LightGraphNode, thread: Option<std::thread::ThreadId>) {
+        if let Some(thread) = thread {
+            let stats = self.stats.get_mut().unwrap_or_else(PoisonError::into_inner);
+            let entry = stats.per_thread.entry(thread).or_default();
+            entry.0 += other.elapsed();
+            entry.1 += other.called();
+        } else {
+            let other = other.stats();
+            let stats = self.stats.get_mut().unwrap_or_else(PoisonError::into_inner);
+            merge_per_thread(&mut stats.per_thread, &other.per_thread);
+        }
+    }
+}
+
+impl Clone for LightGraphNode {
+    fn clone(&self) -> LightGraphNode {
+        LightGraphNode {
+            callsite: self.callsite,
+            elapsed: AtomicU64::new(self.elapsed.load(Ordering::Relaxed)),
+            called: AtomicU32::new(self.called()),
+            sampling_rate: AtomicU32::new(self.sampling_rate.load(Ordering::Relaxed)),
+            stats: Mutex::new(self.stats().clone()),
+        }
+    }
+}
+
+/// Add the per-thread timings and number of calls from `other` to `data`
+#[cfg(feature = "per-thread")]
+fn merge_per_thread(
+    data: &mut HashMap<std::thread::ThreadId, (Duration, u32)>,
+    other: &HashMap<std::thread::ThreadId, (Duration, u32)>,
+) {
+    for (&thread, &(elapsed, called)) in other {
+        let entry = data.entry(thread).or_default();
+        entry.0 += elapsed;
+        entry.1 += called;
+    }
+}
+
+/// Running mean and variance of the elapsed time of single calls, updated
+/// with Welford's algorithm for numerical stability.
+#[derive(Debug, Clone, Copy)]
+struct RunningVariance {
+    /// Number of recorded values
+    count: u64,
+    /// Mean of the recorded values, in nanoseconds
+    mean: f64,
+    /// Sum of the squared differences to the mean, in squared nanoseconds
+    m2: f64,
+}
+
+impl RunningVariance {
+    fn new() -> RunningVariance {
+        RunningVariance {
+            count: 0,
+            mean: 0.0,
+            m2: 0.0,
+        }
+    }
+
+    /// Add a single value to this variance
+    fn add(&mut self, value: Duration) {
+        let value = value.as_nanos() as f64;
+        self.count += 1;
+        let delta = value - self.mean;
+        self.mean += delta / self.count as f64;
+        self.m2 += delta * (value - self.mean);
+    }
+
+    /// Add all the values from `other` to this variance
+    fn merge(&mut self, other: &RunningVariance) {
+        if other.count == 0 {
+            return;
+        } else if self.count == 0 {
+            *self = *other;
+            return;
+        }
+
+        let count = self.count + other.count;
+        let delta = other.mean - self.mean;
+        let weight = self.count as f64 * other.count as f64 / count as f64;
+        self.mean += delta * other.count as f64 / count as f64;
+        self.m2 += other.m2 + delta * delta * weight;
+        self.count = count;
+    }
+
+    /// Get the sample standard deviation of the recorded values, or zero if
+    /// less than two values were recorded
+    fn stddev(&self) -> Duration {
+        if self.count < 2 {
+            return Duration::new(0, 0);
+        }
+        let variance = self.m2 / (self.count - 1) as f64;
+        return Duration::from_nanos(variance.sqrt().round() as u64);
+    }
+}
+
+/// Create a new auto-resizing histogram, with 3 significant digits
+#[cfg(feature = "histogram")]
+fn new_histogram() -> hdrhistogram::Histogram<u64> {
+    hdrhistogram::Histogram::new(3).expect("failed to create histogram")
+}
+
+/// Simple Call graph, identifying call site with their `CallSiteId`.
+///
+/// The graph nodes are spans with associated timings, while the edges represent
+/// the number of calls from one node to the other.
+struct LightCallGraph {
+    graph: Graph<LightGraphNode, LightCallEdge>,
+    /// Index of the node associated with each `CallSiteId` in the graph
+    nodes: HashMap<CallSiteId, NodeIndex>,
+    /// If all the data in this graph comes from a single thread, the id of
+    /// this thread
+    #[cfg(feature = "per-thread")]
+    thread: Option<std::thread::ThreadId>,
+}
+
+impl LightCallGraph {
+    fn new() -> LightCallGraph {
+        LightCallGraph {
+            graph: Graph::new(),
+            nodes: HashMap::new(),
+            #[cfg(feature = "per-thread")]
+            thread: None,
+        }
+    }
+
+    pub fn clear(&mut self) {
+        self.graph.clear();
+        self.nodes.clear();
+    }
+
+    /// Reset all the data recorded for nodes and edges, while keeping the
+    /// nodes and edges themselves
+    pub fn reset_timings(&mut self) {
+        for node in self.graph.node_weights_mut() {
+            *node = LightGraphNode::new(node.callsite);
+        }
+        for edge in self.graph.edge_weights_mut() {
+            *edge = LightCallEdge::default();
+        }
+    }
+
+    /// Add all the data from `other` to this graph
+    pub fn merge(&mut self, other: &LightCallGraph) {
+        merge_graphs(
+            &mut self.graph,
+            &other.graph,
+            |node| node.callsite,
+            |node, other_node| {
+                *node.elapsed.get_mut() += other_node.elapsed.load(Ordering::Relaxed);
+                *node.called.get_mut() += other_node.called();
+                let sampling_rate = node.sampling_rate.get_mut();
+                *sampling_rate = (*sampling_rate).max(other_node.sampling_rate.load(Ordering::Relaxed));
+                #[cfg(feature = "per-thread")]
+                node.merge_per_thread(other_node, other.thread);
+
+                let stats = node.stats.get_mut().unwrap_or_else(PoisonError::into_inner);
+                let other_stats = other_node.stats();
+                stats.min = stats.min.min(other_stats.min);
+                stats.max = stats.max.max(other_stats.max);
+                stats.variance.merge(&other_stats.variance);
+                stats.first_seen = match (stats.first_seen, other_stats.first_seen) {
+                    (Some(first), Some(other)) => Some(first.min(other)),
+                    (first, other) => first.or(other),
+                };
+                merge_metrics(&mut stats.metrics, &other_stats.metrics);
+                #[cfg(feature = "histogram")]
+                stats.histogram.add(&other_stats.histogram).expect("failed to merge histograms");
+                #[cfg(feature = "alloc-tracking")]
+                {
+                    stats.allocations += other_stats.allocations;
+                    stats.bytes_allocated += other_stats.bytes_allocated;
+                }
+            },
+            |other_node, _| {
+                #[cfg(feature = "per-thread")]
+                if let Some(thread) = other.thread {
+                    let mut node = other_node.clone();
+                    node.stats.get_mut().unwrap_or_else(PoisonError::into_inner).per_thread.clear();
+                    node.merge_per_thread(other_node, Some(thread));
+                    return node;
+                }
+                return other_node.clone();
+            },
+        );
+
+        self.nodes = self.graph.node_indices()
+            .map(|index| (self.graph[index].callsite, index))
+            .collect();
+    }
+
+    /// Find a node in the graph with its `CallSiteId`.
+    fn find(&self, callsite: CallSiteId) -> Option<NodeIndex> {
+        self.nodes.get(&callsite).copied()
+    }
+
+    /// Find the node for the given callsite in the graph, adding it if needed.
+    /// The node can be missing if the span is running while its data is
+    /// removed with [`LightCallGraph::remove`].
+    fn find_or_add(&mut self, callsite: CallSiteId) -> NodeIndex {
+        if let Some(index) = self.find(callsite) {
+            return index;
+        }
+
+        let index = self.graph.add_node(LightGraphNode::new(callsite));
+        self.nodes.insert(callsite, index);
+        return index;
+    }
+
+    /// Remove the node for the given callsite and all the corresponding edges
+    /// from the graph, if it exists
+    pub fn remove(&mut self, callsite: CallSiteId) {
+        if let Some(index) = self.nodes.remove(&callsite) {
+            let _ = self.graph.remove_node(index);
+            // the last node in the graph is moved to the removed index
+            if let Some(moved) = self.graph.node_weight(index) {
+                self.nodes.insert(moved.callsite, index);
+            }
+        }
+    }
+
+    /// Add the nodes for `callsite` and `parent` (if any) and the edge
+    /// between them to the graph if needed, making sure a later call to
+    /// [`LightCallGraph::record`] with the same arguments succeeds.
+    pub fn prepare(&mut self, callsite: CallSiteId, parent: Option<CallSiteId>) {
+        let child = self.find_or_add(callsite);
+        if let Some(parent) = parent {
+            let parent = self.find_or_add(parent);
+            if self.graph.find_edge(parent, child).is_none() {
+                self.graph.add_edge(parent, child, LightCallEdge::default());
+            }
+        }
+    }
+
+    /// Record a single execution of the `callsite` span, called from
+    /// `parent`. The call counts as `sampling_rate` calls, each taking the
+    /// measured time (if any).
+    ///
+    /// This only needs shared access to the graph, and returns `false`
+    /// without recording anything if the nodes or the edge between them do
+    /// not exist yet, in which case [`LightCallGraph::prepare`] should be
+    /// called first.
+    pub fn record(
+        &self,
+        callsite: CallSiteId,
+        parent: Option<CallSiteId>,
+        measurement: Option<&Measurement>,
+        sampling_rate: u32,
+    ) -> bool {
+        let child = match self.find(callsite) {
+            Some(child) => child,
+            None => return false,
+        };
+
+        let edge = match parent {
+            Some(parent) => {
+                let edge = self.find(parent).and_then(|parent| self.graph.find_edge(parent, child));
+                match edge {
+                    Some(edge) => Some(&self.graph[edge]),
+                    None => return false,
+                }
+            }
+            None => None,
+        };
+
+        let time = measurement.map(|measurement| measurement.elapsed);
+        let node = &self.graph[child];
+        let before = node.called.fetch_add(sampling_rate, Ordering::Relaxed);
+        node.sampling_rate.fetch_max(sampling_rate, Ordering::Relaxed);
+
+        if let Some(edge) = edge {
+            edge.count.fetch_add(sampling_rate as usize, Ordering::Relaxed);
+            if let Some(time) = time {
+                edge.elapsed.fetch_add(duration_nanos(time * sampling_rate), Ordering::Relaxed);
+            }
+        }
+
+        match measurement {
+            None => {
+                if before == 0 {
+                    // recursive calls finish before the outermost call, which
+                    // will replace this value
+                    let mut stats = node.stats();
+                    if stats.first_seen.is_none() {
+                        stats.first_seen = Some(SystemTime::now());
+                    }
+                }
+            }
+            Some(measurement) => {
+                let time = measurement.elapsed;
+                node.elapsed.fetch_add(duration_nanos(time * sampling_rate), Ordering::Relaxed);
+
+                let mut stats = node.stats();
+                if stats.variance.count == 0 {
+                    let now = SystemTime::now();
+                    stats.first_seen = Some(now.checked_sub(time).unwrap_or(now));
+                }
+
+                stats.min = stats.min.min(time);
+                stats.max = stats.max.max(time);
+                stats.variance.add(time);
+
+                #[cfg(feature = "histogram")]
+                {
+                    // recording can only fail if the histogram can not be
+                    // resized, in which case we drop this value
+                    let _ = stats.histogram.record_n(time.as_nanos() as u64, sampling_rate as u64);
+                }
+
+                #[cfg(feature = "alloc-tracking")]
+                {
+                    let rate = sampling_rate as u64;
+                    stats.allocations += measurement.allocations.0 * rate;
+                    stats.bytes_allocated += measurement.allocations.1 * rate;
+                }
+            }
+        }
+
+        return true;
+    }
+
+    /// Add `value` to the `key` metric of a span. This returns `false` without
+    /// doing anything if there is no node for this span in the graph.
+    pub fn increase_metric(&self, span: CallSiteId, key: &'static str, value: u64) -> bool {
+        let id = match self.find(span) {
+            Some(id) => id,
+            None => return false,
+        };
+        let mut stats = self.graph[id].stats();
+        let metric = stats.metrics.entry(key).or_insert(0);
+        *metric = metric.saturating_add(value);
+        return true;
+    }
+}
+
+/// Sum of the windows of time during which data collection was enabled
+struct CollectionWindow {
+    /// Time at which data collection was last enabled, as returned by
+    /// `clock::now`, if it is currently enabled
+    active_since: Option<u64>,
+    /// Total duration of the previous collection windows
+    previous: Duration,
+}
+
+impl CollectionWindow {
+    /// Start or end a collection window after the number of requests to
+    /// collect data changed from `before` to `after`
+    fn update(&mut self, before: usize, after: usize) {
+        if before == 0 && after != 0 {
+            self.active_since = Some(crate::clock::now());
+        } else if before != 0 && after == 0 {
+            if let Some(start) = self.active_since.take() {
+                self.previous += crate::clock::elapsed(start, crate::clock::now());
+            }
+        }
+    }
+
+    /// Total duration of all collection windows, including the current one
+    fn duration(&self) -> Duration {
+        let current = self.active_since.map_or(Duration::ZERO, |start| {
+            crate::clock::elapsed(start, crate::clock::now())
+        });
+        return self.previous + current;
+    }
+
+    /// Forget about all previous collection windows, starting a new one now
+    /// if data collection is enabled
+    fn reset(&mut self) {
+        self.previous = Duration::ZERO;
+        if self.active_since.is_some() {
+            self.active_since = Some(crate::clock::now());
+        }
+    }
+}
+
+/// Clear the global call graph from all data
+pub fn clear_collected_data() {
+    COLLECTION_WINDOW.lock().unwrap_or_else(PoisonError::into_inner).reset();
+    let all_graphs = THREAD_CALL_GRAPHS.lock().unwrap_or_else(PoisonError::into_inner);
+    FINISHED_THREADS_CALL_GRAPH.lock().unwrap_or_else(PoisonError::into_inner).clear();
+    for graph in all_graphs.iter() {
+        graph.write().unwrap_or_else(PoisonError::into_inner).clear();
+    }
+}
+
+/// Reset all the data recorded in the global call graph (elapsed time, number
+/// of calls, …) to zero, while keeping the spans and calls between them. This
+/// can be used to start a new measurement interval, getting graphs with the
+/// same structure and span ids from [`get_full_graph`].
+///
+/// As with [`clear_collected_data`], this also resets the collection window.
+pub fn reset_timings() {
+    COLLECTION_WINDOW.lock().unwrap_or_else(PoisonError::into_inner).reset();
+    let all_graphs = THREAD_CALL_GRAPHS.lock().unwrap_or_else(PoisonError::into_inner);
+    FINISHED_THREADS_CALL_GRAPH.lock().unwrap_or_else(PoisonError::into_inner).reset_timings();
+    for graph in all_graphs.iter() {
+        graph.write().unwrap_or_else(PoisonError::into_inner).reset_timings();
+    }
+}
+
+/// Remove all the data associated with a single call site from the global call
+/// graph, including the calls from and to this call site. The data for all
+/// other call sites is kept.
+///
+/// # Examples
+/// ```
+/// time_graph::enable_data_collection(true);
+/// time_graph::spanned!("hot path", {
+///     // ...
+/// });
+///
+/// let graph = time_graph::get_full_graph();
+/// let span = graph.span_by_name("hot path").unwrap();
+/// time_graph::clear_callsite(span.callsite.id());
+///
+/// assert!(time_graph::get_full_graph().span_by_name("hot path").is_none());
+/// ```
+pub fn clear_callsite(id: CallSiteId) {
+    let all_graphs = THREAD_CALL_GRAPHS.lock().unwrap_or_else(PoisonError::into_inner);
+    FINISHED_THREADS_CALL_GRAPH.lock().unwrap_or_else(PoisonError::into_inner).remove(id);
+    for graph in all_graphs.iter() {
+        graph.write().unwrap_or_else(PoisonError::into_inner).remove(id);
+    }
+}
+
+/// Remove all the data associated with the call sites with the given `name`
+/// from the global call graph, as with [`clear_callsite`]. Returns the number
+/// of call sites with this name.
+pub fn clear_callsite_by_name(name: &str) -> usize {
+    let mut ids = Vec::new();
+    crate::traverse_registered_callsite(|callsite| {
+        if callsite.name() == name {
+            ids.push(callsite.id());
+        }
+    });
+
+    for &id in &ids {
+        clear_callsite(id);
+    }
+
+    return ids.len();
+}
+
+/// Enable/disable data collection.
+///
+/// Disabling data collection always stops collecting data, even if it was
+/// enabled multiple times with [`push_collection`] or [`collect`]. Enabling
+/// data collection makes sure data is collected until the next call to
+/// `enable_data_collection(false)`, or until all calls to [`push_collection`]
+/// have been matched with a call to [`pop_collection`].
+pub fn enable_data_collection(enabled: bool) {
+    let mut window = COLLECTION_WINDOW.lock().unwrap_or_else(PoisonError::into_inner);
+    if enabled {
+        let before = COLLECTION_ENABLED.fetch_max(1, Ordering::AcqRel);
+        window.update(effective_requests(before), effective_requests(before.max(1)));
+    } else {
+        let before = COLLECTION_ENABLED.swap(0, Ordering::AcqRel);
+        window.update(effective_requests(before), 0);
+    }
+}
+
+/// Check whether data collection is currently enabled, either with
+/// [`enable_data_collection`] or [`push_collection`].
+///
+/// # Examples
+/// ```
+/// let was_enabled = time_graph::is_data_collection_enabled();
+/// time_graph::enable_data_collection(true);
+/// // ...
+/// time_graph::enable_data_collection(was_enabled);
+/// ```
+pub fn is_data_collection_enabled() -> bool {
+    return collection_enabled();
+}
+
+/// Request data collection to be enabled until the corresponding call to
+/// [`pop_collection`].
+///
+/// Calls to `push_collection` and `pop_collection` can be nested, and data
+/// will be collected until every call to `push_collection` is matched by a
+/// call to `pop_collection`.
+///
+/// # Examples
+/// ```
+/// time_graph::push_collection();
+/// time_graph::push_collection();
+/// time_graph::pop_collection();
+/// // data is still collected here
+/// time_graph::pop_collection();
+/// // data collection is disabled
+/// ```
+pub fn push_collection() {
+    let mut window = COLLECTION_WINDOW.lock().unwrap_or_else(PoisonError::into_inner);
+    let before = COLLECTION_ENABLED.fetch_add(1, Ordering::AcqRel);
+    window.update(effective_requests(before), effective_requests(before + 1));
+}
+
+/// Remove a request to collect data made with [`push_collection`]. Data
+/// collection is disabled when all requests have been removed.
+pub fn pop_collection() {
+    let mut window = COLLECTION_WINDOW.lock().unwrap_or_else(PoisonError::into_inner);
+    let result = COLLECTION_ENABLED.fetch_update(Ordering::AcqRel, Ordering::Acquire, |count| {
+        Some(count.saturating_sub(1))
+    });
+    let before = result.unwrap_or_else(|count| count);
+    window.update(effective_requests(before), effective_requests(before.saturating_sub(1)));
+}
+
+/// Enable data collection until the returned [`CollectionGuard`] is dropped.
+///
+/// This is equivalent to calling [`push_collection`] now, and
+/// [`pop_collection`] when the guard is dropped, allowing to nest multiple
+/// calls. All data is still collected in the global call graph.
+///
+/// # Examples
+/// ```
+/// let guard = time_graph::collect();
+/// // data is collected here
+/// std::mem::drop(guard);
+/// // data collection is back to its previous state
+/// ```
+pub fn collect() -> CollectionGuard {
+    push_collection();
+    CollectionGuard {
+        _private: (),
+    }
+}
+
+/// When a [`CollectionGuard`] is dropped, the request to collect data made by
+/// [`collect`] is removed.
+#[must_use]
+pub struct CollectionGuard {
+    _private: (),
+}
+
+impl Drop for CollectionGuard {
+    fn drop(&mut self) {
+        pop_collection();
+    }
+}
+
+/// Number of [`CollectionState`] scopes currently alive, used to detect
+/// scopes dropped in the wrong order
+static COLLECTION_SCOPES: AtomicUsize = AtomicUsize::new(0);
+
+/// RAII scope setting data collection to a given state, and undoing this
+/// change when dropped. Other requests to collect data made while the scope is
+/// alive (with [`push_collection`], [`collect`], ...) are kept.
+///
+/// This is useful in tests and benchmarks, to make sure collection is not
+/// left enabled by accident for the code running afterwards. Scopes can be
+/// nested, but should be dropped in the reverse order of their creation. In
+/// debug builds, a warning is printed if this is not the case.
+///
+/// # Examples
+/// ```
+/// # use time_graph::CollectionState;
+/// assert!(!time_graph::is_data_collection_enabled());
+/// {
+///     let _scope = CollectionState::scoped(true);
+///     assert!(time_graph::is_data_collection_enabled());
+/// }
+/// assert!(!time_graph::is_data_collection_enabled());
+/// ```
+#[must_use]
+pub struct CollectionState {
+    /// Was data collection enabled by this scope?
+    enabled: bool,
+    /// For scopes enabling data collection, number of scopes disabling data
+    /// collection which were suspended by this one
+    suspended: usize,
+    /// Number of scopes alive before this scope was created
+    depth: usize,
+}
+
+impl CollectionState {
+    /// Enable or disable data collection until the returned value is dropped.
+    ///
+    /// Enabling data collection is equivalent to calling [`push_collection`]
+    /// now and [`pop_collection`] when the scope is dropped, and also
+    /// suspends any outer scope disabling data collection. Disabling data
+    /// collection stops collecting data until the scope is dropped, without
+    /// changing requests made with [`push_collection`], [`collect`] or
+    /// [`enable_data_collection`], which are applied again after the scope.
+    pub fn scoped(enabled: bool) -> CollectionState {
+        let mut window = COLLECTION_WINDOW.lock().unwrap_or_else(PoisonError::into_inner);
+        let count = COLLECTION_ENABLED.load(Ordering::Acquire);
+        let before = effective_requests(count);
+        let mut suspended = 0;
+        if enabled {
+            suspended = COLLECTION_FORCED_OFF.swap(0, Ordering::AcqRel);
+            COLLECTION_ENABLED.fetch_add(1, Ordering::AcqRel);
+            window.update(before, count + 1);
+        } else {
+            COLLECTION_FORCED_OFF.fetch_add(1, Ordering::AcqRel);
+            window.update(before, 0);
+        }
+
+        let depth = COLLECTION_SCOPES.fetch_add(1, Ordering::AcqRel);
+        return CollectionState {
+            enabled: enabled,
+            suspended: suspended,
+            depth: depth,
+        };
+    }
+}
+
+impl Drop for CollectionState {
+    fn drop(&mut self) {
+        let mut window = COLLECTION_WINDOW.lock().unwrap_or_else(PoisonError::into_inner);
+        let before = effective_requests(COLLECTION_ENABLED.load(Ordering::Acquire));
+        // the window is locked, so there can not be concurrent updates here
+        if self.enabled {
+            let count = COLLECTION_ENABLED.load(Ordering::Acquire);
+            COLLECTION_ENABLED.store(count.saturating_sub(1), Ordering::Release);
+            COLLECTION_FORCED_OFF.fetch_add(self.suspended, Ordering::AcqRel);
+        } else {
+            let forced = COLLECTION_FORCED_OFF.load(Ordering::Acquire);
+            COLLECTION_FORCED_OFF.store(forced.saturating_sub(1), Ordering::Release);
+        }
+        window.update(before, effective_requests(COLLECTION_ENABLED.load(Ordering::Acquire)));
+
+        let depth = COLLECTION_SCOPES.fetch_sub(1, Ordering::AcqRel);
+        if cfg!(debug_assertions) && depth != self.depth + 1 {
+            eprintln!(
+                "time-graph: unbalanced CollectionState scopes, a scope created \
+                at depth {} was dropped at depth {}", self.depth, depth - 1
+            );
+        }
+    }
+}
+
+/// Get a copy of the call graph as currently known. The spans in this graph
+/// are sorted by source location, see [`TimedSpan::id`].
+pub fn get_full_graph() -> FullCallGraph {
+    let collection_duration = COLLECTION_WINDOW.lock().unwrap_or_else(PoisonError::into_inner).duration();
+    let all_graphs = THREAD_CALL_GRAPHS.lock().unwrap_or_else(PoisonError::into_inner);
+    let mut graph = LightCallGraph::new();
+    graph.merge(&FINISHED_THREADS_CALL_GRAPH.lock().unwrap_or_else(PoisonError::into_inner));
+    for thread_graph in all_graphs.iter() {
+        graph.merge(&thread_graph.read().unwrap_or_else(PoisonError::into_inner));
+    }
+    std::mem::drop(all_graphs);
+
+    let mut all_callsites = BTreeMap::new();
+    crate::traverse_registered_callsite(|callsite| {
+        all_callsites.insert(callsite.id(), callsite);
+    });
+
+    // the order in which spans are first recorded depends on the execution
+    // order of threads, so we sort them by source location to give them ids
+    // which are the same across runs. Spans whose call site was removed by
+    // `reset_registry` and not registered again since are skipped.
+    let mut order = graph.graph.node_indices()
+        .filter_map(|node| all_callsites.get(&graph.graph[node].callsite).map(|&callsite| (node, callsite)))
+        .collect::<Vec<_>>();
+    order.sort_by_key(|&(_, callsite)| {
+        (callsite.file(), callsite.line(), callsite.module_path(), callsite.name(), callsite.id())
+    });
+
+    let mut sorted = Graph::with_capacity(order.len(), graph.graph.edge_count());
+    let mut new_indexes = vec![NodeIndex::end(); graph.graph.node_count()];
+    for (id, &(node, callsite)) in order.iter().enumerate() {
+        let span = TimedSpan::new(&graph.graph[node], id, callsite, collection_duration);
+        new_indexes[node.index()] = sorted.add_node(span);
+    }
+
+    let mut edges = graph.graph.raw_edges().iter()
+        .map(|edge| (new_indexes[edge.source().index()], new_indexes[edge.target().index()], edge.weight.load()))
+        .filter(|&(source, target, _)| source != NodeIndex::end() && target != NodeIndex::end())
+        .collect::<Vec<_>>();
+    edges.sort_by_key(|&(source, target, _)| (source, target));
+    for (source, target, weight) in edges {
+        sorted.add_edge(source, target, weight);
+    }
+
+    let mut graph = FullCallGraph {
+        graph: sorted,
+        collection_duration: collection_duration,
+        name_overrides: HashMap::new(),
+    };
+    graph.update_self_time();
+
+    return graph;
+}
+
+/// Get the total elapsed time and number of calls of every span/function
+/// recorded so far, identified by the id of their call site.
+///
+/// This is much cheaper than [`get_full_graph`], since it only copies these
+/// numbers without building the call graph or looking up call sites, and can
+/// be called frequently to monitor a running program. The spans are sorted by
+/// call site id.
+pub fn snapshot_totals() -> Vec<(CallSiteId, Duration, u32)> {
+    let mut totals = BTreeMap::<CallSiteId, (Duration, u32)>::new();
+    let mut add_graph = |graph: &LightCallGraph| {
+        for node in graph.graph.raw_nodes() {
+            let total = totals.entry(node.weight.callsite).or_default();
+            total.0 += node.weight.elapsed();
+            total.1 += node.weight.called();
+        }
+    };
+
+    let all_graphs = THREAD_CALL_GRAPHS.lock().unwrap_or_else(PoisonError::into_inner);
+    add_graph(&FINISHED_THREADS_CALL_GRAPH.lock().unwrap_or_else(PoisonError::into_inner));
+    for thread_graph in all_graphs.iter() {
+        add_graph(&thread_graph.read().unwrap_or_else(PoisonError::into_inner));
+    }
+    std::mem::drop(all_graphs);
+
+    return totals.into_iter()
+        .map(|(callsite, (elapsed, called))| (callsite, elapsed, called))
+        .collect();
+}
+
+/// [`TimedSpan`] contains all data related to a single function or span inside
 /// the global call graph.
 pub struct TimedSpan {
-    /// Unique identifier of this function/span in the call graph
+    /// Unique identifier of this function/span in the call graph. In graphs
+    /// returned by [`get_full_graph`], ids are attributed by sorting the spans
+    /// by source location (file, line, module and name), so the same code
+    /// recording the same spans gets the same ids across runs.
     pub id: usize,
     /// [`CallSite`] associated with this function/span
     pub callsite: &'static CallSite,
-    /// Total elapsed time inside this function/span
+    /// Total elapsed time inside this function/span.
+    ///
+    /// For recursive functions/spans, only the time of the outermost call is
+    /// recorded, since it already includes the time of the recursive calls.
     pub elapsed: Duration,
-    /// Number of times this function/span have been called
+    /// Number of times this function/span have been called, including
+    /// recursive calls
     pub called: u32,
+    /// Shortest elapsed time for a single call to this function/span,
+    /// excluding recursive calls
+    pub min: Duration,
+    /// Longest elapsed time for a single call to this function/span,
+    /// excluding recursive calls
+    pub max: Duration,
+    /// Elapsed time inside this function/span, excluding the time spent in
+    /// the functions/spans it called.
+    ///
+    /// Since only the total time of each span is recorded, the time spent in
+    /// a child span is attributed to its parents proportionally to the number
+    /// of calls from each parent. For recursive spans, this is only an
+    /// approximation.
+    pub self_time: Duration,
+    /// Wall-clock time at which the first recorded call to this function/span
+    /// started, computed from the end of the call and its elapsed time. This
+    /// can be used to correlate the recorded data with timestamped logs.
+    pub first_seen: Option<SystemTime>,
+    /// Sampling rate used to record this function/span, see [`set_sampling`].
+    /// This is 1 if all executions were recorded, and the `elapsed` time and
+    /// `called` count are already scaled up by this value otherwise.
+    pub sampling_rate: u32,
+    /// Variance of the elapsed time of single calls
+    variance: RunningVariance,
+    /// Duration of the data collection window of the graph containing this
+    /// span
+    window: Duration,
+    /// Sum of the custom metrics recorded inside this span
+    metrics: BTreeMap<&'static str, u64>,
+    /// Distribution of the elapsed time of single calls, in nanoseconds
+    #[cfg(feature = "histogram")]
+    histogram: hdrhistogram::Histogram<u64>,
+    /// Elapsed time and number of calls for each thread
+    #[cfg(feature = "per-thread")]
+    per_thread: HashMap<std::thread::ThreadId, (Duration, u32)>,
+    /// Total number of memory allocations inside this function/span,
+    /// including in the functions/spans it called. This is only recorded if
+    /// [`crate::CountingAllocator`] is used as the global allocator.
+    ///
+    /// This field is only available if the `"alloc-tracking"` cargo feature
+    /// is enabled
+    #[cfg(feature = "alloc-tracking")]
+    pub allocations: u64,
+    /// Total number of bytes allocated inside this function/span, including
+    /// in the functions/spans it called. This is only recorded if
+    /// [`crate::CountingAllocator`] is used as the global allocator.
+    ///
+    /// This field is only available if the `"alloc-tracking"` cargo feature
+    /// is enabled
+    #[cfg(feature = "alloc-tracking")]
+    pub bytes_allocated: u64,
 }
 
 impl TimedSpan {
-    fn new(node: &LightGraphNode, id: usize, callsite: &'static CallSite) -> TimedSpan {
+    fn new(node: &LightGraphNode, id: usize, callsite: &'static CallSite, window: Duration) -> TimedSpan {
+        let stats = node.stats();
         TimedSpan {
             id: id,
             callsite: callsite,
-            elapsed: node.elapsed,
-            called: node.called,
+            elapsed: node.elapsed(),
+            called: node.called(),
+            // no timing was recorded yet
+            min: if stats.min == Duration::MAX { Duration::new(0, 0) } else { stats.min },
+            max: stats.max,
+            self_time: node.elapsed(),
+            first_seen: stats.first_seen,
+            sampling_rate: node.sampling_rate.load(Ordering::Relaxed),
+            variance: stats.variance,
+            window: window,
+            metrics: stats.metrics.clone(),
+            #[cfg(feature = "histogram")]
+            histogram: stats.histogram.clone(),
+            #[cfg(feature = "per-thread")]
+            per_thread: stats.per_thread.clone(),
+            #[cfg(feature = "alloc-tracking")]
+            allocations: stats.allocations,
+            #[cfg(feature = "alloc-tracking")]
+            bytes_allocated: stats.bytes_allocated,
+        }
+    }
+
+    /// Get a copy of this span with a different `id`
+    fn with_id(&self, id: usize) -> TimedSpan {
+        TimedSpan {
+            id: id,
+            callsite: self.callsite,
+            elapsed: self.elapsed,
+            called: self.called,
+            min: self.min,
+            max: self.max,
+            self_time: self.self_time,
+            first_seen: self.first_seen,
+            sampling_rate: self.sampling_rate,
+            variance: self.variance,
+            window: self.window,
+            metrics: self.metrics.clone(),
+            #[cfg(feature = "histogram")]
+            histogram: self.histogram.clone(),
+            #[cfg(feature = "per-thread")]
+            per_thread: self.per_thread.clone(),
+            #[cfg(feature = "alloc-tracking")]
+            allocations: self.allocations,
+            #[cfg(feature = "alloc-tracking")]
+            bytes_allocated: self.bytes_allocated,
+        }
+    }
+
+    /// Get the total elapsed time inside this function/span, minus an
+    /// estimate of the time spent by this crate recording the calls to this
+    /// function/span. The result is clamped at zero.
+    ///
+    /// The overhead per call is estimated once per process, and can vary
+    /// between runs and with the state of the system. This should only be
+    /// used as an approximation for very short spans.
+    pub fn elapsed_adjusted(&self) -> Duration {
+        self.elapsed.saturating_sub(*PER_CALL_OVERHEAD * self.called)
+    }
+
+    /// Get the standard deviation of the elapsed time of single calls to this
+    /// function/span, excluding recursive calls. This returns zero if the
+    /// function/span was called less than two times.
+    pub fn stddev(&self) -> Duration {
+        self.variance.stddev()
+    }
+
+    /// Get the sum of all the values recorded for the `key` custom metric in
+    /// this function/span, or `None` if no value was recorded for this key.
+    /// See [`SpanGuard::record_metric`].
+    pub fn metric(&self, key: &str) -> Option<u64> {
+        self.metrics.get(key).copied()
+    }
+
+    /// Was this function/span recorded with sampling, i.e. are the recorded
+    /// data extrapolated from only some of the calls? See
+    /// [`TimedSpan::sampling_rate`].
+    pub fn is_sampled(&self) -> bool {
+        self.sampling_rate > 1
+    }
+
+    /// Get the fraction of the data collection window (see
+    /// [`FullCallGraph::collection_duration`]) spent inside this
+    /// function/span. Spans running on multiple threads at the same time can
+    /// use more than the full window, giving values larger than 1. This
+    /// returns 0 if the window is empty.
+    pub fn fraction_of_window(&self) -> f64 {
+        if self.window.is_zero() {
+            return 0.0;
         }
+        return self.elapsed.as_secs_f64() / self.window.as_secs_f64();
+    }
+
+    /// Get the total elapsed time and number of calls to this function/span
+    /// for each of the threads which called it.
+    ///
+    /// This function is only available if the `"per-thread"` cargo feature is
+    /// enabled
+    #[cfg(feature = "per-thread")]
+    pub fn per_thread(&self) -> &HashMap<std::thread::ThreadId, (Duration, u32)> {
+        &self.per_thread
+    }
+
+    /// Get the elapsed time of a single call to this function/span at the
+    /// given `percentile`, which should be between 0 and 100. For example,
+    /// `span.percentile(99.0)` gives the time under which 99% of the calls
+    /// completed.
+    ///
+    /// The returned value has a relative precision of 0.1%.
+    ///
+    /// This function is only available if the `"histogram"` cargo feature is
+    /// enabled
+    #[cfg(feature = "histogram")]
+    pub fn percentile(&self, percentile: f64) -> Duration {
+        Duration::from_nanos(self.histogram.value_at_percentile(percentile))
     }
 }
 
@@ -285,7 +2092,87 @@ impl std::fmt::Display for TimedSpan {
 ///                  | inner, called 3 |
 /// ```
 pub struct FullCallGraph {
-    graph: Graph<TimedSpan, usize>
+    graph: Graph<TimedSpan, CallEdge>,
+    collection_duration: Duration,
+    /// Names used instead of the call site names when displaying spans, set
+    /// with [`FullCallGraph::with_name_overrides`]
+    name_overrides: HashMap<CallSiteId, String>,
+}
+
+/// Headline numbers about a [`FullCallGraph`], created by
+/// [`FullCallGraph::summary`].
+#[derive(Debug, Clone)]
+pub struct GraphSummary {
+    /// Number of spans/functions in the graph
+    pub total_spans: usize,
+    /// Total number of calls to all spans/functions
+    pub total_calls: u64,
+    /// Sum of the elapsed time of all spans/functions. Since the elapsed time
+    /// of a span includes the time spent in the spans it calls, nested spans
+    /// are counted multiple times.
+    pub total_elapsed: Duration,
+    /// The span/function with the largest elapsed time, if the graph is not
+    /// empty
+    pub slowest_span: Option<SpanSummary>,
+    /// Number of spans in the longest chain of calls in the graph. Recursive
+    /// calls are only counted once.
+    pub deepest_depth: usize,
+}
+
+/// Identification and elapsed time of a single span, used in [`GraphSummary`]
+#[derive(Debug, Clone)]
+pub struct SpanSummary {
+    /// Identifier of the span/function in the graph
+    pub id: usize,
+    /// Full name of the span/function
+    pub name: String,
+    /// Total elapsed time inside this span/function
+    pub elapsed: Duration,
+}
+
+/// Difference between the same span/function in two graphs, created by
+/// [`FullCallGraph::diff`].
+#[derive(Clone)]
+pub struct SpanDiff {
+    /// [`CallSite`] associated with this function/span
+    pub callsite: &'static CallSite,
+    /// Is this span present in both graphs, or only in one of them?
+    pub kind: SpanDiffKind,
+    /// Change in total elapsed time compared to the baseline, in nanoseconds
+    pub elapsed_delta: i128,
+    /// Change in number of calls compared to the baseline
+    pub called_delta: i64,
+    /// Relative change in total elapsed time compared to the baseline, in
+    /// percent. This is `None` if the span did not run in the baseline.
+    pub percent_change: Option<f64>,
+}
+
+/// Error returned by [`FullCallGraph::spans_topological`] when the graph
+/// contains a cycle
+#[derive(Debug, Clone)]
+pub struct CyclicGraphError {
+    /// Identifier of one of the spans/functions in the cycle
+    pub span: usize,
+}
+
+impl std::fmt::Display for CyclicGraphError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "the call graph contains a cycle going through span {}", self.span)
+    }
+}
+
+impl std::error::Error for CyclicGraphError {}
+
+/// Presence of a span/function in the graphs compared by
+/// [`FullCallGraph::diff`]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SpanDiffKind {
+    /// The span is present in both graphs
+    Changed,
+    /// The span is only present in the new graph
+    Added,
+    /// The span is only present in the baseline graph
+    Removed,
 }
 
 /// A set of calls from one function/span to another
@@ -296,28 +2183,763 @@ pub struct Calls {
     pub callee: usize,
     /// number of time the inner function/span have been called by the outer one
     pub count: usize,
+    /// total time spent in the inner function/span when called by the outer
+    /// one, excluding recursive calls
+    pub elapsed: Duration,
 }
 
-impl FullCallGraph {
-    /// Get the full list of spans/functions known by this graph
-    pub fn spans(&self) -> impl Iterator<Item = &TimedSpan> {
-        self.graph.raw_nodes().iter().map(|node| &node.weight)
-    }
+impl FullCallGraph {
+    /// Compute the `self_time` of all spans in this graph from their total
+    /// elapsed time and the elapsed time of their children.
+    fn update_self_time(&mut self) {
+        use petgraph::Direction;
+
+        for node in self.graph.node_indices() {
+            let mut children = Duration::new(0, 0);
+            for edge in self.graph.edges_directed(node, Direction::Outgoing) {
+                if edge.target() == node {
+                    // the time of recursive calls is not part of the elapsed
+                    // time of the span
+                    continue;
+                }
+                let child = &self.graph[edge.target()];
+                if child.called != 0 {
+                    let fraction = edge.weight().count as f64 / child.called as f64;
+                    children += child.elapsed.mul_f64(fraction.min(1.0));
+                }
+            }
+
+            let span = &mut self.graph[node];
+            span.self_time = span.elapsed.saturating_sub(children);
+        }
+    }
+
+    /// Get the wall-clock time during which data collection was enabled,
+    /// since the last call to [`clear_collected_data`]. If data collection was
+    /// enabled and disabled multiple times, this is the sum of all the
+    /// windows where it was enabled.
+    ///
+    /// This can be used as the denominator when reporting the fraction of
+    /// time spent in a given span.
+    pub fn collection_duration(&self) -> Duration {
+        self.collection_duration
+    }
+
+    /// Get the sum of the self time of all the spans/functions in this graph.
+    /// Contrary to the sum of their elapsed time, the time spent in nested
+    /// spans is only counted once, giving the total time spent in all the
+    /// recorded code.
+    ///
+    /// For spans in a cycle of calls (e.g. mutually recursive functions), the
+    /// time attributed to children can not be larger than the span's own
+    /// elapsed time, so this never counts more time than actually recorded,
+    /// but can count less.
+    pub fn total_self_time(&self) -> Duration {
+        self.spans().map(|span| span.self_time).sum()
+    }
+
+    /// Get the `n` spans/functions with the largest total elapsed time, from
+    /// the slowest to the fastest. Spans with the same elapsed time are
+    /// sorted by increasing id.
+    pub fn hottest(&self, n: usize) -> Vec<&TimedSpan> {
+        let mut spans = self.spans_sorted();
+        spans.sort_by_key(|span| std::cmp::Reverse(span.elapsed));
+        spans.truncate(n);
+        return spans;
+    }
+
+    /// Get the `n` spans/functions with the largest mean elapsed time per
+    /// call, from the slowest to the fastest. Spans which did not finish
+    /// running yet have a mean of zero, and spans with the same mean are
+    /// sorted by increasing id.
+    pub fn hottest_by_mean(&self, n: usize) -> Vec<&TimedSpan> {
+        let mean = |span: &TimedSpan| span.elapsed.checked_div(span.called).unwrap_or_default();
+
+        let mut spans = self.spans_sorted();
+        spans.sort_by_key(|span| std::cmp::Reverse(mean(span)));
+        spans.truncate(n);
+        return spans;
+    }
+
+    /// Get a summary of the data in this graph
+    pub fn summary(&self) -> GraphSummary {
+        let slowest_span = self.spans().max_by_key(|span| span.elapsed).map(|span| SpanSummary {
+            id: span.id,
+            name: self.display_name(span, NameStyle::Full),
+            elapsed: span.elapsed,
+        });
+
+        return GraphSummary {
+            total_spans: self.graph.node_count(),
+            total_calls: self.spans().map(|span| span.called as u64).sum(),
+            total_elapsed: self.spans().map(|span| span.elapsed).sum(),
+            slowest_span: slowest_span,
+            deepest_depth: self.deepest_depth(),
+        };
+    }
+
+    /// Compare this graph with a `baseline` graph, for example to find spans
+    /// which got slower between two runs. Spans are matched by call site, or
+    /// by full name if the call site is different.
+    ///
+    /// The returned list contains all the spans in this graph, followed by the
+    /// spans which are only present in the baseline.
+    pub fn diff(&self, baseline: &FullCallGraph) -> Vec<SpanDiff> {
+        let find = |graph: &'_ FullCallGraph, callsite: &'static CallSite| {
+            let full_name = callsite.full_name();
+            graph.spans().find(|span| std::ptr::eq(span.callsite, callsite))
+                .or_else(|| graph.spans().find(|span| span.callsite.full_name() == full_name))
+                .map(|span| (span.elapsed, span.called))
+        };
+
+        let mut diff = Vec::new();
+        for span in self.spans() {
+            let (kind, baseline_elapsed, baseline_called) = match find(baseline, span.callsite) {
+                Some((elapsed, called)) => (SpanDiffKind::Changed, elapsed, called),
+                None => (SpanDiffKind::Added, Duration::new(0, 0), 0),
+            };
+
+            diff.push(SpanDiff {
+                callsite: span.callsite,
+                kind: kind,
+                elapsed_delta: span.elapsed.as_nanos() as i128 - baseline_elapsed.as_nanos() as i128,
+                called_delta: i64::from(span.called) - i64::from(baseline_called),
+                percent_change: percent_change(baseline_elapsed, span.elapsed),
+            });
+        }
+
+        for span in baseline.spans() {
+            if find(self, span.callsite).is_none() {
+                diff.push(SpanDiff {
+                    callsite: span.callsite,
+                    kind: SpanDiffKind::Removed,
+                    elapsed_delta: -(span.elapsed.as_nanos() as i128),
+                    called_delta: -i64::from(span.called),
+                    percent_change: percent_change(span.elapsed, Duration::new(0, 0)),
+                });
+            }
+        }
+
+        return diff;
+    }
+
+    /// Check if this graph contains cycles, i.e. if some spans are recursive
+    /// or mutually recursive.
+    ///
+    /// All the output formats support cyclic graphs, but some analysis (such
+    /// as [`FullCallGraph::critical_path`]) are only approximate for cyclic
+    /// graphs.
+    pub fn is_cyclic(&self) -> bool {
+        petgraph::algo::is_cyclic_directed(&self.graph)
+    }
+
+    /// Get the critical path of this graph: the chain of calls going from one
+    /// of the [roots](FullCallGraph::roots) of the graph to a leaf which
+    /// maximizes the sum of the elapsed time of the spans in the chain. The
+    /// spans are returned in caller to callee order.
+    ///
+    /// Recursive calls are ignored. For mutually recursive spans, only calls
+    /// leaving the set of mutually recursive spans are followed, making the
+    /// path a best-effort approximation.
+    pub fn critical_path(&self) -> Vec<&TimedSpan> {
+        use petgraph::Direction;
+
+        // strongly connected components are sorted with callees before callers
+        let components = petgraph::algo::kosaraju_scc(&self.graph);
+        let mut component_of = vec![0; self.graph.node_count()];
+        for (i, component) in components.iter().enumerate() {
+            for node in component {
+                component_of[node.index()] = i;
+            }
+        }
+
+        // longest path starting at each node, and the next node in this path
+        let mut longest = vec![Duration::new(0, 0); self.graph.node_count()];
+        let mut next = vec![None; self.graph.node_count()];
+        for (i, component) in components.iter().enumerate() {
+            for &node in component {
+                let child = self.graph.neighbors_directed(node, Direction::Outgoing)
+                    .filter(|child| component_of[child.index()] != i)
+                    .max_by_key(|child| (longest[child.index()], std::cmp::Reverse(child.index())));
+
+                longest[node.index()] = self.graph[node].elapsed;
+                if let Some(child) = child {
+                    let child_longest = longest[child.index()];
+                    longest[node.index()] += child_longest;
+                    next[node.index()] = Some(child);
+                }
+            }
+        }
+
+        let start = self.roots()
+            .map(|span| NodeIndex::new(span.id))
+            .max_by_key(|node| (longest[node.index()], std::cmp::Reverse(node.index())));
+
+        let mut path = Vec::new();
+        let mut current = start;
+        while let Some(node) = current {
+            path.push(&self.graph[node]);
+            current = next[node.index()];
+        }
+
+        return path;
+    }
+
+    /// Get the number of spans in the longest chain of calls in this graph,
+    /// counting each set of mutually recursive spans only once
+    fn deepest_depth(&self) -> usize {
+        use petgraph::Direction;
+
+        // strongly connected components are sorted with callees before callers
+        let components = petgraph::algo::kosaraju_scc(&self.graph);
+        let mut component_of = vec![0; self.graph.node_count()];
+        for (i, component) in components.iter().enumerate() {
+            for node in component {
+                component_of[node.index()] = i;
+            }
+        }
+
+        let mut depth = vec![0; components.len()];
+        for (i, component) in components.iter().enumerate() {
+            let children = component.iter()
+                .flat_map(|&node| self.graph.neighbors_directed(node, Direction::Outgoing))
+                .filter(|child| component_of[child.index()] != i)
+                .map(|child| depth[component_of[child.index()]])
+                .max()
+                .unwrap_or(0);
+            depth[i] = children + 1;
+        }
+
+        return depth.into_iter().max().unwrap_or(0);
+    }
+
+    /// Get the full list of spans/functions known by this graph
+    pub fn spans(&self) -> impl Iterator<Item = &TimedSpan> {
+        self.graph.raw_nodes().iter().map(|node| &node.weight)
+    }
+
+    /// Get the full list of spans/functions known by this graph, sorted by
+    /// increasing id. Contrary to [`FullCallGraph::spans`], the order is
+    /// guaranteed to stay the same for graphs containing the same data.
+    pub fn spans_sorted(&self) -> Vec<&TimedSpan> {
+        let mut spans = self.spans().collect::<Vec<_>>();
+        spans.sort_by_key(|span| span.id);
+        return spans;
+    }
+
+    /// Get the spans/functions which are not called by any other span, i.e.
+    /// the entry points of this graph. Recursive calls of a span to itself are
+    /// ignored.
+    ///
+    /// If all spans are called by another one (for example with mutually
+    /// recursive functions), this returns the span with the lowest id instead.
+    pub fn roots(&self) -> impl Iterator<Item = &TimedSpan> {
+        use petgraph::Direction;
+
+        let mut roots = self.graph.node_indices()
+            .filter(|&node| {
+                self.graph.neighbors_directed(node, Direction::Incoming).all(|other| other == node)
+            })
+            .map(|node| &self.graph[node])
+            .collect::<Vec<_>>();
+
+        if roots.is_empty() {
+            roots.extend(self.spans().min_by_key(|span| span.id));
+        }
+
+        return roots.into_iter();
+    }
+
+    /// Get all the spans/functions in this graph, ordered such that callers
+    /// always come before the spans they call. Calls from a span to itself
+    /// (i.e. recursive calls) are ignored.
+    ///
+    /// This returns an error if the graph contains a cycle between different
+    /// spans, for example with mutually recursive functions.
+    pub fn spans_topological(&self) -> Result<Vec<&TimedSpan>, CyclicGraphError> {
+        let graph = petgraph::visit::EdgeFiltered::from_fn(&self.graph, |edge| {
+            edge.source() != edge.target()
+        });
+
+        let nodes = petgraph::algo::toposort(&graph, None).map_err(|cycle| CyclicGraphError {
+            span: cycle.node_id().index(),
+        })?;
+
+        return Ok(nodes.into_iter().map(|node| &self.graph[node]).collect());
+    }
+
+    /// Get the spans/functions directly called by the span with the given
+    /// `id`, together with the number of calls from this span to each of
+    /// them. This is empty if there is no span with this `id`.
+    pub fn children_of(&self, id: usize) -> impl Iterator<Item = (&TimedSpan, usize)> {
+        use petgraph::Direction;
+
+        self.graph.edges_directed(NodeIndex::new(id), Direction::Outgoing)
+            .map(move |edge| (&self.graph[edge.target()], edge.weight().count))
+    }
+
+    /// Get the spans/functions directly calling the span with the given `id`,
+    /// together with the number of calls from each of them to this span. This
+    /// is empty if there is no span with this `id`.
+    pub fn parents_of(&self, id: usize) -> impl Iterator<Item = (&TimedSpan, usize)> {
+        use petgraph::Direction;
+
+        self.graph.edges_directed(NodeIndex::new(id), Direction::Incoming)
+            .map(move |edge| (&self.graph[edge.source()], edge.weight().count))
+    }
+
+    /// Get the number of times the span with id `caller` called the span with
+    /// id `callee`, or 0 if it never called it or one of the spans does not
+    /// exist.
+    pub fn call_count(&self, caller: usize, callee: usize) -> usize {
+        match self.graph.find_edge(NodeIndex::new(caller), NodeIndex::new(callee)) {
+            Some(edge) => self.graph[edge].count,
+            None => 0,
+        }
+    }
+
+    /// Get the number of times spans named `caller` called spans named
+    /// `callee`. If multiple spans share one of these names (see
+    /// [`FullCallGraph::spans_by_name`]), the calls between all of them are
+    /// summed.
+    pub fn call_count_by_name(&self, caller: &str, callee: &str) -> usize {
+        let mut count = 0;
+        for caller in self.spans_by_name(caller) {
+            for callee in self.spans_by_name(callee) {
+                count += self.call_count(caller.id, callee.id);
+            }
+        }
+        return count;
+    }
+
+    /// Get the first span/function with the given `name` in this graph, if
+    /// any. See [`FullCallGraph::spans_by_name`] to get all the spans sharing
+    /// the same name.
+    pub fn span_by_name(&self, name: &str) -> Option<&TimedSpan> {
+        self.spans().find(|span| span.callsite.name() == name)
+    }
+
+    /// Get all the spans/functions with the given `name` in this graph. This
+    /// can contain multiple spans defined in different modules.
+    pub fn spans_by_name<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a TimedSpan> + 'a {
+        self.spans().filter(move |span| span.callsite.name() == name)
+    }
+
+    /// Get all the spans/functions with the given `key = value` pair in their
+    /// [`CallSite::metadata`].
+    pub fn spans_with_tag<'a>(&'a self, key: &'a str, value: &'a str) -> impl Iterator<Item = &'a TimedSpan> + 'a {
+        self.spans().filter(move |span| {
+            span.callsite.metadata().iter().any(|&(k, v)| k == key && v == value)
+        })
+    }
+
+    /// Get the span/function with the given full name (as returned by
+    /// [`CallSite::full_name`]) in this graph, if any.
+    pub fn span_by_full_name(&self, full_name: &str) -> Option<&TimedSpan> {
+        self.spans().find(|span| span.callsite.full_name() == full_name)
+    }
+
+    /// Get the spans/functions known by this graph, sorted by decreasing
+    /// [`TimedSpan::self_time`]
+    pub fn spans_by_self_time(&self) -> Vec<&TimedSpan> {
+        let mut spans = self.spans().collect::<Vec<_>>();
+        spans.sort_by_key(|span| std::cmp::Reverse(span.self_time));
+        return spans;
+    }
+
+    /// Get the list of calls between spans in this graph
+    pub fn calls(&self) -> impl Iterator<Item = Calls> + '_ {
+        self.graph.raw_edges().iter().map(|edge| Calls {
+            caller: edge.source().index(),
+            callee: edge.target().index(),
+            count: edge.weight.count,
+            elapsed: edge.weight.elapsed,
+        })
+    }
+
+    /// Get the list of calls between spans in this graph, sorted by caller
+    /// and then callee id. Contrary to [`FullCallGraph::calls`], the order is
+    /// guaranteed to stay the same for graphs containing the same data.
+    pub fn calls_sorted(&self) -> Vec<Calls> {
+        let mut calls = self.calls().collect::<Vec<_>>();
+        calls.sort_by_key(|call| (call.caller, call.callee));
+        return calls;
+    }
+
+    /// Get the list of calls between spans in this graph, together with the
+    /// fraction of the caller total elapsed time spent in the callee for each
+    /// call, between 0 and 1. The fraction is 0 if the caller did not spend
+    /// any time running.
+    ///
+    /// # Examples
+    /// ```
+    /// time_graph::enable_data_collection(true);
+    /// time_graph::spanned!("parent", {
+    ///     time_graph::spanned!("child", {
+    ///         // ...
+    ///     });
+    /// });
+    ///
+    /// for (call, fraction) in time_graph::get_full_graph().call_fractions() {
+    ///     println!("{} -> {}: {:.1}%", call.caller, call.callee, 100.0 * fraction);
+    /// }
+    /// ```
+    pub fn call_fractions(&self) -> Vec<(Calls, f64)> {
+        return self.calls().map(|call| {
+            let caller = self.graph[NodeIndex::new(call.caller)].elapsed;
+            let fraction = if caller.is_zero() {
+                0.0
+            } else {
+                (call.elapsed.as_secs_f64() / caller.as_secs_f64()).min(1.0)
+            };
+            (call, fraction)
+        }).collect();
+    }
+
+    /// Collapse all the spans whose module path starts with `prefix` into a
+    /// single aggregated span. See [`OwnedCallGraph::group_by_module`] for
+    /// more information.
+    pub fn group_by_module(&self, prefix: &str) -> OwnedCallGraph {
+        OwnedCallGraph::from(self).group_by_module(prefix)
+    }
+
+    /// Get a copy of this graph where the spans associated with the call sites
+    /// in `overrides` are displayed with the corresponding name, for example
+    /// to anonymize reports shared externally. The call sites themselves are
+    /// not changed, and the overridden spans keep their module path, file and
+    /// line.
+    ///
+    /// The overrides are used by all the exporters of the returned graph, and
+    /// when converting it to an [`OwnedCallGraph`]. They replace any override
+    /// for the same call sites in this graph.
+    ///
+    /// # Examples
+    /// ```
+    /// # use std::collections::HashMap;
+    /// # use time_graph::NameStyle;
+    /// time_graph::enable_data_collection(true);
+    /// time_graph::spanned!("secret_algorithm", {
+    ///     // ...
+    /// });
+    ///
+    /// let graph = time_graph::get_full_graph();
+    /// let span = graph.span_by_name("secret_algorithm").unwrap();
+    ///
+    /// let mut overrides = HashMap::new();
+    /// overrides.insert(span.callsite.id(), "step 1".to_string());
+    /// let anonymized = graph.with_name_overrides(&overrides);
+    /// let span = anonymized.span_by_name("secret_algorithm").unwrap();
+    /// assert_eq!(anonymized.display_name(span, NameStyle::NameOnly), "step 1");
+    /// ```
+    pub fn with_name_overrides(&self, overrides: &HashMap<CallSiteId, String>) -> FullCallGraph {
+        let mut name_overrides = self.name_overrides.clone();
+        name_overrides.extend(overrides.iter().map(|(&id, name)| (id, name.clone())));
+
+        return FullCallGraph {
+            graph: self.graph.map(|_, span| span.with_id(span.id), |_, &call| call),
+            collection_duration: self.collection_duration,
+            name_overrides: name_overrides,
+        };
+    }
+
+    /// Get the name of `span` displayed with the given `style`, using the name
+    /// set with [`FullCallGraph::with_name_overrides`] if there is one.
+    pub fn display_name(&self, span: &TimedSpan, style: NameStyle) -> String {
+        return crate::callsite::display_name(span.callsite.module_path(), self.span_name(span), style);
+    }
+
+    /// Get the name of `span`, using the name set with
+    /// [`FullCallGraph::with_name_overrides`] if there is one.
+    pub(crate) fn span_name<'a>(&'a self, span: &'a TimedSpan) -> &'a str {
+        match self.name_overrides.get(&span.callsite.id()) {
+            Some(name) => return name,
+            None => return span.callsite.name(),
+        }
+    }
+
+    /// Get the name of `span` displayed with the given `style`, with control
+    /// characters replaced by spaces
+    fn sanitized_name(&self, span: &TimedSpan, style: NameStyle) -> String {
+        return crate::callsite::sanitize(&self.display_name(span, style)).into_owned();
+    }
+
+    /// Get a copy of `span` owning its metadata, using the name set with
+    /// [`FullCallGraph::with_name_overrides`] if there is one.
+    pub(crate) fn owned_span(&self, span: &TimedSpan) -> OwnedSpan {
+        let mut owned = OwnedSpan::from(span);
+        if let Some(name) = self.name_overrides.get(&span.callsite.id()) {
+            owned.name = name.clone();
+        }
+        return owned;
+    }
+
+    /// Collapse all the spans whose name contains `separator` into aggregated
+    /// spans, one for each distinct prefix of the names up to the last
+    /// separator. See [`OwnedCallGraph::group_by_name_prefix`] for more
+    /// information.
+    pub fn group_by_name_prefix(&self, separator: char) -> OwnedCallGraph {
+        OwnedCallGraph::from(self).group_by_name_prefix(separator)
+    }
+
+    /// Get a new graph containing only the spans with a total elapsed time
+    /// larger or equal to `threshold`, and the calls between them.
+    ///
+    /// The spans in the new graph keep the same elapsed and self time as in
+    /// this graph, but they get new ids.
+    pub fn filter_by_elapsed(&self, threshold: Duration) -> FullCallGraph {
+        return self.filter_spans(|span| span.elapsed >= threshold);
+    }
+
+    /// Get a new graph containing only the spans with a [`Level`] larger or
+    /// equal to `min` (see [`CallSite::level`]), and the calls between them.
+    ///
+    /// The spans in the new graph keep the same elapsed and self time as in
+    /// this graph, but they get new ids.
+    ///
+    /// # Examples
+    /// ```
+    /// # use time_graph::{spanned, Level};
+    /// #[time_graph::instrument(level = "debug")]
+    /// fn details() {}
+    ///
+    /// #[time_graph::instrument]
+    /// fn overview() {
+    ///     details();
+    /// }
+    ///
+    /// time_graph::enable_data_collection(true);
+    /// overview();
+    ///
+    /// let graph = time_graph::get_full_graph().filter_by_level(Level::Info);
+    /// assert!(graph.span_by_name("overview").is_some());
+    /// assert!(graph.span_by_name("details").is_none());
+    /// ```
+    pub fn filter_by_level(&self, min: Level) -> FullCallGraph {
+        return self.filter_spans(|span| span.callsite.level() >= min);
+    }
+
+    /// Get a new graph containing only the spans for which `keep` returns
+    /// `true`, and the calls between them.
+    fn filter_spans(&self, keep: impl Fn(&TimedSpan) -> bool) -> FullCallGraph {
+        let graph = self.graph.filter_map(
+            |_, span| if keep(span) { Some(span) } else { None },
+            |_, &call| Some(call),
+        );
+
+        let graph = graph.map(|index, span| span.with_id(index.index()), |_, &call| call);
+        return FullCallGraph {
+            graph: graph,
+            collection_duration: self.collection_duration,
+            name_overrides: self.name_overrides.clone(),
+        };
+    }
+
+    /// Merge the data from `other` into this graph.
+    ///
+    /// Spans are matched by their [`CallSite`], summing the elapsed time and
+    /// number of calls of spans present in both graphs, and the number of
+    /// calls between them. Spans and calls only present in `other` are added
+    /// to this graph. The collection windows of both graphs are summed, and
+    /// the name overrides of `other` are added for call sites without one.
+    pub fn merge(&mut self, other: &FullCallGraph) {
+        merge_graphs(
+            &mut self.graph,
+            &other.graph,
+            |span| span.callsite.id(),
+            |existing, span| {
+                if existing.called == 0 {
+                    existing.min = span.min;
+                    existing.max = span.max;
+                } else if span.called != 0 {
+                    existing.min = existing.min.min(span.min);
+                    existing.max = existing.max.max(span.max);
+                }
+                existing.elapsed += span.elapsed;
+                existing.called += span.called;
+                existing.variance.merge(&span.variance);
+                existing.first_seen = match (existing.first_seen, span.first_seen) {
+                    (Some(first), Some(other)) => Some(first.min(other)),
+                    (first, other) => first.or(other),
+                };
+                existing.sampling_rate = existing.sampling_rate.max(span.sampling_rate);
+                merge_metrics(&mut existing.metrics, &span.metrics);
+                #[cfg(feature = "histogram")]
+                existing.histogram.add(&span.histogram).expect("failed to merge histograms");
+                #[cfg(feature = "per-thread")]
+                merge_per_thread(&mut existing.per_thread, &span.per_thread);
+                #[cfg(feature = "alloc-tracking")]
+                {
+                    existing.allocations += span.allocations;
+                    existing.bytes_allocated += span.bytes_allocated;
+                }
+            },
+            |span, index| span.with_id(index),
+        );
+
+        for (&id, name) in &other.name_overrides {
+            self.name_overrides.entry(id).or_insert_with(|| name.clone());
+        }
+
+        self.collection_duration += other.collection_duration;
+        for span in self.graph.node_weights_mut() {
+            span.window = self.collection_duration;
+        }
+
+        self.update_self_time();
+    }
+
+    /// Get the full graph in [graphviz](https://graphviz.org/) dot format.
+    ///
+    /// The exact output is unstable and should not be relied on.
+    pub fn as_dot(&self) -> String {
+        if self.name_overrides.is_empty() {
+            return petgraph::dot::Dot::new(&self.graph).to_string();
+        }
+
+        let graph = self.graph.map(
+            |_, span| format!(
+                "{} ran for {:?}, called {} times",
+                self.display_name(span, NameStyle::Full), span.elapsed, span.called
+            ),
+            |_, call| *call,
+        );
+        return petgraph::dot::Dot::new(&graph).to_string();
+    }
+
+    /// Get the full graph in [graphviz](https://graphviz.org/) dot format,
+    /// with nodes labeled by their name, total time and number of calls, and
+    /// edges labeled by the number of calls. Nodes are colored from green to
+    /// red according to their total time relative to the slowest span.
+    ///
+    /// The exact output is unstable and should not be relied on.
+    pub fn as_dot_styled(&self) -> String {
+        let slowest = self.spans().map(|span| span.elapsed).max().unwrap_or_default();
+
+        let edge_attributes = |_, edge: petgraph::graph::EdgeReference<'_, CallEdge>| {
+            format!("label = \"{}\"", edge.weight().count)
+        };
+        let node_attributes = |_, (_, span): (NodeIndex, &TimedSpan)| {
+            let fraction = if slowest.is_zero() {
+                0.0
+            } else {
+                span.elapsed.as_secs_f64() / slowest.as_secs_f64()
+            };
+            let red = (255.0 * fraction).round() as u8;
+            let green = (255.0 * (1.0 - fraction)).round() as u8;
+            format!(
+                "label = \"{}\\n{:.2?} ({} calls)\", style = filled, fillcolor = \"#{:02x}{:02x}00\"",
+                escape_dot(&self.display_name(span, NameStyle::Full)), span.elapsed, span.called, red, green,
+            )
+        };
+
+        let config = [petgraph::dot::Config::NodeNoLabel, petgraph::dot::Config::EdgeNoLabel];
+        let dot = petgraph::dot::Dot::with_attr_getters(&self.graph, &config, &edge_attributes, &node_attributes);
+        return dot.to_string();
+    }
+
+    /// Write the full graph in [graphviz](https://graphviz.org/) dot format to
+    /// the file at `path`, creating or truncating it as needed. See
+    /// [`FullCallGraph::as_dot`] for more information.
+    pub fn write_dot<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        return std::fs::write(path, self.as_dot());
+    }
+
+    /// Get the full graph as an indented tree, starting from the
+    /// [roots](FullCallGraph::roots) of the graph.
+    ///
+    /// Each line contains the name of a span, the number of calls and the
+    /// total time spent in this span. Spans called from multiple places are
+    /// repeated under each caller, with the number of calls from this caller,
+    /// and the corresponding fraction of the span total time. Recursive calls
+    /// are marked with `(cycle)` and not expanded further.
+    ///
+    /// The exact output is unstable and should not be relied on.
+    pub fn as_tree(&self) -> String {
+        self.as_tree_with_style(NameStyle::Full)
+    }
+
+    /// Same as `as_tree`, displaying the names of the spans with the given
+    /// `style`.
+    pub fn as_tree_with_style(&self, style: NameStyle) -> String {
+        let mut output = String::new();
+        let mut visited = vec![false; self.graph.node_count()];
+
+        let roots = self.roots().map(|span| NodeIndex::new(span.id)).collect::<Vec<_>>();
+        for root in roots.into_iter().chain(self.graph.node_indices()) {
+            if visited[root.index()] {
+                continue;
+            }
+
+            let span = &self.graph[root];
+            let mut path = vec![root];
+            self.tree_node(&mut output, style, &mut path, &mut visited, span.called as usize, span.elapsed);
+        }
+
+        return output;
+    }
+
+    /// Add the last node of `path` and all its children to the tree `output`.
+    /// `called` and `elapsed` are the number of calls and time spent in this
+    /// node from its parent.
+    fn tree_node(
+        &self,
+        output: &mut String,
+        style: NameStyle,
+        path: &mut Vec<NodeIndex>,
+        visited: &mut [bool],
+        called: usize,
+        elapsed: Duration,
+    ) {
+        use petgraph::Direction;
+
+        let node = *path.last().expect("empty path");
+        visited[node.index()] = true;
+
+        let indent = "    ".repeat(path.len() - 1);
+        let name = self.sanitized_name(&self.graph[node], style);
+        output.push_str(&format!("{}{}, called {} times, {:.2?}\n", indent, name, called, elapsed));
+
+        let mut children = self.graph.edges_directed(node, Direction::Outgoing).collect::<Vec<_>>();
+        children.sort_by_key(|edge| edge.target());
+        for edge in children {
+            let child = edge.target();
+            let span = &self.graph[child];
+            let count = edge.weight().count;
+
+            if path.contains(&child) {
+                let indent = "    ".repeat(path.len());
+                output.push_str(&format!("{}{}, called {} times (cycle)\n", indent, self.sanitized_name(span, style), count));
+                continue;
+            }
 
-    /// Get the list of calls between spans in this graph
-    pub fn calls(&self) -> impl Iterator<Item = Calls> + '_ {
-        self.graph.raw_edges().iter().map(|edge| Calls {
-            caller: edge.target().index(),
-            callee: edge.source().index(),
-            count: edge.weight,
-        })
+            let elapsed = if span.called != 0 {
+                span.elapsed.mul_f64((count as f64 / span.called as f64).min(1.0))
+            } else {
+                Duration::new(0, 0)
+            };
+
+            path.push(child);
+            self.tree_node(output, style, path, visited, count, elapsed);
+            path.pop();
+        }
     }
 
-    /// Get the full graph in [graphviz](https://graphviz.org/) dot format.
+    /// Get the full graph as a [mermaid](https://mermaid.js.org/) flowchart.
     ///
     /// The exact output is unstable and should not be relied on.
-    pub fn as_dot(&self) -> String {
-        petgraph::dot::Dot::new(&self.graph).to_string()
+    pub fn as_mermaid(&self) -> String {
+        let mut output = String::from("graph TD\n");
+        for span in self.spans() {
+            output += &format!("    {}[\"{}\"]\n", span.id, escape_mermaid(&span.to_string()));
+        }
+
+        for edge in self.graph.raw_edges() {
+            let caller = self.graph[edge.source()].id;
+            let callee = self.graph[edge.target()].id;
+            output += &format!("    {} -->|{}| {}\n", caller, edge.weight.count, callee);
+        }
+
+        return output;
     }
 
     /// Get a per span summary table of this graph.
@@ -325,83 +2947,143 @@ impl FullCallGraph {
     /// The exact output is unstable and should not be relied on.
     ///
     /// This function is only available if the `"table"` cargo feature is enabled
-    ///
-    /// # Panic
-    ///
-    /// This function will panic if the graph is cyclical, i.e. if two or more
-    /// span are mutually recursive.
     #[cfg(feature = "table")]
     pub fn as_table(&self) -> String {
-        self.as_table_impl(false)
+        self.as_table_with(TableOptions::default())
+    }
+
+    /// Write the per span summary table of this graph to the file at `path`,
+    /// creating or truncating it as needed. See [`FullCallGraph::as_table`]
+    /// for more information.
+    ///
+    /// This function is only available if the `"table"` cargo feature is enabled
+    #[cfg(feature = "table")]
+    pub fn write_table<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        return std::fs::write(path, self.as_table());
     }
 
-    /// Same as `as_table`, but using the short names of the spans instead of
-    /// the full name.
+    /// Get a compact per span summary table of this graph, using the short
+    /// names of the spans, without the `called by`, `min` and `max` columns,
+    /// and sorted by decreasing total elapsed time.
+    ///
+    /// The exact output is unstable and should not be relied on.
+    ///
+    /// This function is only available if the `"table"` cargo feature is enabled
     #[cfg(feature = "table")]
     pub fn as_short_table(&self) -> String {
-        self.as_table_impl(true)
+        self.as_table_with(TableOptions::short())
     }
 
+    /// Same as `as_table`, displaying the names of the spans with the given
+    /// `style`.
     #[cfg(feature = "table")]
-    fn as_table_impl(&self, short_names: bool) -> String {
-        use petgraph::Direction;
+    pub fn as_table_with_style(&self, style: NameStyle) -> String {
+        self.as_table_with(TableOptions {
+            name_style: style,
+            ..TableOptions::default()
+        })
+    }
 
-        use crate::table::Row;
-        use crate::table::TableCell;
+    /// Same as `as_table`, drawing the table with the given `style`. Use
+    /// [`TableStyle::simple`] to only use ASCII characters.
+    #[cfg(feature = "table")]
+    pub fn as_table_styled(&self, style: TableStyle) -> String {
+        self.as_table_with(TableOptions {
+            style: style,
+            ..TableOptions::default()
+        })
+    }
 
-        let mut names = BTreeMap::new();
-        for node in self.graph.node_weights() {
-            if short_names {
-                names.insert(node.id, node.callsite.name().to_string());
-            } else {
-                names.insert(node.id, node.callsite.full_name());
+    /// Get a per span summary table of this graph, with the columns and
+    /// ordering of rows specified by `options`.
+    ///
+    /// The exact output is unstable and should not be relied on.
+    ///
+    /// This function is only available if the `"table"` cargo feature is enabled
+    #[cfg(feature = "table")]
+    pub fn as_table_with(&self, options: TableOptions) -> String {
+        let graph = self.graph.map(|_, span| {
+            TableSpan {
+                id: span.id,
+                name: self.sanitized_name(span, options.name_style),
+                called: span.called,
+                elapsed: span.elapsed,
+                min: span.min,
+                max: span.max,
+                self_time: span.self_time,
+                stddev: Some(span.stddev()),
+                metrics: span.metrics.clone(),
             }
-        }
+        }, |_, call| call.count);
+
+        return render_table(&graph, &options);
+    }
+
+    /// Get a per span summary of this graph in CSV, with one row per span.
+    ///
+    /// All durations are given in integer nanoseconds. For spans which did not
+    /// finish running yet, the mean, min and max columns are left empty.
+    /// Control characters in the names are replaced by spaces, see
+    /// [`CallSite::sanitized_name`].
+    pub fn as_csv(&self) -> String {
+        let mut output = String::from("id,name,module,called,total_ns,mean_ns,min_ns,max_ns\n");
+        for span in self.spans() {
+            let (mean, min, max) = if span.called != 0 {
+                (
+                    (span.elapsed / span.called).as_nanos().to_string(),
+                    span.min.as_nanos().to_string(),
+                    span.max.as_nanos().to_string(),
+                )
+            } else {
+                (String::new(), String::new(), String::new())
+            };
 
-        let mut table = crate::table::Table::new();
-        table.style = crate::table::TableStyle::extended();
+            output += &format!(
+                "{},{},{},{},{},{},{},{}\n",
+                span.id,
+                escape_csv(&crate::callsite::sanitize(self.span_name(span))),
+                escape_csv(&crate::callsite::sanitize(span.callsite.module_path())),
+                span.called,
+                span.elapsed.as_nanos(),
+                mean,
+                min,
+                max,
+            );
+        }
 
-        table.add_row(Row::new(vec![
-            "id",
-            // pad "span name" to make the table look nicer with short names
-            "span name                                   ",
-            "call count",
-            "called by",
-            "total",
-            "mean",
-        ]));
+        return output;
+    }
 
-        for &node_id in petgraph::algo::kosaraju_scc(&self.graph)
-            .iter()
-            .rev()
-            .flatten()
-        {
-            let node = &self.graph[node_id];
+    /// Get a compact per span summary table of this graph in GitHub-flavored
+    /// Markdown, to be included in pull requests or documentation. The table
+    /// has the same columns and ordering as [`FullCallGraph::as_short_table`],
+    /// and is available without the `"table"` cargo feature.
+    ///
+    /// The exact output is unstable and should not be relied on.
+    pub fn as_markdown(&self) -> String {
+        let mut spans = self.spans_sorted();
+        spans.sort_by_key(|span| std::cmp::Reverse(span.elapsed));
 
-            let mut called_by = vec![];
-            for other in self.graph.neighbors_directed(node_id, Direction::Incoming) {
-                called_by.push(self.graph[other].id.to_string());
-            }
-            let called_by = if !called_by.is_empty() {
-                called_by.join(", ")
+        let mut output = String::from("| id | span name | call count | total | mean |\n");
+        output += "|---:|---|---:|---:|---:|\n";
+        for span in spans {
+            let mean = if span.called != 0 {
+                format!("{:.2?}", span.elapsed / span.called)
             } else {
-                "—".into()
+                "-".into()
             };
 
-            let mean = node.elapsed / node.called;
-            let warn = if mean < Duration::from_nanos(1500) { " ⚠️ " } else { "" };
-
-            table.add_row(Row::new(vec![
-                TableCell::new_right_aligned(node.id),
-                TableCell::new(&names[&node.id]),
-                TableCell::new_right_aligned(node.called),
-                TableCell::new_right_aligned(called_by),
-                TableCell::new_right_aligned(format!("{:.2?}", node.elapsed)),
-                TableCell::new_right_aligned(format!("{:.2?}{}", mean, warn)),
-            ]));
+            output += &format!(
+                "| {} | {} | {} | {:.2?} | {} |\n",
+                span.id,
+                escape_markdown(&self.sanitized_name(span, NameStyle::NameOnly)),
+                span.called,
+                span.elapsed,
+                mean,
+            );
         }
 
-        return table.render();
+        return output;
     }
 
     /// Get all the data in this graph in JSON.
@@ -411,11 +3093,25 @@ impl FullCallGraph {
     /// This function is only available if the `"json"` cargo feature is enabled
     #[cfg(feature = "json")]
     pub fn as_json(&self) -> String {
+        self.as_json_with_unit(DurationUnit::Auto)
+    }
+
+    /// Get all the data in this graph in JSON, with the elapsed times given as
+    /// numbers in the given `unit`. If `unit` is [`DurationUnit::Auto`], this
+    /// is the same as [`FullCallGraph::as_json`].
+    ///
+    /// This function is only available if the `"json"` cargo feature is enabled
+    #[cfg(feature = "json")]
+    pub fn as_json_with_unit(&self, unit: DurationUnit) -> String {
         let mut spans = json::JsonValue::new_object();
         for span in self.spans() {
-            spans[&span.callsite.full_name()] = json::object! {
+            let elapsed = match unit.value(span.elapsed) {
+                Some(value) => json::JsonValue::from(value),
+                None => json::JsonValue::from(format!("{:?}", span.elapsed)),
+            };
+            spans[&self.display_name(span, NameStyle::Full)] = json::object! {
                 "id" => span.id,
-                "elapsed" => format!("{:?}", span.elapsed),
+                "elapsed" => elapsed,
                 "called" => span.called,
             };
         }
@@ -434,4 +3130,777 @@ impl FullCallGraph {
             "calls" => all_calls,
         });
     }
+
+    /// Write all the data in this graph in JSON to the file at `path`,
+    /// creating or truncating it as needed. See [`FullCallGraph::as_json`] for
+    /// more information.
+    ///
+    /// This function is only available if the `"json"` cargo feature is enabled
+    #[cfg(feature = "json")]
+    pub fn write_json<P: AsRef<Path>>(&self, path: P) -> std::io::Result<()> {
+        return std::fs::write(path, self.as_json());
+    }
+
+    /// Get this graph in the [Trace Event Format], which can be loaded in
+    /// [Perfetto](https://ui.perfetto.dev/) or `chrome://tracing`.
+    ///
+    /// Each span is emitted as a single complete event, lasting for the total
+    /// time spent in this span. Since this graph only contains aggregated
+    /// timings, the start time of the events is synthetic: children are laid
+    /// out one after the other inside their first parent. This output is meant
+    /// to give an overview of where time is spent, not a per-call timeline.
+    ///
+    /// When the `"per-thread"` cargo feature is enabled, the spans of each
+    /// thread are placed on a separate track, with the elapsed time and number
+    /// of calls from this thread, and `thread_name` metadata events give the
+    /// name of the corresponding threads.
+    ///
+    /// The exact output is unstable and should not be relied on.
+    ///
+    /// This function is only available if the `"json"` cargo feature is enabled
+    ///
+    /// [Trace Event Format]: https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU
+    #[cfg(feature = "json")]
+    pub fn as_chrome_trace(&self) -> String {
+        let mut events = json::JsonValue::new_array();
+
+        #[cfg(feature = "per-thread")]
+        if self.spans().any(|span| !span.per_thread.is_empty()) {
+            self.add_per_thread_trace_events(&mut events);
+            return json::stringify(events);
+        }
+
+        let layout = self.synthetic_layout();
+        for node in self.graph.node_indices() {
+            let span = &self.graph[node];
+            let name = self.display_name(span, NameStyle::Full);
+            let event = trace_event(name, layout.start[node.index()], span.elapsed, span.called, 1);
+            events.push(event).expect("failed to add event to JSON");
+        }
+
+        return json::stringify(events);
+    }
+
+    /// Add the events for all spans to `events`, with one track per thread
+    #[cfg(all(feature = "json", feature = "per-thread"))]
+    fn add_per_thread_trace_events(&self, events: &mut json::JsonValue) {
+        let names = THREAD_NAMES.lock().unwrap_or_else(PoisonError::into_inner).clone();
+        let name = |thread: &std::thread::ThreadId| {
+            names.get(thread).cloned().unwrap_or_else(|| format!("{:?}", thread))
+        };
+
+        // sort the threads by name to give them the same track across runs
+        let mut threads = Vec::new();
+        for span in self.spans() {
+            for thread in span.per_thread.keys() {
+                if !threads.contains(thread) {
+                    threads.push(*thread);
+                }
+            }
+        }
+        threads.sort_by_cached_key(|thread| (name(thread), format!("{:?}", thread)));
+
+        for (tid, thread) in threads.iter().enumerate() {
+            let tid = tid + 1;
+            events.push(json::object! {
+                "name" => "thread_name",
+                "ph" => "M",
+                "pid" => 1,
+                "tid" => tid,
+                "args" => json::object! {
+                    "name" => name(thread),
+                },
+            }).expect("failed to add event to JSON");
+
+            let thread_data = |span: &TimedSpan| span.per_thread.get(thread).copied();
+            let layout = self.synthetic_layout_with(|span| thread_data(span).unwrap_or_default().0);
+            for node in self.graph.node_indices() {
+                let span = &self.graph[node];
+                if let Some((elapsed, called)) = thread_data(span) {
+                    let name = self.display_name(span, NameStyle::Full);
+                    let event = trace_event(name, layout.start[node.index()], elapsed, called, tid);
+                    events.push(event).expect("failed to add event to JSON");
+                }
+            }
+        }
+    }
+
+    /// Export all spans in this graph to OpenTelemetry using the given
+    /// `tracer`, as one OpenTelemetry span per function/span.
+    ///
+    /// Each span is exported with a duration equal to the total time spent in
+    /// this span, and the number of calls in the `called` attribute. Spans
+    /// called from multiple places are only exported once, as a child of their
+    /// first caller. Since this graph only contains aggregated timings, the
+    /// start time of the spans is synthetic (see
+    /// [`FullCallGraph::as_chrome_trace`]), and anchored at the time of the
+    /// export.
+    ///
+    /// This function is only available if the `"opentelemetry"` cargo feature
+    /// is enabled
+    #[cfg(feature = "opentelemetry")]
+    pub fn export_otel<T: opentelemetry::trace::Tracer>(&self, tracer: &T) {
+        use opentelemetry::trace::{Span as _, SpanBuilder, TraceContextExt};
+        use opentelemetry::{Context, KeyValue};
+
+        let anchor = std::time::SystemTime::now();
+        let layout = self.synthetic_layout();
+
+        let mut contexts = vec![None; self.graph.node_count()];
+        for &node in &layout.order {
+            let span = &self.graph[node];
+
+            let parent = match layout.parent[node.index()] {
+                Some(parent) => {
+                    let parent = contexts[parent.index()].clone().expect("parent span was not exported");
+                    Context::new().with_remote_span_context(parent)
+                }
+                None => Context::new(),
+            };
+
+            let start = anchor + layout.start[node.index()];
+            let builder = SpanBuilder::from_name(self.display_name(span, NameStyle::Full))
+                .with_start_time(start)
+                .with_attributes([
+                    KeyValue::new("called", span.called as i64),
+                    KeyValue::new("code.namespace", span.callsite.module_path()),
+                    KeyValue::new("code.filepath", span.callsite.file()),
+                    KeyValue::new("code.lineno", span.callsite.line() as i64),
+                ]);
+
+            let mut otel_span = tracer.build_with_context(builder, &parent);
+            contexts[node.index()] = Some(otel_span.span_context().clone());
+            otel_span.end_with_timestamp(start + span.elapsed);
+        }
+    }
+
+    /// Get the elapsed time and number of calls of all spans in this graph in
+    /// the Prometheus [text exposition format], as the
+    /// `timegraph_span_elapsed_seconds_total` and `timegraph_span_calls_total`
+    /// counters, labeled with the span `name` and `module`. Spans from
+    /// different callsites with the same name and module are summed together.
+    ///
+    /// This function is only available if the `"prometheus"` cargo feature is
+    /// enabled
+    ///
+    /// [text exposition format]: https://prometheus.io/docs/instrumenting/exposition_formats/
+    #[cfg(feature = "prometheus")]
+    pub fn as_prometheus(&self) -> String {
+        // spans from different callsites can share the same name and module,
+        // their values are summed to produce a single series per label set
+        let mut series = BTreeMap::<(&str, &str), (Duration, u32)>::new();
+        for span in self.spans() {
+            let entry = series.entry((self.span_name(span), span.callsite.module_path())).or_default();
+            entry.0 += span.elapsed;
+            entry.1 += span.called;
+        }
+
+        let labels = |name: &str, module: &str| format!(
+            "name=\"{}\",module=\"{}\"",
+            escape_prometheus(name),
+            escape_prometheus(module),
+        );
+
+        let mut output = String::new();
+        output += "# HELP timegraph_span_elapsed_seconds_total Total time spent inside a span.\n";
+        output += "# TYPE timegraph_span_elapsed_seconds_total counter\n";
+        for (&(name, module), &(elapsed, _)) in &series {
+            output += &format!(
+                "timegraph_span_elapsed_seconds_total{{{}}} {}\n",
+                labels(name, module), elapsed.as_secs_f64()
+            );
+        }
+
+        output += "# HELP timegraph_span_calls_total Number of calls to a span.\n";
+        output += "# TYPE timegraph_span_calls_total counter\n";
+        for (&(name, module), &(_, called)) in &series {
+            output += &format!("timegraph_span_calls_total{{{}}} {}\n", labels(name, module), called);
+        }
+
+        return output;
+    }
+
+    /// Get the full graph as an SVG [flamegraph], rendered with the
+    /// [inferno](https://crates.io/crates/inferno) crate.
+    ///
+    /// Since this graph only contains aggregated timings, the width of each
+    /// frame is the total time spent in the corresponding span from a given
+    /// stack of callers, and not a sample count. Spans called from multiple
+    /// places are split between their callers proportionally to the time
+    /// spent in the span from each caller. Recursive calls are not expanded,
+    /// and their time is attributed to the first occurrence of the span in the
+    /// stack.
+    ///
+    /// This function is only available if the `"flamegraph"` cargo feature is
+    /// enabled
+    ///
+    /// [flamegraph]: https://www.brendangregg.com/flamegraphs.html
+    #[cfg(feature = "flamegraph")]
+    pub fn as_flamegraph_svg(&self) -> String {
+        let mut lines = Vec::new();
+        let mut visited = vec![false; self.graph.node_count()];
+
+        let roots = self.roots().map(|span| NodeIndex::new(span.id)).collect::<Vec<_>>();
+        for root in roots.into_iter().chain(self.graph.node_indices()) {
+            if visited[root.index()] {
+                continue;
+            }
+
+            let mut path = vec![root];
+            self.folded_stacks(&mut lines, &mut path, &mut visited, self.graph[root].elapsed);
+        }
+
+        let mut options = inferno::flamegraph::Options::default();
+        options.count_name = "ns".into();
+
+        let mut svg = Vec::new();
+        inferno::flamegraph::from_lines(&mut options, lines.iter().map(String::as_str), &mut svg)
+            .expect("failed to render flamegraph");
+        return String::from_utf8(svg).expect("flamegraph is not valid UTF-8");
+    }
+
+    /// Add the folded stacks for the last node of `path` and all its children
+    /// to `lines`. `elapsed` is the time spent in this node with this stack of
+    /// callers.
+    #[cfg(feature = "flamegraph")]
+    fn folded_stacks(&self, lines: &mut Vec<String>, path: &mut Vec<NodeIndex>, visited: &mut [bool], elapsed: Duration) {
+        use petgraph::Direction;
+
+        let node = *path.last().expect("empty path");
+        visited[node.index()] = true;
+
+        // fraction of the time of this node spent with the current callers
+        let fraction = if self.graph[node].elapsed.is_zero() {
+            0.0
+        } else {
+            elapsed.as_secs_f64() / self.graph[node].elapsed.as_secs_f64()
+        };
+
+        let mut self_time = elapsed;
+        let mut children = self.graph.edges_directed(node, Direction::Outgoing).collect::<Vec<_>>();
+        children.sort_by_key(|edge| edge.target());
+        for edge in children {
+            let child = edge.target();
+            if path.contains(&child) {
+                continue;
+            }
+
+            let child_elapsed = edge.weight().elapsed.mul_f64(fraction.min(1.0));
+            self_time = self_time.saturating_sub(child_elapsed);
+
+            path.push(child);
+            self.folded_stacks(lines, path, visited, child_elapsed);
+            path.pop();
+        }
+
+        if !self_time.is_zero() {
+            let stack = path.iter()
+                // `;` separates frames in folded stacks
+                .map(|&node| self.sanitized_name(&self.graph[node], NameStyle::Full).replace(';', ","))
+                .collect::<Vec<_>>()
+                .join(";");
+            lines.push(format!("{} {}", stack, self_time.as_nanos()));
+        }
+    }
+}
+
+/// Synthetic start time of all spans in a graph, used to export aggregated
+/// timings to formats expecting a timeline
+#[cfg(any(feature = "json", feature = "opentelemetry"))]
+struct SyntheticLayout {
+    /// All nodes, ordered such that parents come before their children
+    order: Vec<NodeIndex>,
+    /// Start time of each node, relative to the start of the first root
+    start: Vec<Duration>,
+    /// Parent of each node in the layout
+    parent: Vec<Option<NodeIndex>>,
+}
+
+#[cfg(any(feature = "json", feature = "opentelemetry"))]
+impl FullCallGraph {
+    /// Lay out all spans in a synthetic timeline: roots are placed one after
+    /// the other, and children are laid out one after the other inside their
+    /// first parent.
+    fn synthetic_layout(&self) -> SyntheticLayout {
+        self.synthetic_layout_with(|span| span.elapsed)
+    }
+
+    /// Same as `synthetic_layout`, using `elapsed` to get the duration of
+    /// each span in the timeline
+    fn synthetic_layout_with(&self, elapsed: impl Fn(&TimedSpan) -> Duration) -> SyntheticLayout {
+        use petgraph::Direction;
+
+        let count = self.graph.node_count();
+        let mut layout = SyntheticLayout {
+            order: Vec::with_capacity(count),
+            start: vec![Duration::new(0, 0); count],
+            parent: vec![None; count],
+        };
+        let mut visited = vec![false; count];
+        let mut next_root_start = Duration::new(0, 0);
+
+        // lay out spans without parent first, and then anything left over
+        // (i.e. mutually recursive spans)
+        let roots = self.graph.externals(Direction::Incoming);
+        for root in roots.chain(self.graph.node_indices()) {
+            if visited[root.index()] {
+                continue;
+            }
+
+            visited[root.index()] = true;
+            layout.order.push(root);
+            layout.start[root.index()] = next_root_start;
+            next_root_start += elapsed(&self.graph[root]);
+
+            let mut stack = vec![root];
+            while let Some(node) = stack.pop() {
+                let mut child_start = layout.start[node.index()];
+                for child in self.graph.neighbors_directed(node, Direction::Outgoing) {
+                    if !visited[child.index()] {
+                        visited[child.index()] = true;
+                        layout.order.push(child);
+                        layout.start[child.index()] = child_start;
+                        layout.parent[child.index()] = Some(node);
+                        child_start += elapsed(&self.graph[child]);
+                        stack.push(child);
+                    }
+                }
+            }
+        }
+
+        return layout;
+    }
+}
+
+/// Create a complete trace event for the span `name`, starting at `start` and
+/// lasting for `elapsed`, on the thread track `tid`
+#[cfg(feature = "json")]
+fn trace_event(name: String, start: Duration, elapsed: Duration, called: u32, tid: usize) -> json::JsonValue {
+    return json::object! {
+        "name" => name,
+        "cat" => "time-graph",
+        "ph" => "X",
+        "ts" => start.as_secs_f64() * 1e6,
+        "dur" => elapsed.as_secs_f64() * 1e6,
+        "pid" => 1,
+        "tid" => tid,
+        "args" => json::object! {
+            "called" => called,
+        },
+    };
+}
+
+/// Get the relative change from `before` to `after` in percent, or `None` if
+/// `before` is zero
+fn percent_change(before: Duration, after: Duration) -> Option<f64> {
+    if before.is_zero() {
+        return None;
+    }
+    let before = before.as_secs_f64();
+    return Some(100.0 * (after.as_secs_f64() - before) / before);
+}
+
+/// Escape `field` to be used in a CSV file, quoting it if it contains
+/// commas, quotes or new lines.
+fn escape_csv(field: &str) -> std::borrow::Cow<'_, str> {
+    if field.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", field.replace('"', "\"\"")).into()
+    } else {
+        field.into()
+    }
+}
+
+/// Escape `text` to be used inside a Markdown table cell
+fn escape_markdown(text: &str) -> String {
+    return text.replace('\\', "\\\\").replace('|', "\\|");
+}
+
+/// Escape `label` to be used inside a double-quoted graphviz label. New lines
+/// are kept as `\n`, and other control characters replaced by spaces.
+fn escape_dot(label: &str) -> String {
+    let mut escaped = String::with_capacity(label.len());
+    for c in label.chars() {
+        match c {
+            '\\' => escaped += "\\\\",
+            '"' => escaped += "\\\"",
+            '\n' => escaped += "\\n",
+            c if c.is_control() => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    return escaped;
+}
+
+/// Escape `label` to be used inside a double-quoted mermaid node label.
+/// Characters with a special meaning for mermaid are replaced by their entity
+/// codes, and control characters (including new lines) by spaces.
+fn escape_mermaid(label: &str) -> String {
+    let mut escaped = String::with_capacity(label.len());
+    for c in label.chars() {
+        match c {
+            '"' => escaped += "#quot;",
+            '#' => escaped += "#35;",
+            '<' => escaped += "#lt;",
+            '>' => escaped += "#gt;",
+            c if c.is_control() => escaped.push(' '),
+            c => escaped.push(c),
+        }
+    }
+    return escaped;
+}
+
+/// Escape `value` to be used as a Prometheus label value
+#[cfg(feature = "prometheus")]
+fn escape_prometheus(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for c in value.chars() {
+        match c {
+            '\\' => escaped += "\\\\",
+            '"' => escaped += "\\\"",
+            '\n' => escaped += "\\n",
+            c => escaped.push(c),
+        }
+    }
+    return escaped;
+}
+
+/// Unit used to display durations in tables and JSON output
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum DurationUnit {
+    /// Pick the most appropriate unit for each duration
+    #[default]
+    Auto,
+    /// Always use nanoseconds
+    Nanoseconds,
+    /// Always use microseconds
+    Microseconds,
+    /// Always use milliseconds
+    Milliseconds,
+    /// Always use seconds
+    Seconds,
+}
+
+impl DurationUnit {
+    /// Get the value of `duration` in this unit, or `None` for
+    /// [`DurationUnit::Auto`]
+    pub fn value(self, duration: Duration) -> Option<f64> {
+        let seconds = duration.as_secs_f64();
+        match self {
+            DurationUnit::Auto => None,
+            DurationUnit::Nanoseconds => Some(seconds * 1e9),
+            DurationUnit::Microseconds => Some(seconds * 1e6),
+            DurationUnit::Milliseconds => Some(seconds * 1e3),
+            DurationUnit::Seconds => Some(seconds),
+        }
+    }
+
+    /// Get the suffix used for this unit, or `None` for [`DurationUnit::Auto`]
+    pub fn suffix(self) -> Option<&'static str> {
+        match self {
+            DurationUnit::Auto => None,
+            DurationUnit::Nanoseconds => Some("ns"),
+            DurationUnit::Microseconds => Some("µs"),
+            DurationUnit::Milliseconds => Some("ms"),
+            DurationUnit::Seconds => Some("s"),
+        }
+    }
+
+    /// Format `duration` in this unit with two decimal digits
+    #[cfg(feature = "table")]
+    fn format(self, duration: Duration) -> String {
+        match (self.value(duration), self.suffix()) {
+            (Some(value), Some(suffix)) => format!("{:.2}{}", value, suffix),
+            _ => format!("{:.2?}", duration),
+        }
+    }
+}
+
+/// Columns which can be included in the table created by
+/// [`FullCallGraph::as_table_with`].
+#[cfg(feature = "table")]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TableColumn {
+    /// Identifier of the span
+    Id,
+    /// Name of the span
+    Name,
+    /// Number of calls to the span
+    Count,
+    /// Identifiers of the spans calling this span
+    CalledBy,
+    /// Total elapsed time in the span
+    Total,
+    /// Mean elapsed time for a single call
+    Mean,
+    /// Shortest elapsed time for a single call
+    Min,
+    /// Longest elapsed time for a single call
+    Max,
+    /// Elapsed time in the span, excluding children
+    SelfTime,
+    /// Standard deviation of the elapsed time for a single call
+    StdDev,
+    /// Throughput of the given custom metric (see
+    /// [`SpanGuard::record_metric`]), i.e. the sum of the values recorded for
+    /// this metric divided by the total elapsed time, per second
+    Throughput(&'static str),
+}
+
+#[cfg(feature = "table")]
+impl TableColumn {
+    fn header(self) -> String {
+        match self {
+            TableColumn::Id => "id".into(),
+            TableColumn::Name => "span name".into(),
+            TableColumn::Count => "call count".into(),
+            TableColumn::CalledBy => "called by".into(),
+            TableColumn::Total => "total".into(),
+            TableColumn::Mean => "mean".into(),
+            TableColumn::Min => "min".into(),
+            TableColumn::Max => "max".into(),
+            TableColumn::SelfTime => "self time".into(),
+            TableColumn::StdDev => "std dev".into(),
+            TableColumn::Throughput(key) => format!("{}/s", key),
+        }
+    }
+}
+
+/// Options controlling the table created by [`FullCallGraph::as_table_with`]
+#[cfg(feature = "table")]
+#[derive(Debug, Clone)]
+pub struct TableOptions {
+    /// Columns to include in the table, in order
+    pub columns: Vec<TableColumn>,
+    /// Column used to sort the rows of the table. If `None`, callers are
+    /// placed before the spans they call.
+    pub sort_by: Option<TableColumn>,
+    /// Should the rows be sorted in descending order? If `sort_by` is `None`,
+    /// this places callees before their callers.
+    pub descending: bool,
+    /// How to display the span names
+    pub name_style: NameStyle,
+    /// Maximal width of the span name column, longer names are wrapped on
+    /// multiple lines
+    pub name_width: Option<usize>,
+    /// Characters used to draw the table
+    pub style: TableStyle,
+    /// Unit used to display durations
+    pub duration_unit: DurationUnit,
+}
+
+#[cfg(feature = "table")]
+impl Default for TableOptions {
+    fn default() -> TableOptions {
+        TableOptions {
+            columns: vec![
+                TableColumn::Id,
+                TableColumn::Name,
+                TableColumn::Count,
+                TableColumn::CalledBy,
+                TableColumn::Total,
+                TableColumn::Mean,
+                TableColumn::Min,
+                TableColumn::Max,
+            ],
+            sort_by: None,
+            descending: false,
+            name_style: NameStyle::Full,
+            name_width: None,
+            style: TableStyle::extended(),
+            duration_unit: DurationUnit::Auto,
+        }
+    }
+}
+
+#[cfg(feature = "table")]
+impl TableOptions {
+    /// Options used by [`FullCallGraph::as_short_table`]
+    pub fn short() -> TableOptions {
+        TableOptions {
+            columns: vec![
+                TableColumn::Id,
+                TableColumn::Name,
+                TableColumn::Count,
+                TableColumn::Total,
+                TableColumn::Mean,
+            ],
+            sort_by: Some(TableColumn::Total),
+            descending: true,
+            name_style: NameStyle::NameOnly,
+            name_width: None,
+            style: TableStyle::extended(),
+            duration_unit: DurationUnit::Auto,
+        }
+    }
+}
+
+/// Data about a single span required to render it in a table
+#[cfg(feature = "table")]
+pub(crate) struct TableSpan {
+    pub id: usize,
+    pub name: String,
+    pub called: u32,
+    pub elapsed: Duration,
+    pub min: Duration,
+    pub max: Duration,
+    pub self_time: Duration,
+    /// Standard deviation of single calls, if known
+    pub stddev: Option<Duration>,
+    /// Sum of the custom metrics recorded in this span
+    pub metrics: BTreeMap<&'static str, u64>,
+}
+
+#[cfg(feature = "table")]
+impl TableSpan {
+    fn mean(&self) -> Option<Duration> {
+        if self.called != 0 {
+            Some(self.elapsed / self.called)
+        } else {
+            None
+        }
+    }
+
+    /// Get the throughput of the `key` metric, per second
+    fn throughput(&self, key: &str) -> Option<f64> {
+        let value = self.metrics.get(key)?;
+        if self.elapsed.is_zero() {
+            return None;
+        }
+        return Some(*value as f64 / self.elapsed.as_secs_f64());
+    }
+}
+
+/// Minimal width of the span name column in tables
+#[cfg(feature = "table")]
+const NAME_COLUMN_MIN_WIDTH: usize = 24;
+
+/// Render a per span summary table of the given call graph.
+#[cfg(feature = "table")]
+pub(crate) fn render_table(graph: &Graph<TableSpan, usize>, options: &TableOptions) -> String {
+    use petgraph::Direction;
+
+    use crate::table::Row;
+    use crate::table::TableCell;
+
+    let mut table = crate::table::Table::new();
+    table.style = options.style;
+
+    if let Some(column) = options.columns.iter().position(|&column| column == TableColumn::Name) {
+        // the name column fits the longest name, but is kept wide enough for
+        // the table to look nice with short names
+        table.min_column_widths.insert(column, NAME_COLUMN_MIN_WIDTH);
+        if let Some(width) = options.name_width {
+            table.max_column_widths.insert(column, width);
+        }
+    }
+
+    table.add_row(Row::new(options.columns.iter().map(|column| column.header())));
+
+    // only use ASCII characters in the content if the style is ASCII-only
+    let ascii = options.style.is_ascii();
+    let missing = if ascii { "-" } else { "—" };
+    let warning = if ascii { " (!)" } else { " ⚠️ " };
+    let duration = |duration: Duration| {
+        let formatted = options.duration_unit.format(duration);
+        if ascii {
+            formatted.replace('µ', "u")
+        } else {
+            formatted
+        }
+    };
+
+    let mut nodes = petgraph::algo::kosaraju_scc(graph)
+        .into_iter()
+        .rev()
+        .flatten()
+        .collect::<Vec<_>>();
+
+    if let Some(sort_by) = options.sort_by {
+        nodes.sort_by(|&a, &b| {
+            let (a, b) = (&graph[a], &graph[b]);
+            let ordering = match sort_by {
+                TableColumn::Id | TableColumn::CalledBy => a.id.cmp(&b.id),
+                TableColumn::Name => a.name.cmp(&b.name),
+                TableColumn::Count => a.called.cmp(&b.called),
+                TableColumn::Total => a.elapsed.cmp(&b.elapsed),
+                TableColumn::Mean => a.mean().cmp(&b.mean()),
+                TableColumn::Min => a.min.cmp(&b.min),
+                TableColumn::Max => a.max.cmp(&b.max),
+                TableColumn::SelfTime => a.self_time.cmp(&b.self_time),
+                TableColumn::StdDev => a.stddev.cmp(&b.stddev),
+                TableColumn::Throughput(key) => {
+                    a.throughput(key).partial_cmp(&b.throughput(key)).unwrap_or(std::cmp::Ordering::Equal)
+                }
+            };
+
+            if options.descending {
+                ordering.reverse()
+            } else {
+                ordering
+            }
+        });
+    } else if options.descending {
+        nodes.reverse();
+    }
+
+    for node_id in nodes {
+        let node = &graph[node_id];
+
+        let cells = options.columns.iter().map(|column| match column {
+            TableColumn::Id => TableCell::new_right_aligned(node.id),
+            TableColumn::Name => TableCell::new(&node.name),
+            TableColumn::Count => TableCell::new_right_aligned(node.called),
+            TableColumn::CalledBy => {
+                let mut called_by = vec![];
+                for other in graph.neighbors_directed(node_id, Direction::Incoming) {
+                    called_by.push(graph[other].id.to_string());
+                }
+                let called_by = if !called_by.is_empty() {
+                    called_by.join(", ")
+                } else {
+                    missing.into()
+                };
+                TableCell::new_right_aligned(called_by)
+            }
+            TableColumn::Total => TableCell::new_right_aligned(duration(node.elapsed)),
+            TableColumn::Mean => {
+                let mean = if let Some(mean) = node.mean() {
+                    let warn = if mean < Duration::from_nanos(1500) { warning } else { "" };
+                    format!("{}{}", duration(mean), warn)
+                } else {
+                    missing.into()
+                };
+                TableCell::new_right_aligned(mean)
+            }
+            TableColumn::Min | TableColumn::Max => {
+                let value = if node.called == 0 {
+                    missing.into()
+                } else if *column == TableColumn::Min {
+                    duration(node.min)
+                } else {
+                    duration(node.max)
+                };
+                TableCell::new_right_aligned(value)
+            }
+            TableColumn::SelfTime => TableCell::new_right_aligned(duration(node.self_time)),
+            TableColumn::StdDev => {
+                let stddev = match node.stddev {
+                    Some(stddev) if node.called != 0 => duration(stddev),
+                    _ => missing.into(),
+                };
+                TableCell::new_right_aligned(stddev)
+            }
+            TableColumn::Throughput(key) => {
+                let throughput = match node.throughput(key) {
+                    Some(throughput) => format!("{:.2}", throughput),
+                    None => missing.into(),
+                };
+                TableCell::new_right_aligned(throughput)
+            }
+        });
+
+        table.add_row(Row::new(cells));
+    }
+
+    return table.render();
 }
@@ -0,0 +1,161 @@
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+use std::time::Duration;
+
+use crate::{CallSite, CallSiteId};
+use crate::graph::{collection_enabled, module_allowed, sample, is_count_only, CurrentSpan, LOCAL_CURRENT_SPAN};
+use crate::graph::{enter_active_span, exit_active_span, enter_depth, exit_depth, Measurement};
+
+/// An [`Instrumented`] future records the execution of an inner future as a
+/// single call to a [`CallSite`].
+///
+/// The span associated with the callsite is entered every time the inner
+/// future is polled, and exited when the poll returns. The recorded elapsed
+/// time is the total time spent polling the inner future, and does not
+/// include the time this future was waiting to be woken up. The recording
+/// happens when the inner future completes.
+///
+/// This is not usually constructed manually but with the
+/// [`instrument`](attr.instrument.html) macro applied to an `async fn`.
+pub struct Instrumented<F> {
+    inner: F,
+    callsite: &'static CallSite,
+    /// Parent span at the time this future was first polled
+    parent: Option<Option<CallSiteId>>,
+    /// Was the same span already executing when this future was first polled?
+    recursive: bool,
+    /// Sampling rate of this execution of the span, or `None` if it is not
+    /// sampled. This is decided the first time the future is polled.
+    sampling_rate: Option<Option<u32>>,
+    /// Time spent polling the inner future
+    elapsed: Duration,
+    /// Number of allocations and allocated bytes while polling the inner
+    /// future
+    #[cfg(feature = "alloc-tracking")]
+    allocations: (u64, u64),
+}
+
+impl<F: Future> Instrumented<F> {
+    /// Create a new [`Instrumented`] future, recording the execution of
+    /// `inner` with the given `callsite`.
+    pub fn new(callsite: &'static CallSite, inner: F) -> Instrumented<F> {
+        Instrumented {
+            inner: inner,
+            callsite: callsite,
+            parent: None,
+            recursive: false,
+            sampling_rate: None,
+            elapsed: Duration::new(0, 0),
+            #[cfg(feature = "alloc-tracking")]
+            allocations: (0, 0),
+        }
+    }
+}
+
+/// Exit the span with the given `callsite` and reset the current span of this
+/// thread to `parent` when dropped, even if polling the inner future panicked.
+struct ExitSpan {
+    callsite: CallSiteId,
+    parent: Option<CurrentSpan>,
+}
+
+impl Drop for ExitSpan {
+    fn drop(&mut self) {
+        LOCAL_CURRENT_SPAN.with(|current| {
+            *current.borrow_mut() = self.parent;
+        });
+        exit_active_span(self.callsite);
+        exit_depth();
+    }
+}
+
+/// Go back one level in the spans executing on the current thread when
+/// dropped, for futures too deep to be recorded.
+struct ExitDepth;
+
+impl Drop for ExitDepth {
+    fn drop(&mut self) {
+        exit_depth();
+    }
+}
+
+impl<F: Future> Future for Instrumented<F> {
+    type Output = F::Output;
+
+    fn poll(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<F::Output> {
+        // SAFETY: `inner` is never moved out of `self`, and is only accessed
+        // through a pinned reference
+        let this = unsafe { self.get_unchecked_mut() };
+        let inner = unsafe { Pin::new_unchecked(&mut this.inner) };
+
+        if !collection_enabled() || !module_allowed(this.callsite) {
+            return inner.poll(cx);
+        }
+
+        let callsite = this.callsite;
+        let sampling_rate = match *this.sampling_rate.get_or_insert_with(|| sample(callsite)) {
+            Some(rate) => rate,
+            None => return inner.poll(cx),
+        };
+
+        if !enter_depth() {
+            let _exit = ExitDepth;
+            return inner.poll(cx);
+        }
+
+        let id = this.callsite.id();
+        let before = this.elapsed;
+        let count_only = is_count_only(this.callsite);
+        let start = if count_only { 0 } else { crate::clock::now() };
+        let parent = LOCAL_CURRENT_SPAN.with(|current| current.borrow_mut().replace(CurrentSpan {
+            id: id,
+            start: start,
+            before: before,
+            sampling_rate: sampling_rate,
+            count_only: count_only,
+        }));
+        let recursive = enter_active_span(id);
+        let exit = ExitSpan { callsite: id, parent: parent };
+
+        if this.parent.is_none() {
+            this.parent = Some(parent.map(|parent| parent.id));
+            this.recursive = recursive;
+        }
+
+        #[cfg(feature = "alloc-tracking")]
+        let allocations = crate::alloc::allocations();
+        let result = inner.poll(cx);
+        if !recursive && !count_only {
+            // the time of recursive executions is already included in the time
+            // of the outermost execution
+            this.elapsed += crate::clock::elapsed(start, crate::clock::now());
+            #[cfg(feature = "alloc-tracking")]
+            {
+                let (count, bytes) = crate::alloc::allocations();
+                this.allocations.0 += count - allocations.0;
+                this.allocations.1 += bytes - allocations.1;
+            }
+        }
+
+        std::mem::drop(exit);
+
+        if result.is_ready() {
+            crate::callback::span_completed(this.callsite, this.elapsed, this.parent.flatten());
+
+            let measurement = if this.recursive || count_only {
+                None
+            } else {
+                Some(Measurement {
+                    elapsed: this.elapsed,
+                    #[cfg(feature = "alloc-tracking")]
+                    allocations: this.allocations,
+                })
+            };
+            crate::callsite::ensure_registered(this.callsite);
+            crate::graph::record_span(id, this.parent.flatten(), measurement, sampling_rate);
+        }
+
+        return result;
+    }
+}
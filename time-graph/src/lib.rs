@@ -13,9 +13,14 @@
 //! # Controlling data collection
 //!
 //! By default, no data is collected until you call [`enable_data_collection`]
-//! to start collecting timing data. Once you are done running your code, you
-//! can extract collected data with [`get_full_graph`], and possibly clear all
-//! collected data using [`clear_collected_data`].
+//! to start collecting timing data, or create a [`CollectionGuard`] with
+//! [`collect`] to collect data in a given scope. Libraries which need to
+//! enable data collection temporarily can use [`push_collection`] and
+//! [`pop_collection`], which can be nested. Tests and benchmarks can use
+//! [`CollectionState::scoped`] to make sure the previous state is restored at
+//! the end of a scope. Once you are done running your
+//! code, you can extract collected data with [`get_full_graph`], and possibly
+//! clear all collected data using [`clear_collected_data`].
 //!
 //! [`time-graph`]: https://crates.io/crates/time-graph
 //!
@@ -31,11 +36,41 @@
 //!
 //! # Crate features
 //!
-//! This crate has two cargo features:
+//! This crate has twelve cargo features:
 //!
-//! - **json**: enables json output format for the full call graph
+//! - **json**: enables json and chrome trace event output formats for the full
+//!   call graph
 //! - **table**: enables pretty-printing the full call graph to a table using
 //!   [term-table](https://crates.io/crates/term-table)
+//! - **serde**: implements `serde::Serialize` for [`FullCallGraph`], and
+//!   `serde::Serialize`/`serde::Deserialize` for [`OwnedCallGraph`], with all
+//!   durations serialized as integer nanoseconds
+//! - **histogram**: records the distribution of the elapsed time of every call
+//!   to a function/span, giving access to percentiles with
+//!   [`TimedSpan::percentile`]. Depending on the duration of the longest call,
+//!   this uses up to a few hundred kB of additional memory per recorded
+//!   function/span, and makes data collection a bit slower.
+//! - **per-thread**: keeps track of the elapsed time and number of calls of
+//!   each function/span for every thread, see [`TimedSpan::per_thread`]
+//! - **prometheus**: enables exporting the elapsed time and number of calls of
+//!   all functions/spans in the Prometheus text format
+//! - **opentelemetry**: enables exporting the full call graph to an
+//!   OpenTelemetry tracer with [`FullCallGraph::export_otel`]
+//! - **flamegraph**: enables rendering the full call graph to an SVG
+//!   flamegraph with [`FullCallGraph::as_flamegraph_svg`], using
+//!   [inferno](https://crates.io/crates/inferno)
+//! - **alloc-tracking**: records the number of memory allocations and
+//!   allocated bytes for each function/span, when `CountingAllocator` is
+//!   installed as the global allocator
+//! - **test-util**: enables the `test_util` module, with helpers to check the
+//!   recorded data in tests
+//! - **disabled**: makes [`instrument`] return functions unchanged, removing
+//!   all instrumentation at compile time. Spans created manually are still
+//!   recorded.
+//! - **std-clock**: measures time with `std::time::Instant` instead of the
+//!   [quanta](https://crates.io/crates/quanta) clock, which reads the CPU
+//!   timestamp counter (TSC) when available. This is slower, but more reliable
+//!   in virtualized environments where the TSC misbehaves.
 
 #![allow(clippy::redundant_field_names, clippy::needless_return)]
 
@@ -47,23 +82,35 @@ pub use once_cell::sync::Lazy;
 /// Create a new [`CallSite`] with the given name at the current source
 /// location.
 ///
+/// Additional `key = "value"` metadata can be attached to the call site, and
+/// retrieved later with [`CallSite::metadata`].
+///
+/// There is a single call site for each invocation of this macro in the
+/// source code, stored in a `static`. The `name` must then be a constant
+/// expression, use [`dynamic_spanned!`] to create spans with names computed at
+/// runtime.
+///
 /// # Examples
 /// ```
 /// use time_graph::{CallSite, callsite};
 ///
 /// let callsite: &'static CallSite = callsite!("here");
 /// assert_eq!(callsite.name(), "here");
+///
+/// let callsite = callsite!("there", category = "io");
+/// assert_eq!(callsite.metadata(), &[("category", "io")]);
 /// ```
 #[macro_export]
 macro_rules! callsite {
-    ($name: expr) => {
+    ($name: expr $(, $key: ident = $value: expr)* $(,)?) => {
         {
             static CALL_SITE: $crate::Lazy<$crate::CallSite> = $crate::Lazy::new(|| {
-                $crate::CallSite::new(
+                $crate::CallSite::with_metadata(
                     $name,
                     module_path!(),
                     file!(),
                     line!(),
+                    &[$((stringify!($key), $value)),*],
                 )
             });
             static REGISTRATION: $crate::Lazy<()> = $crate::Lazy::new(|| {
@@ -76,11 +123,14 @@ macro_rules! callsite {
     };
 }
 
-/// Run a block of code inside a new span
+/// Run a block of code or a single expression inside a new span
 ///
 /// This macro creates a new [`CallSite`] with the given name at the current
 /// source location, and record the provided code execution by running it inside
-/// a [`Span`].
+/// a [`Span`]. The value of the code is returned by the macro.
+///
+/// The name of the span must be a constant expression, see
+/// [`dynamic_spanned!`] to use names computed at runtime.
 ///
 /// # Examples
 /// ```
@@ -94,28 +144,172 @@ macro_rules! callsite {
 ///
 /// assert_eq!(result, 42);
 /// ```
+///
+/// Any expression can be used instead of a block:
+/// ```
+/// use time_graph::spanned;
+///
+/// fn compute(value: usize) -> usize {
+///     value * 2
+/// }
+///
+/// let result = spanned!("compute", compute(21));
+/// assert_eq!(result, 42);
+/// ```
 #[macro_export]
 macro_rules! spanned {
-    ($name: expr, $block: expr) => {
+    ($name: expr, $code: expr) => {
+        {
+            let __tfg_callsite = $crate::callsite!($name);
+            let __tfg_span = $crate::Span::new(__tfg_callsite);
+            let __tfg_guard = __tfg_span.enter();
+
+            $code
+        }
+    }
+}
+
+/// Run a block of code or a single expression inside a new span, recorded as
+/// a child of the span with the given `parent` id instead of the span
+/// currently executing on this thread.
+///
+/// `parent` should be an `Option<CallSiteId>`, usually obtained with
+/// [`current_span_id`]. See [`Span::enter_as_child_of`] for more information.
+///
+/// # Examples
+/// ```
+/// use time_graph::{spanned, spanned_under, current_span_id};
+///
+/// let parent = spanned!("parent", current_span_id());
+///
+/// let result = spanned_under!(parent, "child", {
+///     // ...
+///     42
+/// });
+/// assert_eq!(result, 42);
+/// ```
+#[macro_export]
+macro_rules! spanned_under {
+    ($parent: expr, $name: expr, $code: expr) => {
         {
             let __tfg_callsite = $crate::callsite!($name);
             let __tfg_span = $crate::Span::new(__tfg_callsite);
+            let __tfg_guard = __tfg_span.enter_as_child_of($parent);
+
+            $code
+        }
+    }
+}
+
+/// Run a block of code or a single expression inside a span with a name
+/// computed at runtime.
+///
+/// Contrary to [`spanned!`], a different call site is created for each
+/// distinct name. This requires locking a global mutex every time the macro
+/// runs, and call sites are never deallocated, so the number of distinct names
+/// should stay small.
+///
+/// # Examples
+/// ```
+/// use time_graph::dynamic_spanned;
+///
+/// for i in 0..3 {
+///     dynamic_spanned!(format!("iteration {}", i), {
+///         // ...
+///     });
+/// }
+/// ```
+#[macro_export]
+macro_rules! dynamic_spanned {
+    ($name: expr, $code: expr) => {
+        {
+            static BASE_CALL_SITE: $crate::Lazy<$crate::CallSite> = $crate::Lazy::new(|| {
+                $crate::CallSite::with_metadata("", module_path!(), file!(), line!(), &[])
+            });
+            let __tfg_callsite = $crate::dynamic_callsite(
+                &*BASE_CALL_SITE,
+                ::std::convert::AsRef::<str>::as_ref(&$name),
+            );
+            let __tfg_span = $crate::Span::new(__tfg_callsite);
             let __tfg_guard = __tfg_span.enter();
 
-            $block
+            $code
         }
     }
 }
 
+/// Run a block of code or a single expression inside a new span, and return
+/// both the value of the code and the time it took to run.
+///
+/// This works like [`spanned!`], but the duration is always measured, even if
+/// data collection is disabled. See [`Span::measure`] for more information.
+/// The code runs inside a closure, so `return` and `?` can not be used to exit
+/// the surrounding function.
+///
+/// # Examples
+/// ```
+/// use std::time::Duration;
+/// use time_graph::timed;
+///
+/// let (result, elapsed) = timed!("sleep", {
+///     std::thread::sleep(Duration::from_millis(2));
+///     42
+/// });
+///
+/// assert_eq!(result, 42);
+/// assert!(elapsed >= Duration::from_millis(2));
+/// ```
+#[macro_export]
+macro_rules! timed {
+    ($name: expr, $code: expr) => {
+        $crate::Span::new($crate::callsite!($name)).measure(|| $code)
+    }
+}
+
 mod callsite;
-pub use self::callsite::CallSite;
-pub(crate) use self::callsite::CallSiteId;
-pub use self::callsite::{register_callsite, traverse_registered_callsite};
+pub use self::callsite::{CallSite, NameStyle, Level};
+pub use self::callsite::CallSiteId;
+pub use self::callsite::{register_callsite, dynamic_callsite, traverse_registered_callsite, reset_registry};
+pub use self::callsite::short_type_name;
+pub use self::callsite::{registered_callsite_count, collect_callsites, collect_callsites_sorted};
+
+#[cfg(feature = "alloc-tracking")]
+mod alloc;
+#[cfg(feature = "alloc-tracking")]
+pub use self::alloc::CountingAllocator;
+
+mod callback;
+pub use self::callback::{on_span_complete, remove_span_complete_callback};
+
+mod clock;
+pub use self::clock::{TimeSource, set_clock, reset_clock};
 
 mod graph;
-pub use self::graph::{Span, SpanGuard};
-pub use self::graph::{get_full_graph, clear_collected_data, enable_data_collection};
-pub use self::graph::{FullCallGraph, TimedSpan};
+pub use self::graph::{Span, SpanBuilder, SpanGuard, current_span_id, current_span_elapsed, set_max_recording_depth, set_module_filter, set_sampling, set_count_only, record_metric, spawn};
+pub use self::graph::{get_full_graph, snapshot_totals, clear_collected_data, enable_data_collection, is_data_collection_enabled};
+pub use self::graph::{clear_callsite, clear_callsite_by_name, reset_timings};
+pub use self::graph::{collect, CollectionGuard, CollectionState, push_collection, pop_collection};
+pub use self::graph::{FullCallGraph, TimedSpan, Calls, CyclicGraphError};
+pub use self::graph::{GraphSummary, SpanSummary, SpanDiff, SpanDiffKind};
+pub use self::graph::DurationUnit;
+#[cfg(feature = "table")]
+pub use self::graph::{TableOptions, TableColumn};
+
+mod instrumented;
+pub use self::instrumented::Instrumented;
+
+mod owned;
+pub use self::owned::{OwnedCallGraph, OwnedSpan};
+
+mod binary;
+
+#[cfg(feature = "serde")]
+mod serialization;
+
+#[cfg(feature = "test-util")]
+pub mod test_util;
 
 #[cfg(feature = "table")]
 mod table;
+#[cfg(feature = "table")]
+pub use self::table::TableStyle;
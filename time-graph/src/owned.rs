@@ -0,0 +1,291 @@
+use std::time::Duration;
+
+use petgraph::graph::Graph;
+
+use crate::{FullCallGraph, TimedSpan, Calls, NameStyle};
+use crate::graph::{CallEdge, module_matches};
+#[cfg(feature = "table")]
+use crate::TableOptions;
+
+/// [`OwnedSpan`] contains the same data as a [`TimedSpan`], but owns the
+/// metadata of its call site instead of referencing a [`crate::CallSite`].
+///
+/// This allows to create spans for call sites which are not part of the
+/// current executable, for example when loading data saved by a previous run.
+#[derive(Debug, Clone)]
+pub struct OwnedSpan {
+    /// Unique identifier of this function/span in the call graph
+    pub id: usize,
+    /// The name of the call site
+    pub name: String,
+    /// The name of the Rust module where the call site occurred
+    pub module_path: String,
+    /// The name of the source code file where the call site occurred
+    pub file: String,
+    /// The line number in the source code file where the call site occurred
+    pub line: u32,
+    /// Total elapsed time inside this function/span
+    pub elapsed: Duration,
+    /// Number of times this function/span have been called
+    pub called: u32,
+    /// Shortest elapsed time for a single call to this function/span
+    pub min: Duration,
+    /// Longest elapsed time for a single call to this function/span
+    pub max: Duration,
+    /// Elapsed time inside this function/span, excluding the time spent in
+    /// the functions/spans it called. See [`TimedSpan::self_time`].
+    pub self_time: Duration,
+}
+
+impl OwnedSpan {
+    /// Get the full name of this span, containing both the name and the
+    /// module path.
+    pub fn full_name(&self) -> String {
+        crate::callsite::full_name(&self.module_path, &self.name)
+    }
+
+    /// Get the name of this span, displayed with the given `style`.
+    pub fn display_name(&self, style: NameStyle) -> String {
+        crate::callsite::display_name(&self.module_path, &self.name, style)
+    }
+}
+
+impl From<&TimedSpan> for OwnedSpan {
+    fn from(span: &TimedSpan) -> OwnedSpan {
+        OwnedSpan {
+            id: span.id,
+            name: span.callsite.name().to_owned(),
+            module_path: span.callsite.module_path().to_owned(),
+            file: span.callsite.file().to_owned(),
+            line: span.callsite.line(),
+            elapsed: span.elapsed,
+            called: span.called,
+            min: span.min,
+            max: span.max,
+            self_time: span.self_time,
+        }
+    }
+}
+
+/// Call graph owning all its data, which can be created from a
+/// [`FullCallGraph`] or loaded from a previously saved graph.
+///
+/// With the `"serde"` cargo feature, this type can be deserialized from the
+/// output of serializing a [`FullCallGraph`].
+pub struct OwnedCallGraph {
+    graph: Graph<OwnedSpan, CallEdge>
+}
+
+impl OwnedCallGraph {
+    /// Create a new graph containing the given `spans`, connected by the
+    /// given `calls`. This function returns `None` if some of the calls
+    /// refer to a span id which is not part of `spans`.
+    pub fn new(spans: Vec<OwnedSpan>, calls: Vec<Calls>) -> Option<OwnedCallGraph> {
+        let mut graph = Graph::new();
+
+        let mut nodes = std::collections::BTreeMap::new();
+        for span in spans {
+            let id = span.id;
+            nodes.insert(id, graph.add_node(span));
+        }
+
+        for call in calls {
+            let caller = *nodes.get(&call.caller)?;
+            let callee = *nodes.get(&call.callee)?;
+            graph.add_edge(caller, callee, CallEdge {
+                count: call.count,
+                elapsed: call.elapsed,
+            });
+        }
+
+        return Some(OwnedCallGraph { graph: graph });
+    }
+
+    /// Get the full list of spans/functions known by this graph
+    pub fn spans(&self) -> impl Iterator<Item = &OwnedSpan> {
+        self.graph.raw_nodes().iter().map(|node| &node.weight)
+    }
+
+    /// Get the list of calls between spans in this graph
+    pub fn calls(&self) -> impl Iterator<Item = Calls> + '_ {
+        self.graph.raw_edges().iter().map(move |edge| Calls {
+            caller: self.graph[edge.source()].id,
+            callee: self.graph[edge.target()].id,
+            count: edge.weight.count,
+            elapsed: edge.weight.elapsed,
+        })
+    }
+
+    /// Collapse all the spans defined in the module `prefix` or one of its
+    /// submodules into a single aggregated span, named `*` and with `prefix`
+    /// as module path.
+    ///
+    /// The aggregated span sums the elapsed time, self time and number of
+    /// calls of all the grouped spans, and calls from and to the grouped spans
+    /// are redirected to it. Calls between two grouped spans are removed.
+    pub fn group_by_module(&self, prefix: &str) -> OwnedCallGraph {
+        return self.group_spans(|span| {
+            if module_matches(&span.module_path, prefix) {
+                Some(("*".into(), prefix.trim_end_matches("::").into()))
+            } else {
+                None
+            }
+        });
+    }
+
+    /// Collapse all the spans whose name contains `separator` into aggregated
+    /// spans, one for each distinct prefix of the names up to the last
+    /// separator. For example, with `'.'` as separator, `db.query.plan` and
+    /// `db.query.run` are grouped in a `db.query` span, while `db.connect` is
+    /// grouped in a `db` span. Spans without separator in their name are kept
+    /// as-is.
+    ///
+    /// This only uses the name of the spans, and groups spans defined in
+    /// different modules together. The aggregated spans use the module path of
+    /// the first span in the group. The data of the aggregated spans and the
+    /// calls between them is computed as in
+    /// [`OwnedCallGraph::group_by_module`], and calls inside a group are
+    /// removed.
+    pub fn group_by_name_prefix(&self, separator: char) -> OwnedCallGraph {
+        return self.group_spans(|span| {
+            let (prefix, _) = span.name.rsplit_once(separator)?;
+            Some((prefix.into(), span.module_path.clone()))
+        });
+    }
+
+    /// Collapse spans into aggregated spans. The `group` function gives the
+    /// name and module path of the aggregated span for each span, or `None`
+    /// if the span should be kept as-is. Spans are grouped by name.
+    fn group_spans(&self, group: impl Fn(&OwnedSpan) -> Option<(String, String)>) -> OwnedCallGraph {
+        let mut spans = Vec::new();
+        // index of the aggregated span for each grouped span id
+        let mut span_groups = std::collections::HashMap::new();
+        let mut groups: Vec<OwnedSpan> = Vec::new();
+        for span in self.spans() {
+            let (name, module_path) = if let Some(group) = group(span) {
+                group
+            } else {
+                spans.push(span.clone());
+                continue;
+            };
+
+            if let Some(index) = groups.iter().position(|group| group.name == name) {
+                let group = &mut groups[index];
+                if group.called == 0 {
+                    group.min = span.min;
+                    group.max = span.max;
+                } else if span.called != 0 {
+                    group.min = group.min.min(span.min);
+                    group.max = group.max.max(span.max);
+                }
+                group.elapsed += span.elapsed;
+                group.self_time += span.self_time;
+                group.called += span.called;
+                span_groups.insert(span.id, index);
+            } else {
+                span_groups.insert(span.id, groups.len());
+                groups.push(OwnedSpan {
+                    id: span.id,
+                    name: name,
+                    module_path: module_path,
+                    file: String::new(),
+                    line: 0,
+                    ..span.clone()
+                });
+            }
+        }
+
+        let group_id = |span: &OwnedSpan| span_groups.get(&span.id).map(|&index| groups[index].id);
+
+        let mut calls: Vec<Calls> = Vec::new();
+        for edge in self.graph.raw_edges() {
+            let caller = &self.graph[edge.source()];
+            let callee = &self.graph[edge.target()];
+            let caller = group_id(caller).unwrap_or(caller.id);
+            let callee = match group_id(callee) {
+                Some(group) if group == caller => continue,
+                Some(group) => group,
+                None => callee.id,
+            };
+
+            if let Some(call) = calls.iter_mut().find(|call| call.caller == caller && call.callee == callee) {
+                call.count += edge.weight.count;
+                call.elapsed += edge.weight.elapsed;
+            } else {
+                calls.push(Calls {
+                    caller: caller,
+                    callee: callee,
+                    count: edge.weight.count,
+                    elapsed: edge.weight.elapsed,
+                });
+            }
+        }
+
+        spans.extend(groups);
+        return OwnedCallGraph::new(spans, calls).expect("invalid call graph");
+    }
+
+    /// Get a per span summary table of this graph.
+    ///
+    /// The exact output is unstable and should not be relied on.
+    ///
+    /// This function is only available if the `"table"` cargo feature is enabled
+    #[cfg(feature = "table")]
+    pub fn as_table(&self) -> String {
+        self.as_table_with(TableOptions::default())
+    }
+
+    /// Get a compact per span summary table of this graph, using the short
+    /// names of the spans, without the `called by`, `min` and `max` columns,
+    /// and sorted by decreasing total elapsed time.
+    ///
+    /// The exact output is unstable and should not be relied on.
+    ///
+    /// This function is only available if the `"table"` cargo feature is enabled
+    #[cfg(feature = "table")]
+    pub fn as_short_table(&self) -> String {
+        self.as_table_with(TableOptions::short())
+    }
+
+    /// Same as `as_table`, displaying the names of the spans with the given
+    /// `style`.
+    #[cfg(feature = "table")]
+    pub fn as_table_with_style(&self, style: NameStyle) -> String {
+        self.as_table_with(TableOptions {
+            name_style: style,
+            ..TableOptions::default()
+        })
+    }
+
+    /// Get a per span summary table of this graph, with the columns and
+    /// ordering of rows specified by `options`.
+    ///
+    /// This function is only available if the `"table"` cargo feature is enabled
+    #[cfg(feature = "table")]
+    pub fn as_table_with(&self, options: TableOptions) -> String {
+        let graph = self.graph.map(|_, span| {
+            crate::graph::TableSpan {
+                id: span.id,
+                name: crate::callsite::sanitize(&span.display_name(options.name_style)).into_owned(),
+                called: span.called,
+                elapsed: span.elapsed,
+                min: span.min,
+                max: span.max,
+                self_time: span.self_time,
+                // the variance and metrics are not kept in owned graphs
+                stddev: None,
+                metrics: std::collections::BTreeMap::new(),
+            }
+        }, |_, call| call.count);
+
+        return crate::graph::render_table(&graph, &options);
+    }
+}
+
+impl From<&FullCallGraph> for OwnedCallGraph {
+    fn from(graph: &FullCallGraph) -> OwnedCallGraph {
+        let spans = graph.spans().map(|span| graph.owned_span(span)).collect();
+        let calls = graph.calls().collect();
+        return OwnedCallGraph::new(spans, calls).expect("invalid call graph");
+    }
+}
@@ -0,0 +1,146 @@
+use std::time::Duration;
+
+use serde::{Serialize, Serializer, Deserialize, Deserializer};
+use serde::ser::SerializeStruct;
+
+use crate::{FullCallGraph, TimedSpan, Calls};
+use crate::{OwnedCallGraph, OwnedSpan};
+
+impl Serialize for TimedSpan {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        OwnedSpan::from(self).serialize(serializer)
+    }
+}
+
+/// Serialized representation of a span, shared by [`TimedSpan`] and
+/// [`OwnedSpan`]
+#[derive(Serialize, Deserialize)]
+struct SerializedSpan {
+    id: usize,
+    name: String,
+    module_path: String,
+    file: String,
+    line: u32,
+    elapsed_ns: u128,
+    called: u32,
+    min_ns: u128,
+    max_ns: u128,
+    self_time_ns: u128,
+}
+
+fn duration_from_nanos(nanos: u128) -> Duration {
+    let secs = (nanos / 1_000_000_000) as u64;
+    let nanos = (nanos % 1_000_000_000) as u32;
+    Duration::new(secs, nanos)
+}
+
+impl Serialize for OwnedSpan {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerializedSpan {
+            id: self.id,
+            name: self.name.clone(),
+            module_path: self.module_path.clone(),
+            file: self.file.clone(),
+            line: self.line,
+            elapsed_ns: self.elapsed.as_nanos(),
+            called: self.called,
+            min_ns: self.min.as_nanos(),
+            max_ns: self.max.as_nanos(),
+            self_time_ns: self.self_time.as_nanos(),
+        }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for OwnedSpan {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let span = SerializedSpan::deserialize(deserializer)?;
+        Ok(OwnedSpan {
+            id: span.id,
+            name: span.name,
+            module_path: span.module_path,
+            file: span.file,
+            line: span.line,
+            elapsed: duration_from_nanos(span.elapsed_ns),
+            called: span.called,
+            min: duration_from_nanos(span.min_ns),
+            max: duration_from_nanos(span.max_ns),
+            self_time: duration_from_nanos(span.self_time_ns),
+        })
+    }
+}
+
+/// Serialize an iterator as a sequence
+struct Sequence<F>(F);
+
+impl<F, I> Serialize for Sequence<F> where F: Fn() -> I, I: Iterator, I::Item: Serialize {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_seq((self.0)())
+    }
+}
+
+/// Serialized representation of [`Calls`]
+#[derive(Serialize, Deserialize)]
+struct SerializedCalls {
+    caller: usize,
+    callee: usize,
+    count: usize,
+    // graphs serialized before this field was added do not contain it
+    #[serde(default)]
+    elapsed_ns: u128,
+}
+
+impl Serialize for Calls {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        SerializedCalls {
+            caller: self.caller,
+            callee: self.callee,
+            count: self.count,
+            elapsed_ns: self.elapsed.as_nanos(),
+        }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Calls {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let calls = SerializedCalls::deserialize(deserializer)?;
+        Ok(Calls {
+            caller: calls.caller,
+            callee: calls.callee,
+            count: calls.count,
+            elapsed: duration_from_nanos(calls.elapsed_ns),
+        })
+    }
+}
+
+impl Serialize for FullCallGraph {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut graph = serializer.serialize_struct("FullCallGraph", 2)?;
+        graph.serialize_field("spans", &Sequence(|| self.spans().map(|span| self.owned_span(span))))?;
+        graph.serialize_field("calls", &Sequence(|| self.calls()))?;
+        graph.end()
+    }
+}
+
+impl Serialize for OwnedCallGraph {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut graph = serializer.serialize_struct("OwnedCallGraph", 2)?;
+        graph.serialize_field("spans", &Sequence(|| self.spans()))?;
+        graph.serialize_field("calls", &Sequence(|| self.calls()))?;
+        graph.end()
+    }
+}
+
+impl<'de> Deserialize<'de> for OwnedCallGraph {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        #[derive(Deserialize)]
+        struct SerializedGraph {
+            spans: Vec<OwnedSpan>,
+            calls: Vec<Calls>,
+        }
+
+        let graph = SerializedGraph::deserialize(deserializer)?;
+        OwnedCallGraph::new(graph.spans, graph.calls).ok_or_else(|| {
+            serde::de::Error::custom("calls refer to a span which does not exist")
+        })
+    }
+}
@@ -29,7 +29,7 @@ pub enum RowPosition {
 ///# Example
 ///
 ///```
-/// term_table::TableStyle {
+/// time_graph::TableStyle {
 ///     top_left_corner: '╔',
 ///     top_right_corner: '╗',
 ///     bottom_left_corner: '╚',
@@ -91,6 +91,50 @@ impl TableStyle {
         }
     }
 
+    /// Table style using only ASCII characters
+    ///
+    ///# Example
+    ///
+    ///<pre>
+    /// +---------------------------+----------------------------+
+    /// | This is left aligned text | This is right aligned text |
+    /// +---------------------------+----------------------------+
+    /// | This is left aligned text | This is right aligned text |
+    /// +---------------------------+----------------------------+
+    ///</pre>
+    pub fn simple() -> TableStyle {
+        TableStyle {
+            top_left_corner: '+',
+            top_right_corner: '+',
+            bottom_left_corner: '+',
+            bottom_right_corner: '+',
+            outer_left_vertical: '+',
+            outer_right_vertical: '+',
+            outer_bottom_horizontal: '+',
+            outer_top_horizontal: '+',
+            intersection: '+',
+            vertical: '|',
+            horizontal: '-',
+        }
+    }
+
+    /// Does this style only use ASCII characters?
+    pub(crate) fn is_ascii(&self) -> bool {
+        [
+            self.top_left_corner,
+            self.top_right_corner,
+            self.bottom_left_corner,
+            self.bottom_right_corner,
+            self.outer_left_vertical,
+            self.outer_right_vertical,
+            self.outer_bottom_horizontal,
+            self.outer_top_horizontal,
+            self.intersection,
+            self.vertical,
+            self.horizontal,
+        ].iter().all(char::is_ascii)
+    }
+
     /// Returns the start character of a table style based on the
     /// vertical position of the row
     fn start_for_position(&self, pos: RowPosition) -> char {
@@ -149,6 +193,9 @@ pub struct Table {
     pub max_column_width: usize,
     /// The maximum widths of specific columns. Override max_column
     pub max_column_widths: HashMap<usize, usize>,
+    /// The minimum widths of specific columns, used to pad narrow columns.
+    /// This is ignored if it is larger than the maximum width of the column.
+    pub min_column_widths: HashMap<usize, usize>,
     /// Whether or not to vertically separate rows in the table
     pub separate_rows: bool,
     /// Whether the table should have a top boarder.
@@ -165,6 +212,7 @@ impl Table {
             style: TableStyle::extended(),
             max_column_width: usize::MAX,
             max_column_widths: HashMap::new(),
+            min_column_widths: HashMap::new(),
             separate_rows: true,
             has_top_boarder: true,
             has_bottom_boarder: true,
@@ -248,6 +296,13 @@ impl Table {
             }
         }
 
+        for (&column, &min_width) in &self.min_column_widths {
+            if column < num_columns {
+                let max_width = *self.max_column_widths.get(&column).unwrap_or(&self.max_column_width);
+                max_widths[column] = max(max_widths[column], min(min_width, max_width));
+            }
+        }
+
         // Here we are dealing with the case where we have a cell that is center
         // aligned but the max_width doesn't allow for even padding on either side
         for row in &self.rows {
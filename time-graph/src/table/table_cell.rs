@@ -1,5 +1,6 @@
 use std::cmp;
 
+use unicode_segmentation::UnicodeSegmentation;
 use unicode_width::UnicodeWidthChar;
 use unicode_width::UnicodeWidthStr;
 
@@ -70,8 +71,8 @@ impl TableCell {
     /// The minium width required to display the cell properly
     pub fn min_width(&self) -> usize {
         let mut max_char_width: usize = 0;
-        for c in self.data.chars() {
-            max_char_width = cmp::max(max_char_width, c.width().unwrap_or(1));
+        for grapheme in self.data.graphemes(true) {
+            max_char_width = cmp::max(max_char_width, grapheme.width());
         }
 
         if self.pad_content {
@@ -83,24 +84,38 @@ impl TableCell {
 
     /// Wraps the cell's content to the provided width.
     ///
-    /// New line characters are taken into account.
+    /// The content is wrapped on grapheme cluster boundaries, so characters
+    /// made of multiple code points are never split across lines.
+    ///
+    /// New line characters are taken into account. If `width` is 0, the
+    /// content is not wrapped. If `width` is too small to fit a single
+    /// character and the padding, each character is put on its own line.
     pub fn wrapped_content(&self, width: usize) -> Vec<String> {
         let pad_char = if self.pad_content { ' ' } else { '\0' };
+        let pad_width = pad_char.width().unwrap_or(1);
+
+        let max_width = if width == 0 {
+            usize::MAX
+        } else {
+            // always leave space for at least one character after the padding
+            cmp::max(width.saturating_sub(pad_width), pad_width + 1)
+        };
 
         let mut res: Vec<String> = Vec::new();
         let mut buf = String::new();
         buf.push(pad_char);
-        for c in self.data.chars() {
-            if buf.width() >= width - pad_char.width().unwrap_or(1) || c == '\n' {
+        for grapheme in self.data.graphemes(true) {
+            let newline = grapheme == "\n" || grapheme == "\r\n";
+            if newline || (buf.width() + grapheme.width() > max_width && buf.width() > pad_width) {
                 buf.push(pad_char);
                 res.push(buf);
                 buf = String::new();
                 buf.push(pad_char);
-                if c == '\n' {
+                if newline {
                     continue;
                 }
             }
-            buf.push(c);
+            buf.push_str(grapheme);
         }
         buf.push(pad_char);
         res.push(buf);
@@ -117,3 +132,42 @@ where
         TableCell::new(other)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::TableCell;
+
+    #[test]
+    fn wrapped_content_narrow_width() {
+        let cell = TableCell::new("abc");
+
+        assert_eq!(cell.wrapped_content(0), [" abc "]);
+        assert_eq!(cell.wrapped_content(1), [" a ", " b ", " c "]);
+        assert_eq!(cell.wrapped_content(5), [" abc "]);
+
+        let mut cell = TableCell::new("abc");
+        cell.pad_content = false;
+        assert_eq!(cell.wrapped_content(0).len(), 1);
+        assert_eq!(cell.wrapped_content(1).len(), 3);
+    }
+
+    #[test]
+    fn wrapped_content_graphemes() {
+        let family = "\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}";
+        let cell = TableCell::new(format!("{0}{0}{0}", family));
+        let wrapped = cell.wrapped_content(4);
+        assert_eq!(wrapped.len(), 3);
+        for line in wrapped {
+            assert_eq!(line, format!(" {} ", family));
+        }
+
+        let accented = "e\u{301}";
+        let cell = TableCell::new(accented.repeat(4));
+        let wrapped = cell.wrapped_content(3);
+        assert_eq!(wrapped.len(), 4);
+        for line in wrapped {
+            assert_eq!(line, format!(" {} ", accented));
+        }
+        assert_eq!(cell.width(), 6);
+    }
+}
@@ -0,0 +1,65 @@
+//! Helpers to check the data recorded by this crate in tests.
+//!
+//! All functions in this module panic with a message containing the recorded
+//! values when the check fails.
+//!
+//! # Examples
+//! ```
+//! use std::time::Duration;
+//! use time_graph::test_util::{assert_span_called, assert_span_elapsed};
+//!
+//! #[time_graph::instrument]
+//! fn sleep() {
+//!     std::thread::sleep(Duration::from_millis(2));
+//! }
+//!
+//! time_graph::enable_data_collection(true);
+//! sleep();
+//! sleep();
+//!
+//! let graph = time_graph::get_full_graph();
+//! assert_span_called(&graph, "sleep", 2);
+//! assert_span_elapsed(&graph, "sleep", Duration::from_millis(4), Duration::from_secs(1));
+//! ```
+
+use std::time::Duration;
+
+use crate::{FullCallGraph, TimedSpan};
+
+/// Get the span with the given `name` in the `graph`, panicking with the list
+/// of known spans if it does not exist.
+#[track_caller]
+fn find_span<'a>(graph: &'a FullCallGraph, name: &str) -> &'a TimedSpan {
+    if let Some(span) = graph.span_by_name(name) {
+        return span;
+    }
+
+    let names = graph.spans().map(|span| span.callsite.name()).collect::<Vec<_>>();
+    panic!("could not find a span named '{}' in the graph, available spans are {:?}", name, names);
+}
+
+/// Check that the span/function with the given `name` was called exactly
+/// `expected` times.
+#[track_caller]
+pub fn assert_span_called(graph: &FullCallGraph, name: &str, expected: u32) {
+    let span = find_span(graph, name);
+    if span.called != expected {
+        panic!(
+            "expected span '{}' to be called {} times, but it was called {} times",
+            name, expected, span.called
+        );
+    }
+}
+
+/// Check that the total elapsed time of the span/function with the given
+/// `name` is between `min` and `max` (inclusive).
+#[track_caller]
+pub fn assert_span_elapsed(graph: &FullCallGraph, name: &str, min: Duration, max: Duration) {
+    let span = find_span(graph, name);
+    if span.elapsed < min || span.elapsed > max {
+        panic!(
+            "expected span '{}' to take between {:?} and {:?}, but it took {:?} (over {} calls)",
+            name, min, max, span.elapsed, span.called
+        );
+    }
+}
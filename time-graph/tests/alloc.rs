@@ -0,0 +1,45 @@
+#![cfg(feature = "alloc-tracking")]
+
+use time_graph::CountingAllocator;
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator::new(std::alloc::System);
+
+#[time_graph::instrument]
+fn allocate() -> usize {
+    let data = std::hint::black_box(vec![0u8; 1024]);
+    data.len()
+}
+
+#[time_graph::instrument]
+fn outer() -> usize {
+    allocate() + allocate()
+}
+
+#[time_graph::instrument]
+fn no_allocation() -> usize {
+    std::hint::black_box(42)
+}
+
+#[test]
+fn allocations() {
+    time_graph::enable_data_collection(true);
+    outer();
+    no_allocation();
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+
+    let allocate = graph.span_by_name("allocate").unwrap();
+    assert_eq!(allocate.called, 2);
+    assert!(allocate.allocations >= 2);
+    assert!(allocate.bytes_allocated >= 2048);
+
+    let outer = graph.span_by_name("outer").unwrap();
+    assert!(outer.allocations >= allocate.allocations);
+    assert!(outer.bytes_allocated >= allocate.bytes_allocated);
+
+    let no_allocation = graph.span_by_name("no_allocation").unwrap();
+    assert_eq!(no_allocation.allocations, 0);
+    assert_eq!(no_allocation.bytes_allocated, 0);
+}
@@ -0,0 +1,26 @@
+#![cfg(feature = "alloc-tracking")]
+
+use time_graph::{CountingAllocator, SpanBuilder};
+
+#[global_allocator]
+static ALLOCATOR: CountingAllocator = CountingAllocator::new(std::alloc::System);
+
+#[test]
+fn alloc_cross_thread() {
+    time_graph::enable_data_collection(true);
+
+    // make sure this thread allocated more than the thread dropping the guard
+    for _ in 0..100 {
+        std::hint::black_box(vec![0u8; 1024]);
+    }
+
+    let guard = SpanBuilder::new("moved").enter();
+    std::thread::spawn(move || {
+        std::mem::drop(guard);
+    }).join().unwrap();
+
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+    assert_eq!(graph.span_by_name("moved").unwrap().called, 1);
+}
@@ -0,0 +1,56 @@
+use std::time::Duration;
+
+#[time_graph::instrument]
+async fn parent() -> Result<usize, std::num::ParseIntError> {
+    let value = child().await?;
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    Ok(value)
+}
+
+#[time_graph::instrument]
+async fn child() -> Result<usize, std::num::ParseIntError> {
+    tokio::time::sleep(Duration::from_millis(10)).await;
+    let value = "42".parse::<usize>()?;
+    // busy wait to make sure the time spent polling is measurable
+    let start = std::time::Instant::now();
+    while start.elapsed() < Duration::from_millis(2) {}
+    Ok(value)
+}
+
+#[test]
+fn async_functions() {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .enable_time()
+        .build()
+        .unwrap();
+
+    time_graph::enable_data_collection(true);
+    let value = runtime.block_on(async {
+        let value = parent().await.unwrap();
+        // the current span should be reset after the instrumented future
+        // completes
+        child().await.unwrap();
+        value
+    });
+    time_graph::enable_data_collection(false);
+    assert_eq!(value, 42);
+
+    let graph = time_graph::get_full_graph();
+    let parent = graph.span_by_name("parent").unwrap();
+    let child = graph.span_by_name("child").unwrap();
+
+    assert_eq!(parent.called, 1);
+    assert_eq!(child.called, 2);
+
+    assert!(child.min >= Duration::from_millis(2));
+    // time spent waiting for the timers is not included
+    assert!(child.max < Duration::from_millis(10));
+    assert!(parent.elapsed >= child.min);
+    assert!(parent.elapsed < Duration::from_millis(20));
+
+    let calls = graph.calls().collect::<Vec<_>>();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].caller, parent.id);
+    assert_eq!(calls[0].callee, child.id);
+    assert_eq!(calls[0].count, 1);
+}
@@ -29,4 +29,19 @@ fn check_created_callsite() {
     });
     let expected = ["do_nothing", "renamed", "even with spaces"].iter().cloned().collect::<HashSet<_>>();
     assert_eq!(names, expected);
+
+    time_graph::enable_data_collection(true);
+    do_nothing();
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+    let span = graph.span_by_name("renamed").unwrap();
+    assert_eq!(span.callsite.full_name(), "attribute::renamed");
+    assert!(graph.span_by_name("named").is_none());
+    assert_eq!(graph.spans_by_name("do_nothing").count(), 1);
+
+    let span = graph.span_by_full_name("attribute::{even with spaces}").unwrap();
+    assert_eq!(span.callsite.name(), "even with spaces");
+    assert_eq!(span.called, 1);
+    assert!(graph.span_by_full_name("even with spaces").is_none());
 }
@@ -0,0 +1,52 @@
+use time_graph::OwnedCallGraph;
+
+#[time_graph::instrument]
+fn parent() {
+    child();
+    child();
+}
+
+#[time_graph::instrument(name = "child with spaces")]
+fn child() {
+    std::thread::sleep(std::time::Duration::from_millis(1));
+}
+
+#[test]
+fn binary() {
+    time_graph::enable_data_collection(true);
+    parent();
+
+    let graph = time_graph::get_full_graph();
+    let bytes = graph.to_bytes();
+    let decoded = OwnedCallGraph::from_bytes(&bytes).unwrap();
+
+    assert_eq!(decoded.spans().count(), graph.spans().count());
+    assert_eq!(decoded.calls().count(), graph.calls().count());
+
+    for span in graph.spans() {
+        let decoded = decoded.spans().find(|decoded| decoded.id == span.id).unwrap();
+        assert_eq!(decoded.name, span.callsite.name());
+        assert_eq!(decoded.module_path, span.callsite.module_path());
+        assert_eq!(decoded.file, span.callsite.file());
+        assert_eq!(decoded.line, span.callsite.line());
+        assert_eq!(decoded.called, span.called);
+        assert_eq!(decoded.elapsed, span.elapsed);
+        assert_eq!(decoded.min, span.min);
+        assert_eq!(decoded.max, span.max);
+        assert_eq!(decoded.self_time, span.self_time);
+    }
+
+    for call in graph.calls() {
+        let decoded = decoded.calls().find(|decoded| decoded.caller == call.caller && decoded.callee == call.callee).unwrap();
+        assert_eq!(decoded.count, call.count);
+        assert_eq!(decoded.elapsed, call.elapsed);
+    }
+
+    // re-encoding an owned graph gives the same bytes
+    assert_eq!(decoded.to_bytes(), bytes);
+
+    // invalid data
+    assert!(OwnedCallGraph::from_bytes(&[]).is_none());
+    assert!(OwnedCallGraph::from_bytes(b"not a graph").is_none());
+    assert!(OwnedCallGraph::from_bytes(&bytes[..bytes.len() - 1]).is_none());
+}
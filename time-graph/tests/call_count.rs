@@ -0,0 +1,26 @@
+mod common;
+
+use common::run_computation;
+
+#[test]
+fn call_count() {
+    time_graph::enable_data_collection(true);
+    run_computation(10);
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+    assert_eq!(graph.call_count_by_name("run_computation", "compute"), 10);
+    assert_eq!(graph.call_count_by_name("run_computation", "bottom_5us"), 100);
+    assert_eq!(graph.call_count_by_name("compute", "bottom_5us"), 45);
+    assert_eq!(graph.call_count_by_name("another span", "bottom_5us"), 1);
+
+    // no direct calls, or calls in the other direction
+    assert_eq!(graph.call_count_by_name("run_computation", "unknown"), 0);
+    assert_eq!(graph.call_count_by_name("compute", "run_computation"), 0);
+
+    let run_computation = graph.span_by_name("run_computation").unwrap();
+    let compute = graph.span_by_name("compute").unwrap();
+    assert_eq!(graph.call_count(run_computation.id, compute.id), 10);
+    assert_eq!(graph.call_count(compute.id, run_computation.id), 0);
+    assert_eq!(graph.call_count(run_computation.id, 1000), 0);
+}
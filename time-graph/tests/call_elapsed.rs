@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+#[time_graph::instrument]
+fn child(duration: Duration) {
+    std::thread::sleep(duration);
+}
+
+#[time_graph::instrument]
+fn fast_parent() {
+    child(Duration::from_millis(2));
+}
+
+#[time_graph::instrument]
+fn slow_parent() {
+    child(Duration::from_millis(20));
+    child(Duration::from_millis(20));
+}
+
+#[test]
+fn call_elapsed() {
+    time_graph::enable_data_collection(true);
+
+    fast_parent();
+    slow_parent();
+
+    let graph = time_graph::get_full_graph();
+    let child = graph.span_by_name("child").unwrap();
+    let fast = graph.span_by_name("fast_parent").unwrap();
+    let slow = graph.span_by_name("slow_parent").unwrap();
+
+    let from_fast = graph.calls().find(|call| call.caller == fast.id && call.callee == child.id).unwrap();
+    let from_slow = graph.calls().find(|call| call.caller == slow.id && call.callee == child.id).unwrap();
+
+    assert_eq!(from_fast.count, 1);
+    assert_eq!(from_slow.count, 2);
+
+    assert!(from_fast.elapsed >= Duration::from_millis(2));
+    assert!(from_fast.elapsed <= fast.elapsed);
+    assert!(from_slow.elapsed >= Duration::from_millis(40));
+    assert!(from_slow.elapsed <= slow.elapsed);
+
+    // all the time of the child is attributed to one of the parents
+    assert_eq!(from_fast.elapsed + from_slow.elapsed, child.elapsed);
+}
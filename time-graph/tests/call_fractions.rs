@@ -0,0 +1,45 @@
+#[time_graph::instrument]
+fn parent() {
+    first_child();
+    second_child();
+    second_child();
+}
+
+#[time_graph::instrument]
+fn first_child() {
+    std::thread::sleep(std::time::Duration::from_millis(5));
+}
+
+#[time_graph::instrument]
+fn second_child() {
+    std::thread::sleep(std::time::Duration::from_millis(5));
+}
+
+#[test]
+fn call_fractions() {
+    time_graph::enable_data_collection(true);
+    parent();
+    parent();
+
+    let graph = time_graph::get_full_graph();
+    let parent = graph.span_by_name("parent").unwrap().id;
+    let first = graph.span_by_name("first_child").unwrap().id;
+    let second = graph.span_by_name("second_child").unwrap().id;
+
+    let fractions = graph.call_fractions();
+    assert_eq!(fractions.len(), 2);
+
+    let mut total = 0.0;
+    for (call, fraction) in &fractions {
+        assert_eq!(call.caller, parent);
+        assert!(*fraction > 0.0 && *fraction <= 1.0);
+        total += fraction;
+    }
+    assert!(total <= 1.0);
+
+    let fraction_of = |callee| {
+        fractions.iter().find(|(call, _)| call.callee == callee).unwrap().1
+    };
+    // second_child is called twice as often, and takes as long each time
+    assert!(fraction_of(second) > fraction_of(first));
+}
@@ -0,0 +1,22 @@
+use time_graph::callsite;
+
+#[test]
+fn callsite_count() {
+    let before = time_graph::registered_callsite_count();
+    assert_eq!(time_graph::collect_callsites().len(), before);
+
+    let first = callsite!("first");
+    let second = callsite!("second");
+    for _ in 0..3 {
+        // only registered once
+        let _ = callsite!("third");
+    }
+
+    assert_eq!(time_graph::registered_callsite_count(), before + 3);
+
+    let callsites = time_graph::collect_callsites();
+    assert_eq!(callsites.len(), before + 3);
+    assert!(callsites.iter().any(|callsite| std::ptr::eq(*callsite, first)));
+    assert!(callsites.iter().any(|callsite| std::ptr::eq(*callsite, second)));
+    assert!(callsites.iter().any(|callsite| callsite.name() == "third"));
+}
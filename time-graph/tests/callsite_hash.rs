@@ -0,0 +1,40 @@
+// the hash of call sites only uses their id, which never changes
+#![allow(clippy::mutable_key_type)]
+
+use std::collections::{BTreeMap, HashSet};
+
+use time_graph::{callsite, CallSite};
+
+fn get_callsite() -> &'static CallSite {
+    callsite!("same")
+}
+
+#[test]
+fn callsite_hash() {
+    let first = callsite!("first");
+    let second = callsite!("second");
+    // same name, different call site
+    let other_first = callsite!("first");
+
+    let mut set = HashSet::<&CallSite>::new();
+    assert!(set.insert(first));
+    assert!(set.insert(second));
+    assert!(set.insert(other_first));
+    assert!(set.insert(get_callsite()));
+    assert!(!set.insert(get_callsite()));
+    assert!(!set.insert(first));
+    assert_eq!(set.len(), 4);
+
+    assert!(get_callsite() == get_callsite());
+    assert!(first != other_first);
+
+    let mut map = BTreeMap::new();
+    map.insert(second, 2);
+    map.insert(first, 1);
+    map.insert(other_first, 3);
+    let ids = map.keys().map(|callsite| callsite.id()).collect::<Vec<_>>();
+    let mut sorted = ids.clone();
+    sorted.sort();
+    assert_eq!(ids, sorted);
+    assert_eq!(map[first], 1);
+}
@@ -0,0 +1,38 @@
+use time_graph::CallSite;
+
+fn new_callsite(name: &'static str, file: &'static str, line: u32) -> &'static CallSite {
+    Box::leak(Box::new(CallSite::with_metadata(name, module_path!(), file, line, &[])))
+}
+
+fn sorted_names() -> Vec<&'static str> {
+    time_graph::collect_callsites_sorted().into_iter().map(|callsite| callsite.name()).collect()
+}
+
+#[test]
+fn callsites_sorted() {
+    let callsites = [
+        new_callsite("b", "src/a.rs", 10),
+        new_callsite("a", "src/a.rs", 10),
+        new_callsite("c", "src/a.rs", 2),
+        new_callsite("d", "src/b.rs", 1),
+    ];
+
+    time_graph::reset_registry();
+    for &callsite in &callsites {
+        time_graph::register_callsite(callsite);
+    }
+    let first_run = sorted_names();
+
+    time_graph::reset_registry();
+    for &callsite in callsites.iter().rev() {
+        time_graph::register_callsite(callsite);
+    }
+    let second_run = sorted_names();
+
+    assert_eq!(first_run, ["c", "a", "b", "d"]);
+    assert_eq!(first_run, second_run);
+
+    // the low-level traversal is unchanged, from the last registered call site
+    let names = time_graph::collect_callsites().into_iter().map(|callsite| callsite.name()).collect::<Vec<_>>();
+    assert_eq!(names, ["b", "a", "c", "d"]);
+}
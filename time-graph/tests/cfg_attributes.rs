@@ -0,0 +1,25 @@
+/// Only one of these functions should be compiled, the other one would fail
+/// to compile if the `cfg` attribute was not preserved
+#[time_graph::instrument]
+#[cfg(any())]
+fn gated() -> usize {
+    this_function_does_not_exist()
+}
+
+#[time_graph::instrument]
+#[cfg(not(any()))]
+#[must_use]
+fn gated() -> usize {
+    42
+}
+
+#[test]
+fn cfg_attributes() {
+    time_graph::enable_data_collection(true);
+
+    assert_eq!(gated(), 42);
+
+    let graph = time_graph::get_full_graph();
+    assert_eq!(graph.spans().count(), 1);
+    assert_eq!(graph.span_by_name("gated").unwrap().called, 1);
+}
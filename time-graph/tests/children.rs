@@ -0,0 +1,29 @@
+mod common;
+
+use common::run_computation;
+
+#[test]
+fn children() {
+    time_graph::enable_data_collection(true);
+    run_computation(4);
+
+    let graph = time_graph::get_full_graph();
+    let root = graph.span_by_name("run_computation").unwrap();
+
+    let mut children = graph.children_of(root.id)
+        .map(|(span, count)| (span.callsite.name(), count))
+        .collect::<Vec<_>>();
+    children.sort_unstable();
+    assert_eq!(children, [("another span", 1), ("bottom_5us", 16), ("compute", 4)]);
+
+    let bottom = graph.span_by_name("bottom_5us").unwrap();
+    let mut parents = graph.parents_of(bottom.id)
+        .map(|(span, count)| (span.callsite.name(), count))
+        .collect::<Vec<_>>();
+    parents.sort_unstable();
+    assert_eq!(parents, [("another span", 1), ("compute", 6), ("run_computation", 16)]);
+
+    assert_eq!(graph.parents_of(root.id).count(), 0);
+    assert_eq!(graph.children_of(bottom.id).count(), 0);
+    assert_eq!(graph.children_of(1000).count(), 0);
+}
@@ -0,0 +1,43 @@
+#![cfg(feature = "json")]
+
+#[time_graph::instrument]
+fn parent() {
+    child();
+    recursive(3);
+}
+
+#[time_graph::instrument]
+fn child() {}
+
+#[time_graph::instrument]
+fn recursive(count: usize) {
+    if count > 0 {
+        recursive(count - 1);
+    }
+}
+
+#[test]
+fn chrome_trace() {
+    time_graph::enable_data_collection(true);
+    parent();
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+    let parsed = json::parse(&graph.as_chrome_trace()).expect("invalid JSON");
+
+    assert!(parsed.is_array());
+    // with the "per-thread" feature, there is also a metadata event
+    // containing the name of the thread
+    let events = parsed.members().filter(|event| event["ph"] != "M").collect::<Vec<_>>();
+    assert_eq!(events.len(), graph.spans().count());
+
+    let mut names = Vec::new();
+    for event in events {
+        assert_eq!(event["ph"], "X");
+        assert!(event["ts"].as_f64().unwrap() >= 0.0);
+        assert!(event["dur"].as_f64().unwrap() >= 0.0);
+        names.push(event["name"].as_str().unwrap().to_string());
+    }
+    names.sort();
+    assert_eq!(names, ["chrome_trace::child", "chrome_trace::parent", "chrome_trace::recursive"]);
+}
@@ -0,0 +1,48 @@
+#![cfg(all(feature = "json", feature = "per-thread"))]
+
+use std::collections::BTreeSet;
+
+#[time_graph::instrument]
+fn work() {
+    std::thread::sleep(std::time::Duration::from_millis(1));
+}
+
+#[test]
+fn chrome_trace_threads() {
+    time_graph::enable_data_collection(true);
+    work();
+    std::thread::Builder::new()
+        .name("worker".into())
+        .spawn(|| {
+            work();
+            work();
+        })
+        .unwrap()
+        .join()
+        .unwrap();
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+    let parsed = json::parse(&graph.as_chrome_trace()).expect("invalid JSON");
+
+    let metadata = parsed.members().filter(|event| event["ph"] == "M").collect::<Vec<_>>();
+    assert_eq!(metadata.len(), 2);
+    let mut thread_names = BTreeSet::new();
+    for event in &metadata {
+        assert_eq!(event["name"], "thread_name");
+        thread_names.insert(event["args"]["name"].as_str().unwrap().to_string());
+    }
+    assert!(thread_names.contains("worker"));
+    assert!(thread_names.contains("chrome_trace_threads"));
+
+    let spans = parsed.members().filter(|event| event["ph"] == "X").collect::<Vec<_>>();
+    assert_eq!(spans.len(), 2);
+    let tids = spans.iter().map(|event| event["tid"].as_usize().unwrap()).collect::<BTreeSet<_>>();
+    assert_eq!(tids.len(), 2);
+
+    for event in spans {
+        let thread = metadata.iter().find(|metadata| metadata["tid"] == event["tid"]).unwrap();
+        let called = if thread["args"]["name"] == "worker" { 2 } else { 1 };
+        assert_eq!(event["args"]["called"], called);
+    }
+}
@@ -0,0 +1,53 @@
+#[time_graph::instrument]
+fn hot() {
+    cold();
+}
+
+#[time_graph::instrument]
+fn cold() {}
+
+#[time_graph::instrument]
+fn other() {}
+
+#[test]
+fn clear_callsite() {
+    time_graph::enable_data_collection(true);
+
+    hot();
+    other();
+
+    let graph = time_graph::get_full_graph();
+    assert_eq!(graph.spans().count(), 3);
+    assert_eq!(graph.calls().count(), 1);
+
+    assert_eq!(time_graph::clear_callsite_by_name("hot"), 1);
+    assert_eq!(time_graph::clear_callsite_by_name("missing"), 0);
+
+    let graph = time_graph::get_full_graph();
+    assert!(graph.span_by_name("hot").is_none());
+    assert_eq!(graph.span_by_name("cold").unwrap().called, 1);
+    assert_eq!(graph.span_by_name("other").unwrap().called, 1);
+    assert_eq!(graph.calls().count(), 0);
+
+    let id = graph.span_by_name("other").unwrap().callsite.id();
+    time_graph::clear_callsite(id);
+
+    // data is still recorded for cleared call sites
+    hot();
+
+    let graph = time_graph::get_full_graph();
+    assert!(graph.span_by_name("other").is_none());
+    assert_eq!(graph.span_by_name("hot").unwrap().called, 1);
+    assert_eq!(graph.span_by_name("cold").unwrap().called, 2);
+    assert_eq!(graph.calls().count(), 1);
+
+    // clearing a running call site
+    time_graph::spanned!("outer", {
+        time_graph::clear_callsite_by_name("outer");
+        time_graph::spanned!("inner", {});
+    });
+
+    let graph = time_graph::get_full_graph();
+    assert_eq!(graph.span_by_name("outer").unwrap().called, 1);
+    assert_eq!(graph.span_by_name("inner").unwrap().called, 1);
+}
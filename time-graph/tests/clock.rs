@@ -0,0 +1,47 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+struct FakeClock(AtomicU64);
+
+impl time_graph::TimeSource for FakeClock {
+    fn now(&self) -> u64 {
+        self.0.fetch_add(1000, Ordering::SeqCst)
+    }
+}
+
+#[time_graph::instrument]
+fn parent() {
+    child();
+    child();
+}
+
+#[time_graph::instrument]
+fn child() {}
+
+#[test]
+fn fake_clock() {
+    time_graph::set_clock(FakeClock(AtomicU64::new(0)));
+
+    time_graph::enable_data_collection(true);
+    parent();
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+    let parent_span = graph.span_by_name("parent").unwrap();
+    let child_span = graph.span_by_name("child").unwrap();
+
+    assert_eq!(child_span.elapsed, Duration::from_micros(2));
+    assert_eq!(child_span.min, Duration::from_micros(1));
+    assert_eq!(parent_span.elapsed, Duration::from_micros(5));
+    assert_eq!(parent_span.self_time, Duration::from_micros(3));
+
+    time_graph::reset_clock();
+    time_graph::clear_collected_data();
+
+    time_graph::enable_data_collection(true);
+    child();
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+    assert_ne!(graph.span_by_name("child").unwrap().elapsed, Duration::from_micros(1));
+}
@@ -0,0 +1,24 @@
+#[time_graph::instrument]
+fn function() {}
+
+#[test]
+fn nested_guards() {
+    function();
+
+    let outer = time_graph::collect();
+    function();
+
+    let inner = time_graph::collect();
+    function();
+    std::mem::drop(inner);
+
+    // collection is still enabled by the outer guard
+    function();
+    std::mem::drop(outer);
+
+    // collection is back to disabled
+    function();
+
+    let graph = time_graph::get_full_graph();
+    assert_eq!(graph.span_by_name("function").unwrap().called, 3);
+}
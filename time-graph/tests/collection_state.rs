@@ -0,0 +1,65 @@
+use time_graph::CollectionState;
+
+#[time_graph::instrument]
+fn function() {}
+
+#[test]
+fn collection_state() {
+    assert!(!time_graph::is_data_collection_enabled());
+
+    {
+        let _scope = CollectionState::scoped(true);
+        assert!(time_graph::is_data_collection_enabled());
+        function();
+
+        {
+            let _inner = CollectionState::scoped(false);
+            assert!(!time_graph::is_data_collection_enabled());
+            function();
+        }
+
+        // the inner scope restored the outer state
+        assert!(time_graph::is_data_collection_enabled());
+        function();
+    }
+
+    // collection is disabled again after the guard drops
+    assert!(!time_graph::is_data_collection_enabled());
+    function();
+
+    assert_eq!(time_graph::get_full_graph().span_by_name("function").unwrap().called, 2);
+
+    // nested push_collection requests are restored as well
+    time_graph::push_collection();
+    time_graph::push_collection();
+    {
+        let _scope = CollectionState::scoped(false);
+        assert!(!time_graph::is_data_collection_enabled());
+    }
+    time_graph::pop_collection();
+    assert!(time_graph::is_data_collection_enabled());
+    time_graph::pop_collection();
+    assert!(!time_graph::is_data_collection_enabled());
+
+    // collect guards dropped inside a scope are not undone by the scope
+    let guard = time_graph::collect();
+    let scope = CollectionState::scoped(false);
+    assert!(!time_graph::is_data_collection_enabled());
+    drop(guard);
+    drop(scope);
+    assert!(!time_graph::is_data_collection_enabled());
+
+    // collect guards created inside a scope outlive it
+    let scope = CollectionState::scoped(false);
+    let guard = time_graph::collect();
+    assert!(!time_graph::is_data_collection_enabled());
+    {
+        let _inner = CollectionState::scoped(true);
+        assert!(time_graph::is_data_collection_enabled());
+    }
+    assert!(!time_graph::is_data_collection_enabled());
+    drop(scope);
+    assert!(time_graph::is_data_collection_enabled());
+    drop(guard);
+    assert!(!time_graph::is_data_collection_enabled());
+}
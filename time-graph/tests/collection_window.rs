@@ -0,0 +1,33 @@
+use std::time::Duration;
+
+#[test]
+fn collection_window() {
+    time_graph::enable_data_collection(true);
+    time_graph::spanned!("sleep", {
+        std::thread::sleep(Duration::from_millis(20));
+    });
+    time_graph::enable_data_collection(false);
+
+    // time outside of the collection windows is not counted
+    std::thread::sleep(Duration::from_millis(100));
+
+    {
+        let _guard = time_graph::collect();
+        std::thread::sleep(Duration::from_millis(20));
+    }
+
+    let graph = time_graph::get_full_graph();
+    let window = graph.collection_duration();
+    assert!(window >= Duration::from_millis(40));
+    assert!(window < Duration::from_millis(100));
+
+    let fraction = graph.span_by_name("sleep").unwrap().fraction_of_window();
+    assert!(fraction > 0.0 && fraction <= 1.0);
+
+    // the window is frozen while collection is disabled
+    std::thread::sleep(Duration::from_millis(10));
+    assert_eq!(time_graph::get_full_graph().collection_duration(), window);
+
+    time_graph::clear_collected_data();
+    assert_eq!(time_graph::get_full_graph().collection_duration(), Duration::ZERO);
+}
@@ -0,0 +1,58 @@
+// call graph shared by multiple tests, similar to `examples/calculation.rs`.
+// Spans advance a manual clock instead of sleeping, so tests using
+// `ManualClock` get deterministic timings.
+#![allow(dead_code)]
+
+use std::sync::atomic::{AtomicU64, Ordering};
+
+static NOW: AtomicU64 = AtomicU64::new(0);
+
+/// Clock which only moves forward when calling `advance`
+pub struct ManualClock;
+
+impl time_graph::TimeSource for ManualClock {
+    fn now(&self) -> u64 {
+        NOW.load(Ordering::SeqCst)
+    }
+}
+
+/// Move the `ManualClock` forward by `microseconds`
+pub fn advance(microseconds: u64) {
+    NOW.fetch_add(microseconds * 1_000, Ordering::SeqCst);
+}
+
+#[time_graph::instrument]
+pub fn run_computation(max: u64) {
+    for i in 0..max {
+        compute(i)
+    }
+
+    time_graph::spanned!("another span", {
+        details::bottom_5us();
+    });
+
+    for _ in 0..(max * max) {
+        details::bottom_5us();
+    }
+}
+
+/// Takes 2ms, plus 5µs for each of the `count` calls to `bottom_5us`
+#[time_graph::instrument]
+pub fn compute(count: u64) {
+    advance(2_000);
+    for _ in 0..count {
+        details::bottom_5us();
+    }
+}
+
+pub mod details {
+    #[time_graph::instrument]
+    pub fn bottom_5us() {
+        super::advance(5);
+    }
+}
+
+#[time_graph::instrument]
+pub fn run_other(milliseconds: u64) {
+    advance(milliseconds * 1_000);
+}
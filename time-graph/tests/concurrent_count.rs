@@ -0,0 +1,39 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+
+const THREADS: u32 = 8;
+const ITERATIONS: u32 = 10_000;
+
+#[test]
+fn concurrent_count() {
+    time_graph::enable_data_collection(true);
+
+    let done = Arc::new(AtomicBool::new(false));
+    // extract data while the other threads are recording, to contend with
+    // them as much as possible
+    let reader = {
+        let done = Arc::clone(&done);
+        std::thread::spawn(move || {
+            while !done.load(Ordering::Acquire) {
+                let _ = time_graph::get_full_graph();
+            }
+        })
+    };
+
+    let threads = (0..THREADS).map(|_| {
+        std::thread::spawn(|| {
+            for _ in 0..ITERATIONS {
+                time_graph::spanned!("hot", {});
+            }
+        })
+    }).collect::<Vec<_>>();
+
+    for thread in threads {
+        thread.join().unwrap();
+    }
+    done.store(true, Ordering::Release);
+    reader.join().unwrap();
+
+    let graph = time_graph::get_full_graph();
+    assert_eq!(graph.span_by_name("hot").unwrap().called, THREADS * ITERATIONS);
+}
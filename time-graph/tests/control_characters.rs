@@ -0,0 +1,48 @@
+use time_graph::{dynamic_spanned, spanned};
+
+#[test]
+fn control_characters() {
+    time_graph::enable_data_collection(true);
+
+    let name = format!("line\nbreak \"quoted\"\tand\u{0}nul{}", 1);
+    dynamic_spanned!(name, {
+        spanned!("inner", {});
+    });
+
+    let graph = time_graph::get_full_graph();
+    let span = graph.spans().find(|span| span.callsite.name() == name).unwrap();
+    assert_eq!(
+        span.callsite.sanitized_name(time_graph::NameStyle::NameOnly),
+        "line break \"quoted\" and nul1"
+    );
+
+    let dot = graph.as_dot_styled();
+    assert!(dot.contains(r#"line\nbreak \"quoted\" and nul1"#));
+    assert!(!dot.contains('\t') && !dot.contains('\u{0}'));
+
+    let tree = graph.as_tree();
+    assert_eq!(tree.lines().count(), 2);
+    assert!(tree.contains("line break \"quoted\" and nul1"));
+
+    let csv = graph.as_csv();
+    assert_eq!(csv.lines().count(), 3);
+    assert!(csv.contains(r#""line break ""quoted"" and nul1""#));
+
+    #[cfg(feature = "table")]
+    {
+        let table = graph.as_table();
+        assert!(table.contains("line break \"quoted\" and nul1"));
+        assert!(!table.contains('\t') && !table.contains('\u{0}'));
+        // all the lines in the table have the same width
+        let width = table.lines().next().unwrap().chars().count();
+        assert!(table.lines().all(|line| line.chars().count() == width));
+    }
+
+    #[cfg(feature = "json")]
+    {
+        // JSON keeps the full name, escaping it
+        let parsed = json::parse(&graph.as_json()).expect("invalid JSON");
+        let full_name = span.callsite.full_name();
+        assert!(parsed["timings"].has_key(&full_name));
+    }
+}
@@ -0,0 +1,75 @@
+use std::time::Duration;
+
+use time_graph::{callsite, set_count_only, spanned, Span};
+
+#[time_graph::instrument(count_only)]
+fn counted() {
+    std::thread::sleep(Duration::from_millis(1));
+}
+
+#[time_graph::instrument(count_only)]
+async fn counted_async() {
+    tokio::task::yield_now().await;
+}
+
+#[test]
+fn count_only() {
+    time_graph::enable_data_collection(true);
+
+    spanned!("outer", {
+        for _ in 0..5 {
+            counted();
+        }
+        assert!(time_graph::current_span_elapsed().is_some());
+    });
+
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    runtime.block_on(counted_async());
+
+    // runtime configuration
+    let callsite = callsite!("manual");
+    set_count_only(Some(callsite), true);
+    let span = Span::new(callsite);
+    for _ in 0..3 {
+        let guard = span.enter();
+        assert_eq!(time_graph::current_span_elapsed(), None);
+        assert_eq!(guard.finish(), Duration::ZERO);
+    }
+
+    // global configuration
+    set_count_only(None, true);
+    spanned!("global", {});
+    set_count_only(None, false);
+    spanned!("timed", {
+        std::thread::sleep(Duration::from_millis(1));
+    });
+
+    time_graph::enable_data_collection(false);
+    let graph = time_graph::get_full_graph();
+
+    let counted = graph.span_by_name("counted").unwrap();
+    assert_eq!(counted.called, 5);
+    assert_eq!(counted.elapsed, Duration::ZERO);
+    assert_eq!(counted.callsite.metadata(), &[("count_only", "true")]);
+
+    let outer = graph.span_by_name("outer").unwrap();
+    assert!(outer.elapsed >= Duration::from_millis(5));
+    let call = graph.calls().find(|call| call.callee == counted.id).unwrap();
+    assert_eq!(call.caller, outer.id);
+    assert_eq!(call.count, 5);
+
+    let counted_async = graph.span_by_name("counted_async").unwrap();
+    assert_eq!(counted_async.called, 1);
+    assert_eq!(counted_async.elapsed, Duration::ZERO);
+
+    let manual = graph.span_by_name("manual").unwrap();
+    assert_eq!(manual.called, 3);
+    assert_eq!(manual.elapsed, Duration::ZERO);
+
+    let global = graph.span_by_name("global").unwrap();
+    assert_eq!(global.called, 1);
+    assert_eq!(global.elapsed, Duration::ZERO);
+
+    let timed = graph.span_by_name("timed").unwrap();
+    assert!(timed.elapsed >= Duration::from_millis(1));
+}
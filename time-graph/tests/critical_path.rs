@@ -0,0 +1,48 @@
+mod common;
+
+use common::{run_computation, run_other};
+
+#[time_graph::instrument]
+fn recursive(count: usize) {
+    if count > 0 {
+        recursive(count - 1);
+    }
+}
+
+#[test]
+fn critical_path() {
+    time_graph::set_clock(common::ManualClock);
+    time_graph::enable_data_collection(true);
+    run_other(5);
+    run_computation(4);
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+    let path = graph.critical_path()
+        .into_iter()
+        .map(|span| span.callsite.name())
+        .collect::<Vec<_>>();
+
+    // run_computation takes 8.115ms, more than run_other
+    assert_eq!(path, ["run_computation", "compute", "bottom_5us"]);
+
+    let roots = graph.roots().map(|span| span.callsite.name()).collect::<Vec<_>>();
+    assert!(roots.contains(&path[0]));
+
+    let leaf = graph.span_by_name(path[2]).unwrap();
+    assert_eq!(graph.calls().filter(|call| call.caller == leaf.id).count(), 0);
+
+    // recursive calls are ignored
+    time_graph::clear_collected_data();
+    time_graph::enable_data_collection(true);
+    recursive(3);
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+    let path = graph.critical_path();
+    assert_eq!(path.len(), 1);
+    assert_eq!(path[0].callsite.name(), "recursive");
+
+    time_graph::clear_collected_data();
+    assert!(time_graph::get_full_graph().critical_path().is_empty());
+}
@@ -0,0 +1,43 @@
+use time_graph::{callsite, Span};
+
+#[time_graph::instrument]
+fn worker() {}
+
+#[time_graph::instrument]
+fn spawn_work() {
+    let parent = time_graph::current_span_id();
+    assert!(parent.is_some());
+
+    std::thread::spawn(move || {
+        // no span is executing on the new thread
+        assert!(time_graph::current_span_id().is_none());
+
+        let span = Span::new(callsite!("offloaded"));
+        let _guard = span.enter_as_child_of(parent);
+        worker();
+    }).join().unwrap();
+
+    // the current span is not modified on this thread
+    assert_eq!(time_graph::current_span_id(), parent);
+}
+
+#[test]
+fn cross_thread() {
+    assert!(time_graph::current_span_id().is_none());
+
+    time_graph::enable_data_collection(true);
+    spawn_work();
+    time_graph::enable_data_collection(false);
+
+    assert!(time_graph::current_span_id().is_none());
+
+    let graph = time_graph::get_full_graph();
+    let spawn_work = graph.span_by_name("spawn_work").unwrap();
+    let offloaded = graph.span_by_name("offloaded").unwrap();
+    let worker = graph.span_by_name("worker").unwrap();
+
+    let calls = graph.calls().map(|call| (call.caller, call.callee, call.count)).collect::<Vec<_>>();
+    assert_eq!(calls.len(), 2);
+    assert!(calls.contains(&(spawn_work.id, offloaded.id, 1)));
+    assert!(calls.contains(&(offloaded.id, worker.id, 1)));
+}
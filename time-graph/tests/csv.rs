@@ -0,0 +1,35 @@
+use time_graph::spanned;
+
+#[time_graph::instrument]
+fn function() {
+    spanned!("first, \"second\"", {});
+}
+
+#[test]
+fn csv() {
+    time_graph::enable_data_collection(true);
+    function();
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+    let csv = graph.as_csv();
+
+    let lines = csv.lines().collect::<Vec<_>>();
+    assert_eq!(lines[0], "id,name,module,called,total_ns,mean_ns,min_ns,max_ns");
+    assert_eq!(lines.len(), 3);
+
+    let span = graph.span_by_name("function").unwrap();
+    let expected = format!(
+        "{},function,csv,1,{},{},{},{}",
+        span.id,
+        span.elapsed.as_nanos(),
+        span.elapsed.as_nanos(),
+        span.min.as_nanos(),
+        span.max.as_nanos(),
+    );
+    assert!(lines.contains(&expected.as_str()));
+
+    let span = graph.span_by_name("first, \"second\"").unwrap();
+    let prefix = format!("{},\"first, \"\"second\"\"\",csv,1,", span.id);
+    assert!(lines.iter().any(|line| line.starts_with(&prefix)));
+}
@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use time_graph::spanned;
+
+#[time_graph::instrument]
+async fn run_async() {
+    std::thread::sleep(Duration::from_millis(5));
+    tokio::task::yield_now().await;
+    let elapsed = time_graph::current_span_elapsed().unwrap();
+    assert!(elapsed >= Duration::from_millis(5));
+}
+
+#[test]
+fn current_span_elapsed() {
+    time_graph::enable_data_collection(true);
+    assert_eq!(time_graph::current_span_elapsed(), None);
+
+    spanned!("outer", {
+        std::thread::sleep(Duration::from_millis(10));
+        let outer = time_graph::current_span_elapsed().unwrap();
+        assert!(outer >= Duration::from_millis(10));
+
+        spanned!("inner", {
+            let inner = time_graph::current_span_elapsed().unwrap();
+            assert!(inner < outer);
+        });
+
+        assert!(time_graph::current_span_elapsed().unwrap() >= outer);
+    });
+    assert_eq!(time_graph::current_span_elapsed(), None);
+
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    runtime.block_on(run_async());
+    assert_eq!(time_graph::current_span_elapsed(), None);
+
+    // spans which are not recorded are ignored
+    time_graph::enable_data_collection(false);
+    spanned!("not recorded", {
+        assert_eq!(time_graph::current_span_elapsed(), None);
+    });
+}
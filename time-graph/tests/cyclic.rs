@@ -0,0 +1,56 @@
+#[time_graph::instrument]
+fn function_a(repeat: bool) {
+    if repeat {
+        function_b();
+    }
+}
+
+#[time_graph::instrument]
+fn function_b() {
+    function_a(false);
+}
+
+#[time_graph::instrument]
+fn recursive(count: usize) {
+    if count > 0 {
+        recursive(count - 1);
+    }
+}
+
+#[time_graph::instrument]
+fn not_recursive() {
+    function_b_like();
+}
+
+#[time_graph::instrument]
+fn function_b_like() {}
+
+#[test]
+fn is_cyclic() {
+    time_graph::enable_data_collection(true);
+    not_recursive();
+    time_graph::enable_data_collection(false);
+    assert!(!time_graph::get_full_graph().is_cyclic());
+
+    time_graph::clear_collected_data();
+    time_graph::enable_data_collection(true);
+    recursive(3);
+    time_graph::enable_data_collection(false);
+    assert!(time_graph::get_full_graph().is_cyclic());
+
+    time_graph::clear_collected_data();
+    time_graph::enable_data_collection(true);
+    function_a(true);
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+    assert!(graph.is_cyclic());
+
+    // cyclic graphs can still be rendered as a table
+    #[cfg(feature = "table")]
+    {
+        let table = graph.as_table();
+        assert!(table.contains("cyclic::function_a"));
+        assert!(table.contains("cyclic::function_b"));
+    }
+}
@@ -0,0 +1,78 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+
+use time_graph::SpanDiffKind;
+
+static NOW: AtomicU64 = AtomicU64::new(0);
+static SLOW_DURATION: AtomicU64 = AtomicU64::new(1000);
+
+struct ManualClock;
+
+impl time_graph::TimeSource for ManualClock {
+    fn now(&self) -> u64 {
+        NOW.load(Ordering::SeqCst)
+    }
+}
+
+#[time_graph::instrument]
+fn slow() {
+    NOW.fetch_add(SLOW_DURATION.load(Ordering::SeqCst), Ordering::SeqCst);
+}
+
+#[time_graph::instrument]
+fn stable() {
+    NOW.fetch_add(500, Ordering::SeqCst);
+}
+
+#[time_graph::instrument]
+fn old() {}
+
+#[time_graph::instrument]
+fn new() {}
+
+#[test]
+fn diff() {
+    time_graph::set_clock(ManualClock);
+    time_graph::enable_data_collection(true);
+
+    slow();
+    stable();
+    old();
+    let baseline = time_graph::get_full_graph();
+
+    time_graph::clear_collected_data();
+    SLOW_DURATION.store(2000, Ordering::SeqCst);
+    slow();
+    stable();
+    stable();
+    new();
+    let graph = time_graph::get_full_graph();
+
+    let diff = graph.diff(&baseline);
+    assert_eq!(diff.len(), 4);
+
+    let find = |name| diff.iter().find(|span| span.callsite.name() == name).unwrap();
+
+    let slow = find("slow");
+    assert_eq!(slow.kind, SpanDiffKind::Changed);
+    assert_eq!(slow.elapsed_delta, 1000);
+    assert_eq!(slow.called_delta, 0);
+    assert_eq!(slow.percent_change, Some(100.0));
+
+    let stable = find("stable");
+    assert_eq!(stable.kind, SpanDiffKind::Changed);
+    assert_eq!(stable.elapsed_delta, 500);
+    assert_eq!(stable.called_delta, 1);
+
+    let new = find("new");
+    assert_eq!(new.kind, SpanDiffKind::Added);
+    assert_eq!(new.called_delta, 1);
+    assert_eq!(new.percent_change, None);
+
+    let old = find("old");
+    assert_eq!(old.kind, SpanDiffKind::Removed);
+    assert_eq!(old.called_delta, -1);
+    assert_eq!(old.elapsed_delta, 0);
+
+    // removed spans come last
+    assert_eq!(diff.last().unwrap().callsite.name(), "old");
+}
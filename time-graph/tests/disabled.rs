@@ -0,0 +1,24 @@
+#![cfg(feature = "disabled")]
+
+#[time_graph::instrument]
+fn instrumented() -> usize {
+    42
+}
+
+#[time_graph::instrument]
+async fn instrumented_async() -> usize {
+    42
+}
+
+#[test]
+fn disabled() {
+    time_graph::enable_data_collection(true);
+
+    let before = time_graph::registered_callsite_count();
+    assert_eq!(instrumented(), 42);
+    let future = instrumented_async();
+    drop(future);
+    assert_eq!(time_graph::registered_callsite_count(), before);
+
+    assert_eq!(time_graph::get_full_graph().spans().count(), 0);
+}
@@ -0,0 +1,38 @@
+use std::time::Duration;
+
+#[time_graph::instrument]
+fn slow() {
+    std::thread::sleep(Duration::from_millis(10));
+    fast();
+    fast();
+}
+
+#[time_graph::instrument(name = "fast \"quoted\"")]
+fn fast() {}
+
+#[test]
+fn styled_dot() {
+    time_graph::enable_data_collection(true);
+    slow();
+
+    let graph = time_graph::get_full_graph();
+    let dot = graph.as_dot_styled();
+
+    assert!(dot.starts_with("digraph {"));
+    assert_eq!(dot.matches("fillcolor").count(), 2);
+    assert_eq!(dot.matches("style = filled").count(), 2);
+
+    // the slowest span is red
+    let slow_line = dot.lines().find(|line| line.contains("dot::slow")).unwrap();
+    assert!(slow_line.contains("(1 calls)"));
+    assert!(slow_line.contains(r##"fillcolor = "#ff0000""##));
+
+    let fast_line = dot.lines().find(|line| line.contains("dot::{fast")).unwrap();
+    assert!(fast_line.contains(r#"label = "dot::{fast \"quoted\"}\n"#));
+    assert!(fast_line.contains("(2 calls)"));
+
+    // edges are labeled by the number of calls
+    let slow = graph.span_by_name("slow").unwrap();
+    let fast = graph.span_by_name("fast \"quoted\"").unwrap();
+    assert!(dot.contains(&format!(r#"{} -> {} [ label = "2"]"#, slow.id, fast.id)));
+}
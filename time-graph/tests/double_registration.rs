@@ -0,0 +1,24 @@
+use time_graph::{CallSite, Lazy};
+
+static FIRST: Lazy<CallSite> = Lazy::new(|| CallSite::new("first", module_path!(), file!(), line!()));
+static SECOND: Lazy<CallSite> = Lazy::new(|| CallSite::new("second", module_path!(), file!(), line!()));
+
+#[test]
+fn double_registration() {
+    let before = time_graph::registered_callsite_count();
+
+    time_graph::register_callsite(&FIRST);
+    // registering the current head again
+    time_graph::register_callsite(&FIRST);
+    time_graph::register_callsite(&SECOND);
+    // registering a call site deeper in the list
+    time_graph::register_callsite(&FIRST);
+    time_graph::register_callsite(&SECOND);
+
+    // the list is still acyclic, and contains each call site once
+    assert_eq!(time_graph::registered_callsite_count(), before + 2);
+
+    let callsites = time_graph::collect_callsites();
+    assert_eq!(callsites.iter().filter(|callsite| std::ptr::eq(**callsite, &*FIRST)).count(), 1);
+    assert_eq!(callsites.iter().filter(|callsite| std::ptr::eq(**callsite, &*SECOND)).count(), 1);
+}
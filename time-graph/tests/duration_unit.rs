@@ -0,0 +1,57 @@
+use std::time::Duration;
+
+use time_graph::DurationUnit;
+
+#[time_graph::instrument]
+fn slow() {
+    std::thread::sleep(Duration::from_millis(5));
+    fast();
+}
+
+#[time_graph::instrument]
+fn fast() {}
+
+#[test]
+fn duration_unit() {
+    time_graph::enable_data_collection(true);
+    slow();
+
+    let graph = time_graph::get_full_graph();
+    let slow = graph.span_by_name("slow").unwrap();
+    assert_eq!(DurationUnit::Milliseconds.value(Duration::from_micros(1500)), Some(1.5));
+    assert_eq!(DurationUnit::Auto.value(slow.elapsed), None);
+
+    #[cfg(feature = "table")]
+    {
+        let options = time_graph::TableOptions {
+            columns: vec![time_graph::TableColumn::Name, time_graph::TableColumn::Total],
+            duration_unit: DurationUnit::Microseconds,
+            style: time_graph::TableStyle::simple(),
+            ..Default::default()
+        };
+        let table = graph.as_table_with(options);
+
+        let rows = table.lines()
+            .filter(|line| line.contains("duration_unit::"))
+            .collect::<Vec<_>>();
+        assert_eq!(rows.len(), 2);
+        for row in rows {
+            let cells = row.split('|').map(str::trim).collect::<Vec<_>>();
+            let total = cells[2];
+            assert!(total.ends_with("us"), "{}", total);
+            total.trim_end_matches("us").parse::<f64>().unwrap();
+        }
+    }
+
+    #[cfg(feature = "json")]
+    {
+        let parsed = json::parse(&graph.as_json_with_unit(DurationUnit::Milliseconds)).unwrap();
+        let elapsed = parsed["timings"]["duration_unit::slow"]["elapsed"].as_f64().unwrap();
+        assert!(elapsed >= 5.0);
+        assert!(parsed["timings"]["duration_unit::fast"]["elapsed"].as_f64().unwrap() < 5.0);
+
+        // the default output uses strings
+        let parsed = json::parse(&graph.as_json()).unwrap();
+        assert!(parsed["timings"]["duration_unit::slow"]["elapsed"].is_string());
+    }
+}
@@ -0,0 +1,35 @@
+use time_graph::{dynamic_spanned, spanned};
+
+#[test]
+fn dynamic_spanned() {
+    time_graph::enable_data_collection(true);
+
+    for i in 0..2 {
+        spanned!("static", {});
+        dynamic_spanned!(format!("dynamic {}", i), {
+            spanned!("inner", {});
+        });
+    }
+    dynamic_spanned!("dynamic 1", {});
+
+    let graph = time_graph::get_full_graph();
+
+    assert_eq!(graph.span_by_name("static").unwrap().called, 2);
+
+    let first = graph.span_by_name("dynamic 0").unwrap();
+    let second = graph.span_by_name("dynamic 1").unwrap();
+    assert_ne!(first.id, second.id);
+    assert_eq!(first.called, 1);
+    assert_eq!(second.called, 1);
+    // the same name used at another location creates a different span
+    assert_eq!(graph.spans_by_name("dynamic 1").count(), 2);
+    assert_eq!(first.callsite.module_path(), "dynamic_spanned");
+
+    // inner is called from both dynamic spans
+    let inner = graph.span_by_name("inner").unwrap();
+    let callers = graph.calls()
+        .filter(|call| call.callee == inner.id)
+        .map(|call| graph.spans().find(|span| span.id == call.caller).unwrap().callsite.name())
+        .collect::<std::collections::BTreeSet<_>>();
+    assert_eq!(callers.into_iter().collect::<Vec<_>>(), ["dynamic 0", "dynamic 1"]);
+}
@@ -0,0 +1,40 @@
+use std::sync::atomic::{AtomicBool, Ordering};
+
+static RECORD: AtomicBool = AtomicBool::new(false);
+
+fn never() -> bool {
+    false
+}
+
+fn when_requested() -> bool {
+    RECORD.load(Ordering::Relaxed)
+}
+
+#[time_graph::instrument(enabled_if = never)]
+fn never_recorded() {}
+
+#[time_graph::instrument(name = "sometimes", enabled_if = when_requested)]
+fn sometimes_recorded() -> usize {
+    42
+}
+
+#[time_graph::instrument]
+fn always_recorded() {
+    never_recorded();
+    sometimes_recorded();
+}
+
+#[test]
+fn enabled_if() {
+    time_graph::enable_data_collection(true);
+    always_recorded();
+    RECORD.store(true, Ordering::Relaxed);
+    always_recorded();
+    assert_eq!(sometimes_recorded(), 42);
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+    assert!(graph.span_by_name("never_recorded").is_none());
+    assert_eq!(graph.span_by_name("always_recorded").unwrap().called, 2);
+    assert_eq!(graph.span_by_name("sometimes").unwrap().called, 2);
+}
@@ -0,0 +1,29 @@
+use time_graph::spanned;
+
+#[test]
+fn special_characters_in_names() {
+    time_graph::enable_data_collection(true);
+    spanned!("he said \"hi\"\n<x>", {
+        spanned!("inner", {});
+    });
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+
+    let dot = graph.as_dot();
+    assert!(dot.contains(r#"escaping::{he said \"hi\"\l<x>}"#));
+    assert!(!dot.contains("\"hi\"\n"));
+
+    let mermaid = graph.as_mermaid();
+    assert!(mermaid.contains("escaping::{he said #quot;hi#quot; #lt;x#gt;}"));
+    for line in mermaid.lines().skip(1) {
+        // only the quotes delimiting the label are left
+        assert!(line.matches('"').count() == 0 || line.matches('"').count() == 2);
+    }
+
+    #[cfg(feature = "json")]
+    {
+        let parsed = json::parse(&graph.as_json()).expect("invalid JSON");
+        assert!(parsed["timings"].has_key("escaping::{he said \"hi\"\n<x>}"));
+    }
+}
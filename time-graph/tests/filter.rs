@@ -0,0 +1,43 @@
+use std::time::Duration;
+
+mod common;
+
+use common::{run_computation, run_other};
+
+#[test]
+fn filter_by_elapsed() {
+    time_graph::set_clock(common::ManualClock);
+    time_graph::enable_data_collection(true);
+
+    run_other(50);
+    run_computation(2);
+
+    let graph = time_graph::get_full_graph();
+    assert_eq!(graph.spans().count(), 5);
+    assert_eq!(graph.calls().count(), 5);
+
+    // "another span" and bottom_5us take less than 1ms
+    let filtered = graph.filter_by_elapsed(Duration::from_millis(1));
+    let mut names = filtered.spans().map(|span| span.callsite.name()).collect::<Vec<_>>();
+    names.sort_unstable();
+    assert_eq!(names, ["compute", "run_computation", "run_other"]);
+
+    // ids are consistent with the calls
+    let run_computation = filtered.span_by_name("run_computation").unwrap();
+    let compute = filtered.span_by_name("compute").unwrap();
+    let calls = filtered.calls().collect::<Vec<_>>();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].caller, run_computation.id);
+    assert_eq!(calls[0].callee, compute.id);
+    assert_eq!(calls[0].count, 2);
+
+    // timings are unchanged
+    let original = graph.span_by_name("compute").unwrap();
+    assert_eq!(compute.elapsed, original.elapsed);
+    assert_eq!(compute.self_time, original.self_time);
+    assert_eq!(compute.elapsed, Duration::from_micros(4_005));
+
+    // the original graph is untouched
+    assert_eq!(graph.spans().count(), 5);
+    assert_eq!(graph.filter_by_elapsed(Duration::from_secs(3600)).spans().count(), 0);
+}
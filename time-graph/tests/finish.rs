@@ -0,0 +1,31 @@
+use std::time::Duration;
+
+use time_graph::{callsite, Span};
+
+#[test]
+fn finish() {
+    time_graph::enable_data_collection(true);
+
+    let span = Span::new(callsite!("work"));
+    for _ in 0..2 {
+        let guard = span.enter();
+        std::thread::sleep(Duration::from_millis(1));
+        let elapsed = guard.finish();
+        assert!(elapsed >= Duration::from_millis(1));
+        assert_eq!(time_graph::current_span_id(), None);
+
+        // cleanup, which should not be included in the span
+        std::thread::sleep(Duration::from_millis(50));
+    }
+
+    time_graph::enable_data_collection(false);
+    let graph = time_graph::get_full_graph();
+    let span = graph.spans().find(|span| span.callsite.name() == "work").unwrap();
+    assert_eq!(span.called, 2);
+    assert!(span.elapsed >= Duration::from_millis(2));
+    assert!(span.elapsed < Duration::from_millis(100));
+
+    // nothing is recorded when data collection is disabled
+    let span = Span::new(callsite!("disabled"));
+    assert_eq!(span.enter().finish(), Duration::new(0, 0));
+}
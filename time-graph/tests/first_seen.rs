@@ -0,0 +1,43 @@
+use std::time::{Duration, SystemTime};
+
+#[time_graph::instrument]
+fn sleep_recursive(count: usize) {
+    std::thread::sleep(Duration::from_millis(10));
+    if count > 0 {
+        sleep_recursive(count - 1);
+    }
+}
+
+#[test]
+fn first_seen() {
+    time_graph::enable_data_collection(true);
+
+    let before = SystemTime::now();
+    sleep_recursive(2);
+    let after = SystemTime::now();
+    sleep_recursive(0);
+
+    let graph = time_graph::get_full_graph();
+    let span = graph.span_by_name("sleep_recursive").unwrap();
+    let first_seen = span.first_seen.unwrap();
+
+    // the first call started between `before` and the start of the recursive
+    // calls, allowing for small differences between the clocks
+    let tolerance = Duration::from_millis(5);
+    assert!(first_seen + tolerance >= before);
+    assert!(first_seen <= after - Duration::from_millis(20) + tolerance);
+
+    // merging graphs keeps the earliest time, even if the span was never
+    // called in the graph we merge into
+    time_graph::reset_timings();
+    let mut empty = time_graph::get_full_graph();
+    assert!(empty.span_by_name("sleep_recursive").unwrap().first_seen.is_none());
+    empty.merge(&graph);
+    assert_eq!(empty.span_by_name("sleep_recursive").unwrap().first_seen, Some(first_seen));
+
+    sleep_recursive(0);
+    let mut later = time_graph::get_full_graph();
+    assert!(later.span_by_name("sleep_recursive").unwrap().first_seen.unwrap() > first_seen);
+    later.merge(&graph);
+    assert_eq!(later.span_by_name("sleep_recursive").unwrap().first_seen, Some(first_seen));
+}
@@ -0,0 +1,32 @@
+#![cfg(feature = "flamegraph")]
+
+use time_graph::spanned;
+
+#[time_graph::instrument]
+fn outer() {
+    inner();
+    spanned!("semi;colon", {
+        inner();
+    });
+}
+
+#[time_graph::instrument]
+fn inner() {
+    std::thread::sleep(std::time::Duration::from_millis(1));
+}
+
+#[test]
+fn flamegraph() {
+    time_graph::enable_data_collection(true);
+    outer();
+    time_graph::enable_data_collection(false);
+
+    let svg = time_graph::get_full_graph().as_flamegraph_svg();
+    assert!(svg.starts_with("<?xml"));
+    assert!(svg.contains("<svg"));
+    assert!(svg.trim_end().ends_with("</svg>"));
+
+    assert!(svg.contains("flamegraph::outer"));
+    assert!(svg.contains("flamegraph::inner"));
+    assert!(svg.contains("flamegraph::semi,colon"));
+}
@@ -0,0 +1,80 @@
+#[time_graph::instrument]
+fn run(count: u64) {
+    for _ in 0..count {
+        details::outer();
+    }
+
+    time_graph::spanned!("another span", {
+        details::inner();
+    });
+
+    details_extra::extra();
+}
+
+mod details {
+    #[time_graph::instrument]
+    pub fn outer() {
+        inner();
+    }
+
+    #[time_graph::instrument]
+    pub fn inner() {}
+}
+
+mod details_extra {
+    #[time_graph::instrument]
+    pub fn extra() {}
+}
+
+#[test]
+fn group_by_module() {
+    time_graph::enable_data_collection(true);
+
+    run(3);
+
+    let graph = time_graph::get_full_graph();
+    assert_eq!(graph.spans().count(), 5);
+
+    let prefix = concat!(module_path!(), "::details");
+    let grouped = graph.group_by_module(prefix);
+    assert_eq!(grouped.spans().count(), 4);
+
+    // details_extra is not a submodule of details
+    assert!(grouped.spans().any(|span| span.name == "extra"));
+
+    let group = grouped.spans().find(|span| span.name == "*").unwrap();
+    assert_eq!(group.module_path, prefix);
+    assert_eq!(group.full_name(), format!("{}::*", prefix));
+    // 3 calls to outer, and 4 calls to inner
+    assert_eq!(group.called, 7);
+
+    let outer = graph.span_by_name("outer").unwrap();
+    let inner = graph.span_by_name("inner").unwrap();
+    assert_eq!(group.elapsed, outer.elapsed + inner.elapsed);
+    assert_eq!(group.self_time, outer.self_time + inner.self_time);
+
+    let run = graph.span_by_name("run").unwrap();
+    let another = graph.span_by_name("another span").unwrap();
+
+    let mut calls = grouped.calls()
+        .filter(|call| call.callee == group.id)
+        .map(|call| (call.caller, call.count))
+        .collect::<Vec<_>>();
+    calls.sort_unstable();
+    let mut expected = vec![(run.id, 3), (another.id, 1)];
+    expected.sort_unstable();
+    assert_eq!(calls, expected);
+
+    // no self-loop for calls inside the group
+    assert!(grouped.calls().all(|call| call.caller != group.id));
+
+    // a trailing separator in the prefix is ignored
+    let grouped = graph.group_by_module(&format!("{}::", prefix));
+    assert_eq!(grouped.spans().count(), 4);
+    assert!(grouped.spans().any(|span| span.name == "extra"));
+
+    // nothing to group
+    let grouped = graph.group_by_module("not_a_module");
+    assert_eq!(grouped.spans().count(), 5);
+    assert_eq!(grouped.calls().count(), graph.calls().count());
+}
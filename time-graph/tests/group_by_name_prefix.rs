@@ -0,0 +1,44 @@
+use time_graph::spanned;
+
+#[test]
+fn group_by_name_prefix() {
+    time_graph::enable_data_collection(true);
+
+    spanned!("request", {
+        spanned!("db.connect", {});
+        for _ in 0..3 {
+            spanned!("db.query.plan", {
+                spanned!("db.query.run", {});
+            });
+        }
+    });
+
+    let graph = time_graph::get_full_graph();
+    assert_eq!(graph.spans().count(), 4);
+
+    let grouped = graph.group_by_name_prefix('.');
+    let mut names = grouped.spans().map(|span| span.name.as_str()).collect::<Vec<_>>();
+    names.sort_unstable();
+    assert_eq!(names, ["db", "db.query", "request"]);
+
+    let query = grouped.spans().find(|span| span.name == "db.query").unwrap();
+    assert_eq!(query.called, 6);
+    assert_eq!(query.module_path, module_path!());
+
+    let plan = graph.span_by_name("db.query.plan").unwrap();
+    let run = graph.span_by_name("db.query.run").unwrap();
+    assert_eq!(query.elapsed, plan.elapsed + run.elapsed);
+
+    let db = grouped.spans().find(|span| span.name == "db").unwrap();
+    assert_eq!(db.called, 1);
+
+    let request = grouped.spans().find(|span| span.name == "request").unwrap();
+    let mut calls = grouped.calls()
+        .map(|call| (call.caller, call.callee, call.count))
+        .collect::<Vec<_>>();
+    calls.sort_unstable();
+    let mut expected = vec![(request.id, query.id, 3), (request.id, db.id, 1)];
+    expected.sort_unstable();
+    // calls inside the `db.query` group are removed
+    assert_eq!(calls, expected);
+}
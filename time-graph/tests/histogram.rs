@@ -0,0 +1,29 @@
+#![cfg(feature = "histogram")]
+
+use std::time::Duration;
+
+#[time_graph::instrument]
+fn sleep_100us() {
+    std::thread::sleep(Duration::from_micros(100));
+}
+
+#[test]
+fn percentiles() {
+    time_graph::enable_data_collection(true);
+    for _ in 0..1000 {
+        sleep_100us();
+    }
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+    let span = graph.span_by_name("sleep_100us").unwrap();
+    assert_eq!(span.called, 1000);
+
+    let p50 = span.percentile(50.0);
+    assert!(p50 >= Duration::from_micros(99));
+    assert!(p50 < Duration::from_millis(5));
+
+    assert!(span.percentile(0.0) <= p50);
+    assert!(p50 <= span.percentile(99.0));
+    assert!(span.percentile(100.0) <= span.max + span.max / 100);
+}
@@ -0,0 +1,29 @@
+mod common;
+
+use common::{run_computation, run_other};
+
+#[test]
+fn hottest() {
+    time_graph::set_clock(common::ManualClock);
+    time_graph::enable_data_collection(true);
+    run_other(10);
+    run_computation(3);
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+
+    let hottest = graph.hottest(3);
+    let names = hottest.iter().map(|span| span.callsite.name()).collect::<Vec<_>>();
+    // run_other takes 10ms, run_computation 6.065ms and compute 6.015ms
+    assert_eq!(names, ["run_other", "run_computation", "compute"]);
+
+    let hottest = graph.hottest_by_mean(3);
+    let names = hottest.iter().map(|span| span.callsite.name()).collect::<Vec<_>>();
+    // compute takes 2.005ms on average
+    assert_eq!(names, ["run_other", "run_computation", "compute"]);
+
+    // asking for more spans than available returns all of them
+    assert_eq!(graph.hottest(100).len(), 5);
+    assert_eq!(graph.hottest_by_mean(100).len(), 5);
+    assert!(graph.hottest(0).is_empty());
+}
@@ -0,0 +1,21 @@
+#[test]
+fn is_data_collection_enabled() {
+    assert!(!time_graph::is_data_collection_enabled());
+
+    time_graph::enable_data_collection(true);
+    assert!(time_graph::is_data_collection_enabled());
+
+    time_graph::enable_data_collection(false);
+    assert!(!time_graph::is_data_collection_enabled());
+
+    time_graph::push_collection();
+    assert!(time_graph::is_data_collection_enabled());
+    time_graph::pop_collection();
+    assert!(!time_graph::is_data_collection_enabled());
+
+    {
+        let _guard = time_graph::collect();
+        assert!(time_graph::is_data_collection_enabled());
+    }
+    assert!(!time_graph::is_data_collection_enabled());
+}
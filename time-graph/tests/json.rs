@@ -0,0 +1,34 @@
+#![cfg(feature = "json")]
+
+#[time_graph::instrument]
+fn parent() {
+    child();
+    child();
+}
+
+#[time_graph::instrument]
+fn child() {}
+
+#[test]
+fn calls_direction() {
+    time_graph::enable_data_collection(true);
+    parent();
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+    let parsed = json::parse(&graph.as_json()).expect("invalid JSON");
+
+    let parent = parsed["timings"]["json::parent"]["id"].as_usize().unwrap();
+    let child = parsed["timings"]["json::child"]["id"].as_usize().unwrap();
+    assert_ne!(parent, child);
+
+    let calls = &parsed["calls"];
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0]["caller"].as_usize(), Some(parent));
+    assert_eq!(calls[0]["callee"].as_usize(), Some(child));
+    assert_eq!(calls[0]["count"].as_usize(), Some(2));
+
+    let call = graph.calls().next().unwrap();
+    assert_eq!(call.caller, parent);
+    assert_eq!(call.callee, child);
+}
@@ -0,0 +1,41 @@
+use time_graph::Level;
+
+#[time_graph::instrument(level = "warn")]
+fn request() {
+    for _ in 0..2 {
+        handler();
+    }
+}
+
+#[time_graph::instrument]
+fn handler() {
+    parse();
+}
+
+#[time_graph::instrument(level = "trace")]
+fn parse() {}
+
+#[test]
+fn level() {
+    time_graph::enable_data_collection(true);
+    request();
+
+    let graph = time_graph::get_full_graph();
+    assert_eq!(graph.span_by_name("request").unwrap().callsite.level(), Level::Warn);
+    assert_eq!(graph.span_by_name("handler").unwrap().callsite.level(), Level::Info);
+    assert_eq!(graph.span_by_name("parse").unwrap().callsite.level(), Level::Trace);
+
+    let filtered = graph.filter_by_level(Level::Trace);
+    assert_eq!(filtered.spans().count(), 3);
+
+    let filtered = graph.filter_by_level(Level::Info);
+    assert_eq!(filtered.spans().count(), 2);
+    assert!(filtered.span_by_name("parse").is_none());
+    let request = filtered.span_by_name("request").unwrap();
+    let handler = filtered.span_by_name("handler").unwrap();
+    let calls = filtered.calls().map(|call| (call.caller, call.callee, call.count)).collect::<Vec<_>>();
+    assert_eq!(calls, [(request.id, handler.id, 2)]);
+
+    let filtered = graph.filter_by_level(Level::Error);
+    assert_eq!(filtered.spans().count(), 0);
+}
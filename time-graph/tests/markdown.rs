@@ -0,0 +1,32 @@
+use time_graph::spanned;
+
+#[time_graph::instrument]
+fn outer() {
+    spanned!("pipe | in name", {
+        std::thread::sleep(std::time::Duration::from_millis(1));
+    });
+}
+
+#[test]
+fn markdown() {
+    time_graph::enable_data_collection(true);
+    outer();
+    outer();
+    time_graph::enable_data_collection(false);
+
+    let markdown = time_graph::get_full_graph().as_markdown();
+    let lines = markdown.lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 4);
+    assert_eq!(lines[0], "| id | span name | call count | total | mean |");
+    assert!(lines[1].starts_with("|---"));
+    assert!(lines[1].chars().all(|c| matches!(c, '|' | '-' | ':')));
+
+    // sorted by decreasing total time
+    assert!(lines[2].contains(" | outer | 2 | "));
+    assert!(lines[3].contains(" | pipe \\| in name | 2 | "));
+
+    // all rows have the same number of unescaped pipes
+    for line in lines {
+        assert_eq!(line.replace("\\|", "").matches('|').count(), 6);
+    }
+}
@@ -0,0 +1,44 @@
+use std::time::Duration;
+
+#[time_graph::instrument]
+fn sleep_recursive(count: usize) {
+    std::thread::sleep(Duration::from_millis(1));
+    if count > 0 {
+        sleep_recursive(count - 1);
+    }
+}
+
+#[time_graph::instrument]
+fn outer() {
+    sleep_recursive(5);
+}
+
+#[test]
+fn max_depth() {
+    time_graph::set_max_recording_depth(Some(2));
+    time_graph::enable_data_collection(true);
+
+    sleep_recursive(5);
+
+    let graph = time_graph::get_full_graph();
+    let recursive = graph.span_by_name("sleep_recursive").unwrap();
+    // only the top two frames are recorded
+    assert_eq!(recursive.called, 2);
+    // the outermost call still includes the time of all the nested calls
+    assert!(recursive.elapsed >= Duration::from_millis(6));
+
+    time_graph::clear_collected_data();
+    outer();
+
+    let graph = time_graph::get_full_graph();
+    assert_eq!(graph.span_by_name("outer").unwrap().called, 1);
+    assert_eq!(graph.span_by_name("sleep_recursive").unwrap().called, 1);
+
+    // removing the limit records everything again
+    time_graph::set_max_recording_depth(None);
+    time_graph::clear_collected_data();
+    outer();
+
+    let graph = time_graph::get_full_graph();
+    assert_eq!(graph.span_by_name("sleep_recursive").unwrap().called, 6);
+}
@@ -0,0 +1,66 @@
+#[time_graph::instrument]
+fn function_a(repeat: bool) {
+    if repeat {
+        function_b();
+    }
+}
+
+#[time_graph::instrument]
+fn function_b() {
+    function_a(false);
+}
+
+#[time_graph::instrument]
+fn recursive(count: usize) {
+    if count > 1 {
+        recursive(count - 1);
+    }
+}
+
+#[time_graph::instrument]
+fn only_second_run() {}
+
+#[test]
+fn merge() {
+    time_graph::enable_data_collection(true);
+    recursive(4);
+    function_a(true);
+    let mut graph = time_graph::get_full_graph();
+
+    time_graph::clear_collected_data();
+    recursive(4);
+    function_a(true);
+    only_second_run();
+    let other = time_graph::get_full_graph();
+    time_graph::enable_data_collection(false);
+
+    let called = |graph: &time_graph::FullCallGraph, name| {
+        graph.spans().find(|span| span.callsite.name() == name).map(|span| span.called)
+    };
+    let count = |graph: &time_graph::FullCallGraph, caller, callee| {
+        let id = |name| graph.spans().find(|span| span.callsite.name() == name).unwrap().id;
+        graph.calls()
+            .find(|call| call.caller == id(caller) && call.callee == id(callee))
+            .map(|call| call.count)
+    };
+
+    assert_eq!(called(&graph, "recursive"), Some(4));
+    assert_eq!(called(&graph, "only_second_run"), None);
+    assert_eq!(count(&graph, "recursive", "recursive"), Some(3));
+
+    graph.merge(&other);
+
+    assert_eq!(graph.spans().count(), 4);
+    assert_eq!(called(&graph, "recursive"), Some(8));
+    assert_eq!(called(&graph, "function_a"), Some(4));
+    assert_eq!(called(&graph, "function_b"), Some(2));
+    assert_eq!(called(&graph, "only_second_run"), Some(1));
+
+    assert_eq!(count(&graph, "recursive", "recursive"), Some(6));
+    assert_eq!(count(&graph, "function_a", "function_b"), Some(2));
+    assert_eq!(count(&graph, "function_b", "function_a"), Some(2));
+
+    for (index, span) in graph.spans().enumerate() {
+        assert_eq!(span.id, index);
+    }
+}
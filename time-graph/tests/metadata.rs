@@ -0,0 +1,41 @@
+#[time_graph::instrument(category = "io")]
+fn read_file() {
+    parse_file();
+}
+
+#[time_graph::instrument(category = "compute", kind = "parser")]
+fn parse_file() {}
+
+#[time_graph::instrument(name = "write", category = "io")]
+fn write_file() {}
+
+#[test]
+fn metadata() {
+    time_graph::enable_data_collection(true);
+
+    read_file();
+    write_file();
+
+    let graph = time_graph::get_full_graph();
+
+    let read = graph.span_by_name("read_file").unwrap();
+    assert_eq!(read.callsite.metadata(), &[("category", "io")]);
+
+    let parse = graph.span_by_name("parse_file").unwrap();
+    assert_eq!(parse.callsite.metadata(), &[("category", "compute"), ("kind", "parser")]);
+
+    let mut io = graph.spans_with_tag("category", "io")
+        .map(|span| span.callsite.name())
+        .collect::<Vec<_>>();
+    io.sort_unstable();
+    assert_eq!(io, ["read_file", "write"]);
+
+    let parsers = graph.spans_with_tag("kind", "parser").collect::<Vec<_>>();
+    assert_eq!(parsers.len(), 1);
+    assert_eq!(parsers[0].callsite.name(), "parse_file");
+
+    assert_eq!(graph.spans_with_tag("category", "network").count(), 0);
+
+    let callsite = time_graph::callsite!("manual", category = "io",);
+    assert_eq!(callsite.metadata(), &[("category", "io")]);
+}
@@ -0,0 +1,48 @@
+#[time_graph::instrument]
+fn process(data: &[u8]) {
+    time_graph::record_metric("bytes", data.len() as u64);
+    std::thread::sleep(std::time::Duration::from_millis(1));
+}
+
+#[test]
+fn metric() {
+    time_graph::enable_data_collection(true);
+
+    process(&[0; 100]);
+    process(&[0; 28]);
+
+    let span = time_graph::Span::new(time_graph::callsite!("guard"));
+    {
+        let guard = span.enter();
+        guard.record_metric("rows", 3);
+        guard.record_metric("rows", 4);
+    }
+
+    // nothing is recorded outside of spans or while collection is disabled
+    time_graph::record_metric("bytes", 1000);
+    time_graph::enable_data_collection(false);
+    process(&[0; 1000]);
+    time_graph::enable_data_collection(true);
+
+    let graph = time_graph::get_full_graph();
+    let process = graph.span_by_name("process").unwrap();
+    assert_eq!(process.metric("bytes"), Some(128));
+    assert_eq!(process.metric("rows"), None);
+
+    let guard = graph.span_by_name("guard").unwrap();
+    assert_eq!(guard.metric("rows"), Some(7));
+
+    #[cfg(feature = "table")]
+    {
+        use time_graph::{TableColumn, TableOptions};
+
+        let table = graph.as_table_with(TableOptions {
+            columns: vec![TableColumn::Name, TableColumn::Throughput("bytes")],
+            ..TableOptions::default()
+        });
+        assert!(table.contains("bytes/s"));
+
+        let throughput = 128.0 / process.elapsed.as_secs_f64();
+        assert!(table.contains(&format!("{:.2}", throughput)));
+    }
+}
@@ -0,0 +1,53 @@
+use time_graph::spanned;
+
+mod allowed {
+    #[time_graph::instrument]
+    pub fn outer() {
+        super::other::middle();
+    }
+
+    #[time_graph::instrument]
+    pub fn inner() {}
+
+    #[time_graph::instrument]
+    pub async fn run_async() {}
+}
+
+mod allowed_not {
+    #[time_graph::instrument]
+    pub fn not_recorded() {}
+}
+
+mod other {
+    #[time_graph::instrument]
+    pub fn middle() {
+        super::allowed::inner();
+    }
+}
+
+#[test]
+fn module_filter() {
+    time_graph::set_module_filter(vec!["module_filter::allowed".into()]);
+    time_graph::enable_data_collection(true);
+
+    allowed::outer();
+    allowed_not::not_recorded();
+    spanned!("top level", {});
+
+    let runtime = tokio::runtime::Builder::new_current_thread().build().unwrap();
+    runtime.block_on(allowed::run_async());
+
+    time_graph::set_module_filter(Vec::new());
+    spanned!("after reset", {});
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+    let mut names = graph.spans().map(|span| span.callsite.name()).collect::<Vec<_>>();
+    names.sort_unstable();
+    assert_eq!(names, ["after reset", "inner", "outer", "run_async"]);
+
+    // the filtered span in the middle is skipped in the call graph
+    let outer = graph.span_by_name("outer").unwrap();
+    let inner = graph.span_by_name("inner").unwrap();
+    assert!(graph.calls().any(|call| call.caller == outer.id && call.callee == inner.id));
+}
@@ -0,0 +1,50 @@
+#![cfg(feature = "table")]
+
+use std::collections::HashMap;
+
+use time_graph::{spanned, NameStyle};
+
+#[test]
+fn name_overrides() {
+    time_graph::enable_data_collection(true);
+
+    spanned!("public_step", {
+        spanned!("secret_algorithm", {});
+    });
+
+    let graph = time_graph::get_full_graph();
+    let secret = graph.span_by_name("secret_algorithm").unwrap();
+
+    let mut overrides = HashMap::new();
+    overrides.insert(secret.callsite.id(), "anonymous".to_string());
+    let anonymized = graph.with_name_overrides(&overrides);
+
+    let table = anonymized.as_table();
+    assert!(!table.contains("secret_algorithm"));
+    assert!(table.contains("name_overrides::anonymous"));
+    assert!(table.contains("name_overrides::public_step"));
+
+    assert_eq!(anonymized.spans().count(), 2);
+    assert_eq!(anonymized.calls().count(), 1);
+
+    // the other metadata of the span is kept
+    let span = anonymized.span_by_name("secret_algorithm").unwrap();
+    assert_eq!(anonymized.display_name(span, NameStyle::NameOnly), "anonymous");
+    assert_eq!(span.callsite.module_path(), "name_overrides");
+    assert_eq!(span.callsite.line(), secret.callsite.line());
+
+    // other exporters use the overrides as well
+    let markdown = anonymized.as_markdown();
+    assert!(markdown.contains("anonymous"));
+    assert!(!markdown.contains("secret_algorithm"));
+    assert!(!anonymized.as_dot().contains("secret_algorithm"));
+
+    let owned = time_graph::OwnedCallGraph::from(&anonymized);
+    let span = owned.spans().find(|span| span.name == "anonymous").unwrap();
+    assert_eq!(span.module_path, "name_overrides");
+    assert_eq!(span.line, secret.callsite.line());
+
+    // the call site is not modified
+    assert_eq!(secret.callsite.name(), "secret_algorithm");
+    assert!(graph.as_table().contains("secret_algorithm"));
+}
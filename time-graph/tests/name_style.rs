@@ -0,0 +1,42 @@
+use time_graph::NameStyle;
+
+mod outer {
+    pub mod inner {
+        #[time_graph::instrument]
+        pub fn function() {
+            time_graph::spanned!("some span", {});
+        }
+    }
+}
+
+#[test]
+fn name_style() {
+    time_graph::enable_data_collection(true);
+    outer::inner::function();
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+
+    let function = graph.span_by_name("function").unwrap().callsite;
+    assert_eq!(function.display_name(NameStyle::Full), "name_style::outer::inner::function");
+    assert_eq!(function.display_name(NameStyle::Full), function.full_name());
+    assert_eq!(function.display_name(NameStyle::ModuleLast), "inner::function");
+    assert_eq!(function.display_name(NameStyle::NameOnly), "function");
+
+    let span = graph.span_by_name("some span").unwrap().callsite;
+    assert_eq!(span.display_name(NameStyle::Full), "name_style::outer::inner::{some span}");
+    assert_eq!(span.display_name(NameStyle::ModuleLast), "inner::{some span}");
+    assert_eq!(span.display_name(NameStyle::NameOnly), "some span");
+
+    let tree = graph.as_tree_with_style(NameStyle::ModuleLast);
+    assert!(tree.starts_with("inner::function, called 1 times"));
+    assert!(tree.contains("\n    inner::{some span}, called 1 times"));
+    assert_eq!(graph.as_tree(), graph.as_tree_with_style(NameStyle::Full));
+
+    #[cfg(feature = "table")]
+    {
+        let table = graph.as_table_with_style(NameStyle::ModuleLast);
+        assert!(table.contains("inner::function"));
+        assert!(!table.contains("outer::inner"));
+    }
+}
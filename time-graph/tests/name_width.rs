@@ -0,0 +1,52 @@
+#![cfg(feature = "table")]
+
+use time_graph::{spanned, NameStyle, TableColumn, TableOptions};
+
+/// Get the width of the name column in the header of the table
+fn name_column_width(table: &str) -> usize {
+    let header = table.lines().nth(1).unwrap();
+    header.split('║').nth(1).unwrap().chars().count()
+}
+
+fn options() -> TableOptions {
+    TableOptions {
+        columns: vec![TableColumn::Name, TableColumn::Count],
+        name_style: NameStyle::NameOnly,
+        ..TableOptions::default()
+    }
+}
+
+#[test]
+fn name_width() {
+    time_graph::enable_data_collection(true);
+
+    spanned!("a", {
+        spanned!("bc", {});
+    });
+
+    // short names use a small column
+    let table = time_graph::get_full_graph().as_table_with(options());
+    let short = name_column_width(&table);
+    assert!(short >= "span name".len());
+    assert!(short <= 30);
+
+    time_graph::clear_collected_data();
+
+    const LONG: &str = "a very long span name, which is longer than the previous column width";
+    spanned!(LONG, {
+        spanned!("another long name, but not as long as the first one", {});
+    });
+
+    // long names are never truncated or wrapped
+    let table = time_graph::get_full_graph().as_table_with(options());
+    assert!(name_column_width(&table) >= LONG.len());
+    assert!(table.lines().any(|line| line.contains(LONG)));
+
+    // unless explicitly requested
+    let table = time_graph::get_full_graph().as_table_with(TableOptions {
+        name_width: Some(20),
+        ..options()
+    });
+    assert!(name_column_width(&table) <= 20);
+    assert!(!table.contains(LONG));
+}
@@ -0,0 +1,55 @@
+use std::sync::Mutex;
+use std::time::Duration;
+
+#[time_graph::instrument]
+fn outer() {
+    inner(1);
+    inner(2);
+}
+
+#[time_graph::instrument]
+fn inner(millis: u64) {
+    std::thread::sleep(Duration::from_millis(millis));
+}
+
+static COMPLETED: Mutex<Vec<(&'static str, Duration, bool)>> = Mutex::new(Vec::new());
+
+#[test]
+fn on_span_complete() {
+    time_graph::on_span_complete(|callsite, elapsed, parent| {
+        COMPLETED.lock().unwrap().push((callsite.name(), elapsed, parent.is_some()));
+    });
+
+    // no data collection, no callback
+    outer();
+    assert!(COMPLETED.lock().unwrap().is_empty());
+
+    time_graph::enable_data_collection(true);
+    outer();
+
+    {
+        let completed = COMPLETED.lock().unwrap();
+        assert_eq!(completed.len(), 3);
+
+        assert_eq!(completed[0].0, "inner");
+        assert!(completed[0].1 >= Duration::from_millis(1));
+        assert!(completed[0].2);
+
+        assert_eq!(completed[1].0, "inner");
+        assert!(completed[1].1 >= Duration::from_millis(2));
+        assert!(completed[1].2);
+
+        assert_eq!(completed[2].0, "outer");
+        assert!(completed[2].1 >= completed[0].1 + completed[1].1);
+        assert!(!completed[2].2);
+    }
+
+    let graph = time_graph::get_full_graph();
+    assert_eq!(graph.span_by_name("inner").unwrap().called, 2);
+
+    time_graph::remove_span_complete_callback();
+    outer();
+    time_graph::enable_data_collection(false);
+
+    assert_eq!(COMPLETED.lock().unwrap().len(), 3);
+}
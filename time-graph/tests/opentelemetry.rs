@@ -0,0 +1,57 @@
+#![cfg(feature = "opentelemetry")]
+
+use opentelemetry::trace::TracerProvider;
+use opentelemetry::Value;
+use opentelemetry_sdk::trace::{InMemorySpanExporter, SdkTracerProvider};
+
+#[time_graph::instrument]
+fn outer() {
+    inner();
+    inner();
+}
+
+#[time_graph::instrument]
+fn inner() {
+    std::thread::sleep(std::time::Duration::from_millis(1));
+}
+
+#[test]
+fn export_otel() {
+    time_graph::enable_data_collection(true);
+    outer();
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+
+    let exporter = InMemorySpanExporter::default();
+    let provider = SdkTracerProvider::builder()
+        .with_simple_exporter(exporter.clone())
+        .build();
+    let tracer = provider.tracer("time-graph");
+
+    graph.export_otel(&tracer);
+    provider.force_flush().unwrap();
+
+    let spans = exporter.get_finished_spans().unwrap();
+    assert_eq!(spans.len(), 2);
+
+    let outer = spans.iter().find(|span| span.name == "opentelemetry::outer").unwrap();
+    let inner = spans.iter().find(|span| span.name == "opentelemetry::inner").unwrap();
+
+    let called = |span: &opentelemetry_sdk::trace::SpanData| {
+        span.attributes.iter()
+            .find(|kv| kv.key.as_str() == "called")
+            .map(|kv| kv.value.clone())
+            .unwrap()
+    };
+    assert_eq!(called(outer), Value::I64(1));
+    assert_eq!(called(inner), Value::I64(2));
+
+    assert_eq!(inner.parent_span_id, outer.span_context.span_id());
+    assert_eq!(inner.span_context.trace_id(), outer.span_context.trace_id());
+
+    let expected = graph.span_by_name("inner").unwrap().elapsed;
+    assert_eq!(inner.end_time.duration_since(inner.start_time).unwrap(), expected);
+    assert!(inner.start_time >= outer.start_time);
+    assert!(inner.end_time <= outer.end_time);
+}
@@ -0,0 +1,24 @@
+#[time_graph::instrument]
+fn empty() {}
+
+#[time_graph::instrument]
+fn outer() {
+    for _ in 0..100 {
+        empty();
+    }
+}
+
+#[test]
+fn elapsed_adjusted() {
+    time_graph::enable_data_collection(true);
+    outer();
+
+    let graph = time_graph::get_full_graph();
+    for span in graph.spans() {
+        assert!(span.elapsed_adjusted() <= span.elapsed);
+    }
+
+    // the overhead is non-zero, and removed from each call
+    let empty = graph.span_by_name("empty").unwrap();
+    assert!(empty.elapsed_adjusted() < empty.elapsed);
+}
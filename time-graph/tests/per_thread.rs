@@ -0,0 +1,49 @@
+#![cfg(feature = "per-thread")]
+
+#[time_graph::instrument]
+fn function() {}
+
+#[test]
+fn per_thread() {
+    time_graph::enable_data_collection(true);
+
+    let first = std::thread::Builder::new()
+        .name("first".into())
+        .spawn(|| {
+            function();
+            function();
+        })
+        .unwrap();
+    let first_id = first.thread().id();
+    first.join().unwrap();
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    let second = std::thread::Builder::new()
+        .name("second".into())
+        .spawn(move || {
+            function();
+            // keep the thread alive until the graph is extracted
+            receiver.recv().unwrap();
+        })
+        .unwrap();
+    let second_id = second.thread().id();
+
+    // wait for the function to be called in the second thread
+    while time_graph::get_full_graph().span_by_name("function").map_or(0, |s| s.called) < 3 {
+        std::thread::yield_now();
+    }
+
+    let graph = time_graph::get_full_graph();
+    sender.send(()).unwrap();
+    second.join().unwrap();
+    time_graph::enable_data_collection(false);
+
+    let span = graph.span_by_name("function").unwrap();
+    assert_eq!(span.called, 3);
+
+    let per_thread = span.per_thread();
+    assert_eq!(per_thread.len(), 2);
+    assert_eq!(per_thread[&first_id].1, 2);
+    assert_eq!(per_thread[&second_id].1, 1);
+    assert_eq!(per_thread[&first_id].0 + per_thread[&second_id].0, span.elapsed);
+}
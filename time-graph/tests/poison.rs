@@ -0,0 +1,33 @@
+#[time_graph::instrument]
+fn panicking() {
+    inner();
+    panic!("oops");
+}
+
+#[time_graph::instrument]
+fn inner() {}
+
+#[time_graph::instrument]
+fn healthy() {}
+
+#[test]
+fn panic_in_instrumented_function() {
+    time_graph::enable_data_collection(true);
+
+    let result = std::panic::catch_unwind(panicking);
+    assert!(result.is_err());
+
+    // a thread panicking while running an instrumented function
+    let result = std::thread::spawn(panicking).join();
+    assert!(result.is_err());
+
+    healthy();
+
+    let graph = time_graph::get_full_graph();
+    assert_eq!(graph.span_by_name("panicking").unwrap().called, 2);
+    assert_eq!(graph.span_by_name("inner").unwrap().called, 2);
+    assert_eq!(graph.span_by_name("healthy").unwrap().called, 1);
+
+    time_graph::clear_collected_data();
+    assert_eq!(time_graph::get_full_graph().spans().count(), 0);
+}
@@ -0,0 +1,46 @@
+#![cfg(feature = "prometheus")]
+
+use time_graph::spanned;
+
+#[time_graph::instrument]
+fn function() {
+    spanned!("quoted \"span\"\nwith \\ new line", {});
+    spanned!("duplicated", {});
+    spanned!("duplicated", {});
+}
+
+#[test]
+fn prometheus() {
+    time_graph::enable_data_collection(true);
+    function();
+    function();
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+    let output = graph.as_prometheus();
+
+    assert!(output.contains("# TYPE timegraph_span_elapsed_seconds_total counter\n"));
+    assert!(output.contains("# TYPE timegraph_span_calls_total counter\n"));
+
+    let elapsed = output.lines()
+        .filter(|line| line.starts_with("timegraph_span_elapsed_seconds_total{"))
+        .collect::<Vec<_>>();
+    // the two "duplicated" callsites share a single series
+    assert_eq!(graph.spans().count(), 4);
+    assert_eq!(elapsed.len(), 3);
+
+    let calls = output.lines()
+        .filter(|line| line.starts_with("timegraph_span_calls_total{"))
+        .collect::<Vec<_>>();
+    assert_eq!(calls.len(), 3);
+    assert!(calls.contains(&"timegraph_span_calls_total{name=\"function\",module=\"prometheus\"} 2"));
+    assert!(calls.contains(&"timegraph_span_calls_total{name=\"quoted \\\"span\\\"\\nwith \\\\ new line\",module=\"prometheus\"} 2"));
+    assert!(calls.contains(&"timegraph_span_calls_total{name=\"duplicated\",module=\"prometheus\"} 4"));
+
+    let span = graph.span_by_name("function").unwrap();
+    let expected = format!(
+        "timegraph_span_elapsed_seconds_total{{name=\"function\",module=\"prometheus\"}} {}",
+        span.elapsed.as_secs_f64()
+    );
+    assert!(elapsed.contains(&expected.as_str()));
+}
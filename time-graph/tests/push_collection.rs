@@ -0,0 +1,42 @@
+#[time_graph::instrument]
+fn function() {}
+
+#[test]
+fn push_pop_collection() {
+    function();
+
+    // outer library
+    time_graph::push_collection();
+    function();
+
+    // inner library
+    time_graph::push_collection();
+    function();
+    time_graph::pop_collection();
+
+    // collection is still enabled for the outer library
+    function();
+    time_graph::pop_collection();
+
+    // collection is disabled now
+    function();
+    // extra calls to pop_collection are ignored
+    time_graph::pop_collection();
+    function();
+
+    assert_eq!(time_graph::get_full_graph().span_by_name("function").unwrap().called, 3);
+
+    // enable_data_collection(false) disables collection unconditionally
+    time_graph::push_collection();
+    time_graph::push_collection();
+    time_graph::enable_data_collection(false);
+    function();
+
+    time_graph::enable_data_collection(true);
+    function();
+    time_graph::enable_data_collection(true);
+    time_graph::pop_collection();
+    function();
+
+    assert_eq!(time_graph::get_full_graph().span_by_name("function").unwrap().called, 4);
+}
@@ -0,0 +1,48 @@
+struct Circle {
+    radius: f64,
+}
+
+impl Circle {
+    #[time_graph::instrument(qualified)]
+    fn area(&self) -> f64 {
+        std::f64::consts::PI * self.radius * self.radius
+    }
+
+    #[time_graph::instrument(qualified, name = "new")]
+    fn with_radius(radius: f64) -> Circle {
+        Circle { radius }
+    }
+}
+
+struct Wrapper<T>(T);
+
+impl<T: Copy> Wrapper<T> {
+    #[time_graph::instrument(qualified, record = "factor")]
+    fn get(&self, factor: u8) -> T {
+        let _ = factor;
+        self.0
+    }
+}
+
+#[test]
+fn qualified() {
+    time_graph::enable_data_collection(true);
+
+    let circle = Circle::with_radius(2.0);
+    circle.area();
+    circle.area();
+
+    Wrapper(3u8).get(1);
+    Wrapper(3u16).get(1);
+
+    let graph = time_graph::get_full_graph();
+
+    let area = graph.span_by_name("Circle::area").unwrap();
+    assert_eq!(area.called, 2);
+    assert_eq!(area.callsite.full_name(), format!("{}::Circle::area", module_path!()));
+
+    assert_eq!(graph.span_by_name("Circle::new").unwrap().called, 1);
+
+    assert!(graph.span_by_name("Wrapper<u8>::get(factor=1)").is_some());
+    assert!(graph.span_by_name("Wrapper<u16>::get(factor=1)").is_some());
+}
@@ -0,0 +1,31 @@
+#[time_graph::instrument(record = "x")]
+fn compute(x: usize) -> usize {
+    x * 2
+}
+
+#[time_graph::instrument(name = "pair", record = "a", record = "b")]
+fn pair(a: &str, b: i32) {
+    let _ = (a, b);
+}
+
+#[test]
+fn record() {
+    time_graph::enable_data_collection(true);
+
+    compute(3);
+    compute(5);
+    compute(3);
+    pair("first", -1);
+
+    let graph = time_graph::get_full_graph();
+
+    let three = graph.span_by_name("compute(x=3)").unwrap();
+    assert_eq!(three.called, 2);
+    assert_eq!(three.callsite.module_path(), module_path!());
+
+    let five = graph.span_by_name("compute(x=5)").unwrap();
+    assert_eq!(five.called, 1);
+    assert_ne!(three.id, five.id);
+
+    assert!(graph.span_by_name("pair(a=\"first\", b=-1)").is_some());
+}
@@ -0,0 +1,51 @@
+use std::time::Duration;
+
+#[time_graph::instrument]
+fn sleep_recursive(count: usize) {
+    std::thread::sleep(Duration::from_millis(10));
+    if count > 0 {
+        sleep_recursive(count - 1);
+    }
+}
+
+#[time_graph::instrument]
+fn function_a(repeat: bool) {
+    std::thread::sleep(Duration::from_millis(10));
+    if repeat {
+        function_b();
+    }
+}
+
+#[time_graph::instrument]
+fn function_b() {
+    std::thread::sleep(Duration::from_millis(10));
+    function_a(false);
+}
+
+#[test]
+fn recursion() {
+    time_graph::enable_data_collection(true);
+    sleep_recursive(5);
+    function_a(true);
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+
+    let recursive = graph.span_by_name("sleep_recursive").unwrap();
+    assert_eq!(recursive.called, 6);
+    // only the outermost call is included in the elapsed time
+    assert!(recursive.elapsed >= Duration::from_millis(60));
+    assert!(recursive.elapsed < Duration::from_millis(100));
+    assert_eq!(recursive.min, recursive.elapsed);
+    assert_eq!(recursive.max, recursive.elapsed);
+    assert_eq!(recursive.self_time, recursive.elapsed);
+
+    let function_a = graph.span_by_name("function_a").unwrap();
+    let function_b = graph.span_by_name("function_b").unwrap();
+    assert_eq!(function_a.called, 2);
+    assert_eq!(function_b.called, 1);
+    assert!(function_a.elapsed >= Duration::from_millis(30));
+    assert!(function_a.elapsed < Duration::from_millis(50));
+    assert!(function_b.elapsed >= Duration::from_millis(20));
+    assert!(function_b.elapsed < function_a.elapsed);
+}
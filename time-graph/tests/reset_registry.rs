@@ -0,0 +1,84 @@
+use std::sync::Mutex;
+
+// tests in the same binary run in parallel, but the registry is global
+static LOCK: Mutex<()> = Mutex::new(());
+
+#[time_graph::instrument]
+fn first() {}
+
+#[time_graph::instrument]
+fn second() {}
+
+fn registered_names() -> Vec<&'static str> {
+    let mut names = Vec::new();
+    time_graph::traverse_registered_callsite(|callsite| names.push(callsite.name()));
+    names.sort_unstable();
+    names
+}
+
+#[test]
+fn first_test() {
+    let _lock = LOCK.lock().unwrap();
+    time_graph::reset_registry();
+    assert!(registered_names().is_empty());
+
+    time_graph::enable_data_collection(true);
+    first();
+    let _ = time_graph::callsite!("first callsite");
+    time_graph::enable_data_collection(false);
+
+    assert_eq!(registered_names(), ["first", "first callsite"]);
+    assert_eq!(time_graph::get_full_graph().spans().count(), 1);
+}
+
+#[test]
+fn second_test() {
+    let _lock = LOCK.lock().unwrap();
+    time_graph::reset_registry();
+    assert!(registered_names().is_empty());
+    assert_eq!(time_graph::get_full_graph().spans().count(), 0);
+
+    time_graph::enable_data_collection(true);
+    second();
+    time_graph::enable_data_collection(false);
+
+    assert_eq!(registered_names(), ["second"]);
+    let graph = time_graph::get_full_graph();
+    assert_eq!(graph.spans().count(), 1);
+    assert!(graph.span_by_name("second").is_some());
+}
+
+#[time_graph::instrument]
+fn third() {}
+
+#[test]
+fn used_after_reset() {
+    let _lock = LOCK.lock().unwrap();
+    time_graph::enable_data_collection(true);
+    third();
+
+    time_graph::reset_registry();
+    assert!(registered_names().is_empty());
+
+    // the call site is registered again when it is used after the reset
+    third();
+    assert_eq!(registered_names(), ["third"]);
+    let graph = time_graph::get_full_graph();
+    assert_eq!(graph.span_by_name("third").unwrap().called, 1);
+
+    // spans which started before the reset are registered again when they
+    // finish, and skipped until then
+    time_graph::spanned!("outer", {
+        time_graph::reset_registry();
+        third();
+        let graph = time_graph::get_full_graph();
+        assert!(graph.span_by_name("outer").is_none());
+        assert_eq!(graph.span_by_name("third").unwrap().called, 1);
+        assert_eq!(graph.calls().count(), 0);
+    });
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+    assert_eq!(graph.span_by_name("outer").unwrap().called, 1);
+    assert_eq!(graph.calls().count(), 1);
+}
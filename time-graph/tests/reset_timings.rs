@@ -0,0 +1,46 @@
+use std::time::Duration;
+
+#[time_graph::instrument]
+fn parent() {
+    child();
+    child();
+}
+
+#[time_graph::instrument]
+fn child() {
+    std::thread::sleep(Duration::from_millis(1));
+}
+
+#[test]
+fn reset_timings() {
+    time_graph::enable_data_collection(true);
+    parent();
+
+    let before = time_graph::get_full_graph();
+    time_graph::reset_timings();
+    let after = time_graph::get_full_graph();
+
+    assert_eq!(after.spans().count(), 2);
+    for span in after.spans() {
+        let previous = before.spans().find(|previous| previous.id == span.id).unwrap();
+        assert_eq!(span.callsite.id(), previous.callsite.id());
+
+        assert_eq!(span.elapsed, Duration::ZERO);
+        assert_eq!(span.called, 0);
+        assert_eq!(span.min, Duration::ZERO);
+        assert_eq!(span.max, Duration::ZERO);
+        assert!(span.first_seen.is_none());
+    }
+
+    let calls = after.calls().collect::<Vec<_>>();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].count, 0);
+    assert_eq!(calls[0].elapsed, Duration::ZERO);
+
+    // recording data again re-uses the same spans
+    parent();
+    let graph = time_graph::get_full_graph();
+    assert_eq!(graph.span_by_name("parent").unwrap().called, 1);
+    assert_eq!(graph.span_by_name("child").unwrap().called, 2);
+    assert_eq!(graph.calls().next().unwrap().count, 2);
+}
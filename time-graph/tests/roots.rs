@@ -0,0 +1,45 @@
+mod common;
+
+use common::{run_computation, run_other};
+
+#[time_graph::instrument]
+fn recursive(count: usize) {
+    if count > 0 {
+        recursive(count - 1);
+    }
+}
+
+#[time_graph::instrument]
+fn function_a(repeat: bool) {
+    if repeat {
+        function_b();
+    }
+}
+
+#[time_graph::instrument]
+fn function_b() {
+    function_a(false);
+}
+
+#[test]
+fn roots() {
+    time_graph::enable_data_collection(true);
+    run_other(5);
+    run_computation(3);
+    recursive(3);
+    let graph = time_graph::get_full_graph();
+
+    let mut roots = graph.roots().map(|span| span.callsite.name()).collect::<Vec<_>>();
+    roots.sort_unstable();
+    assert_eq!(roots, ["recursive", "run_computation", "run_other"]);
+
+    // mutually recursive functions, without any root
+    time_graph::clear_collected_data();
+    function_a(true);
+    let graph = time_graph::get_full_graph();
+    time_graph::enable_data_collection(false);
+
+    let roots = graph.roots().collect::<Vec<_>>();
+    assert_eq!(roots.len(), 1);
+    assert_eq!(roots[0].id, 0);
+}
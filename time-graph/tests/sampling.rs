@@ -0,0 +1,48 @@
+use time_graph::{callsite, set_sampling, spanned, Span};
+
+#[test]
+fn sampling() {
+    let hot = callsite!("hot");
+    set_sampling(Some(hot), 10);
+    let span = Span::new(hot);
+
+    time_graph::enable_data_collection(true);
+    spanned!("outer", {
+        for _ in 0..1000 {
+            let guard = span.enter();
+            guard.record_metric("items", 2);
+        }
+    });
+
+    // global sampling rate
+    set_sampling(None, 4);
+    for _ in 0..100 {
+        spanned!("global", {
+            time_graph::record_metric("items", 1);
+        });
+    }
+    set_sampling(None, 1);
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+
+    let hot = graph.span_by_name("hot").unwrap();
+    assert!(hot.is_sampled());
+    assert_eq!(hot.sampling_rate, 10);
+    assert!((990..=1010).contains(&hot.called));
+    assert!((1980..=2020).contains(&hot.metric("items").unwrap()));
+
+    let outer = graph.span_by_name("outer").unwrap();
+    assert!(!outer.is_sampled());
+    assert_eq!(outer.sampling_rate, 1);
+    assert_eq!(outer.called, 1);
+
+    let call = graph.calls().find(|call| call.callee == hot.id).unwrap();
+    assert_eq!(call.caller, outer.id);
+    assert_eq!(call.count, hot.called as usize);
+
+    let global = graph.span_by_name("global").unwrap();
+    assert_eq!(global.sampling_rate, 4);
+    assert_eq!(global.called, 100);
+    assert_eq!(global.metric("items"), Some(100));
+}
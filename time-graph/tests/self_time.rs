@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+mod common;
+
+use common::run_computation;
+
+#[test]
+fn self_time() {
+    time_graph::set_clock(common::ManualClock);
+    time_graph::enable_data_collection(true);
+    run_computation(5);
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+    let find = |name| graph.spans().find(|span| span.callsite.name() == name).unwrap();
+
+    // 10 calls from compute, 1 from another span and 25 from run_computation
+    let bottom = find("bottom_5us");
+    assert_eq!(bottom.self_time, bottom.elapsed);
+    assert_eq!(bottom.self_time, Duration::from_micros(36 * 5));
+
+    let compute = find("compute");
+    assert_eq!(compute.elapsed, Duration::from_micros(5 * 2_000 + 10 * 5));
+    assert_eq!(compute.self_time, Duration::from_millis(10));
+
+    let another = find("another span");
+    assert_eq!(another.self_time, Duration::ZERO);
+
+    let run_computation = find("run_computation");
+    assert_eq!(run_computation.elapsed, Duration::from_micros(10_050 + 5 + 25 * 5));
+    assert_eq!(run_computation.self_time, Duration::ZERO);
+
+    let sorted = graph.spans_by_self_time();
+    assert_eq!(sorted[0].callsite.name(), "compute");
+    assert_eq!(sorted[1].callsite.name(), "bottom_5us");
+    for window in sorted.windows(2) {
+        assert!(window[0].self_time >= window[1].self_time);
+    }
+}
@@ -0,0 +1,76 @@
+#![cfg(feature = "serde")]
+
+use serde::Deserialize;
+
+#[derive(Deserialize)]
+struct Span {
+    id: usize,
+    name: String,
+    module_path: String,
+    elapsed_ns: u128,
+    called: u32,
+}
+
+#[derive(Deserialize)]
+struct Call {
+    caller: usize,
+    callee: usize,
+    count: usize,
+}
+
+#[derive(Deserialize)]
+struct Graph {
+    spans: Vec<Span>,
+    calls: Vec<Call>,
+}
+
+#[time_graph::instrument]
+fn parent() {
+    child();
+    child();
+}
+
+#[time_graph::instrument]
+fn child() {}
+
+#[test]
+fn round_trip() {
+    time_graph::enable_data_collection(true);
+    parent();
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+    let serialized = serde_json::to_string(&graph).unwrap();
+    let deserialized: Graph = serde_json::from_str(&serialized).unwrap();
+
+    assert_eq!(deserialized.spans.len(), graph.spans().count());
+    for (span, expected) in deserialized.spans.iter().zip(graph.spans()) {
+        assert_eq!(span.id, expected.id);
+        assert_eq!(span.name, expected.callsite.name());
+        assert_eq!(span.module_path, "serde");
+        assert_eq!(span.elapsed_ns, expected.elapsed.as_nanos());
+        assert_eq!(span.called, expected.called);
+    }
+
+    assert_eq!(deserialized.calls.len(), 1);
+    let parent = deserialized.spans.iter().find(|span| span.name == "parent").unwrap();
+    let child = deserialized.spans.iter().find(|span| span.name == "child").unwrap();
+    assert_eq!(deserialized.calls[0].caller, parent.id);
+    assert_eq!(deserialized.calls[0].callee, child.id);
+    assert_eq!(deserialized.calls[0].count, 2);
+
+    let owned: time_graph::OwnedCallGraph = serde_json::from_str(&serialized).unwrap();
+    assert_eq!(owned.spans().count(), graph.spans().count());
+    assert_eq!(owned.calls().count(), graph.calls().count());
+    for (span, expected) in owned.spans().zip(graph.spans()) {
+        assert_eq!(span.full_name(), expected.callsite.full_name());
+        assert_eq!(span.elapsed, expected.elapsed);
+        assert_eq!(span.self_time, expected.self_time);
+    }
+
+    #[cfg(feature = "table")]
+    assert!(owned.as_table().contains("serde::parent"));
+
+    let reserialized = serde_json::to_string(&owned).unwrap();
+    assert_eq!(reserialized, serialized);
+}
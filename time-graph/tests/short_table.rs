@@ -0,0 +1,37 @@
+#![cfg(feature = "table")]
+
+use std::time::Duration;
+
+#[time_graph::instrument]
+fn fast() {}
+
+#[time_graph::instrument]
+fn slow() {
+    std::thread::sleep(Duration::from_millis(2));
+    fast();
+}
+
+#[test]
+fn short_table() {
+    time_graph::enable_data_collection(true);
+    slow();
+    fast();
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+    let table = graph.as_short_table();
+
+    let header = table.lines().nth(1).unwrap();
+    assert!(header.contains("span name"));
+    assert!(header.contains("total"));
+    assert!(header.contains("mean"));
+    assert!(!header.contains("called by"));
+    assert!(!header.contains("min"));
+    assert!(!header.contains("max"));
+
+    // short names and sorted by decreasing total time
+    assert!(!table.contains("short_table::"));
+    let slow = table.find("slow").unwrap();
+    let fast = table.find("fast").unwrap();
+    assert!(slow < fast);
+}
@@ -0,0 +1,26 @@
+use time_graph::spanned;
+
+#[test]
+fn snapshot_totals() {
+    time_graph::enable_data_collection(true);
+
+    spanned!("outer", {
+        for _ in 0..2 {
+            spanned!("inner", {});
+        }
+    });
+    std::thread::spawn(|| spanned!("other thread", {})).join().unwrap();
+
+    let totals = time_graph::snapshot_totals();
+    let graph = time_graph::get_full_graph();
+
+    assert_eq!(totals.len(), 3);
+    assert!(totals.windows(2).all(|pair| pair[0].0 < pair[1].0));
+    for span in graph.spans() {
+        let &(_, elapsed, called) = totals.iter()
+            .find(|(id, _, _)| id == &span.callsite.id())
+            .unwrap();
+        assert_eq!(elapsed, span.elapsed);
+        assert_eq!(called, span.called);
+    }
+}
@@ -0,0 +1,53 @@
+#[time_graph::instrument]
+fn root() {
+    first();
+    second();
+    first();
+}
+
+#[time_graph::instrument]
+fn first() {
+    leaf();
+}
+
+#[time_graph::instrument]
+fn second() {
+    leaf();
+}
+
+#[time_graph::instrument]
+fn leaf() {}
+
+type Spans = Vec<(usize, String)>;
+type Calls = Vec<(usize, usize, usize)>;
+
+fn summarize(graph: &time_graph::FullCallGraph) -> (Spans, Calls) {
+    let spans = graph.spans_sorted().into_iter()
+        .map(|span| (span.id, span.callsite.full_name()))
+        .collect();
+    let calls = graph.calls_sorted().into_iter()
+        .map(|call| (call.caller, call.callee, call.count))
+        .collect();
+    (spans, calls)
+}
+
+#[test]
+fn sorted() {
+    time_graph::enable_data_collection(true);
+
+    root();
+    let (spans, calls) = summarize(&time_graph::get_full_graph());
+
+    let ids = spans.iter().map(|(id, _)| *id).collect::<Vec<_>>();
+    assert!(ids.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(spans.len(), 4);
+
+    let pairs = calls.iter().map(|&(caller, callee, _)| (caller, callee)).collect::<Vec<_>>();
+    assert!(pairs.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(calls.len(), 4);
+
+    // running the same code again gives the same output
+    time_graph::clear_collected_data();
+    root();
+    assert_eq!(summarize(&time_graph::get_full_graph()), (spans, calls));
+}
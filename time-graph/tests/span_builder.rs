@@ -0,0 +1,31 @@
+use time_graph::SpanBuilder;
+
+#[test]
+fn span_builder() {
+    time_graph::enable_data_collection(true);
+
+    for _ in 0..3 {
+        let _outer = SpanBuilder::new("runtime span").module("config::loader").tag("kind", "dynamic").enter();
+        let _inner = SpanBuilder::new("inner runtime span").enter();
+    }
+
+    // a different set of tags creates a different span
+    let span = SpanBuilder::new("runtime span").module("config::loader").tag("kind", "other").build();
+    span.enter().finish();
+
+    let graph = time_graph::get_full_graph();
+    let tagged = graph.spans_with_tag("kind", "dynamic").collect::<Vec<_>>();
+    assert_eq!(tagged.len(), 1);
+    assert_eq!(tagged[0].callsite.name(), "runtime span");
+    assert_eq!(tagged[0].callsite.module_path(), "config::loader");
+    assert_eq!(tagged[0].callsite.file(), file!());
+    assert_eq!(tagged[0].called, 3);
+
+    let other = graph.spans_with_tag("kind", "other").collect::<Vec<_>>();
+    assert_eq!(other.len(), 1);
+    assert_eq!(other[0].called, 1);
+
+    let inner = graph.span_by_name("inner runtime span").unwrap();
+    assert_eq!(inner.called, 3);
+    assert_eq!(graph.call_count(tagged[0].id, inner.id), 3);
+}
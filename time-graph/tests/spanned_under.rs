@@ -0,0 +1,28 @@
+use time_graph::{spanned, spanned_under, current_span_id};
+
+#[test]
+fn spanned_under() {
+    time_graph::enable_data_collection(true);
+
+    let parent = spanned!("parent", current_span_id());
+    assert!(parent.is_some());
+
+    spanned!("unrelated", {
+        // the thread-local parent is `unrelated`, but the child is recorded
+        // under `parent`
+        spanned_under!(parent, "child", {});
+    });
+
+    let graph = time_graph::get_full_graph();
+    let parent = graph.span_by_name("parent").unwrap();
+    let unrelated = graph.span_by_name("unrelated").unwrap();
+    let child = graph.span_by_name("child").unwrap();
+
+    let calls = graph.calls().collect::<Vec<_>>();
+    assert_eq!(calls.len(), 1);
+    assert_eq!(calls[0].caller, parent.id);
+    assert_eq!(calls[0].callee, child.id);
+    assert_eq!(calls[0].count, 1);
+
+    assert!(graph.calls().all(|call| call.caller != unrelated.id));
+}
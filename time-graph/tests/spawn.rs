@@ -0,0 +1,29 @@
+use time_graph::spanned;
+
+#[test]
+fn spawn() {
+    time_graph::enable_data_collection(true);
+
+    spanned!("spawning", {
+        let handle = time_graph::spawn(|| {
+            spanned!("spawned", {});
+            42
+        });
+        assert_eq!(handle.join().unwrap(), 42);
+    });
+
+    // threads spawned outside of spans are not attached to any span
+    time_graph::spawn(|| spanned!("root", {})).join().unwrap();
+
+    let graph = time_graph::get_full_graph();
+    let spawning = graph.span_by_name("spawning").unwrap();
+    let spawned = graph.span_by_name("spawned").unwrap();
+    let root = graph.span_by_name("root").unwrap();
+
+    let parents = graph.parents_of(spawned.id)
+        .map(|(span, count)| (span.id, count))
+        .collect::<Vec<_>>();
+    assert_eq!(parents, [(spawning.id, 1)]);
+
+    assert_eq!(graph.parents_of(root.id).count(), 0);
+}
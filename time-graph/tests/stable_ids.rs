@@ -0,0 +1,60 @@
+use time_graph::{dynamic_spanned, spanned};
+
+#[time_graph::instrument]
+fn first() {}
+
+#[time_graph::instrument]
+fn second() {
+    first();
+}
+
+fn workload(reversed: bool) {
+    let mut names = vec!["alpha", "beta"];
+    if reversed {
+        names.reverse();
+    }
+
+    spanned!("outer", {
+        if reversed {
+            second();
+            for name in &names {
+                dynamic_spanned!(*name, {});
+            }
+        } else {
+            for name in &names {
+                dynamic_spanned!(*name, {});
+            }
+            first();
+            second();
+        }
+    });
+}
+
+fn ids() -> Vec<(usize, String)> {
+    let graph = time_graph::get_full_graph();
+    let mut ids = graph.spans().map(|span| (span.id, span.callsite.full_name())).collect::<Vec<_>>();
+    ids.sort();
+    ids
+}
+
+#[test]
+fn stable_ids() {
+    time_graph::enable_data_collection(true);
+
+    workload(false);
+    let first_run = ids();
+    assert_eq!(first_run.len(), 5);
+
+    time_graph::clear_collected_data();
+    workload(true);
+    let second_run = ids();
+
+    assert_eq!(first_run, second_run);
+
+    // ids follow the source location
+    let graph = time_graph::get_full_graph();
+    let lines = graph.spans_sorted().iter().map(|span| span.callsite.line()).collect::<Vec<_>>();
+    let mut sorted_lines = lines.clone();
+    sorted_lines.sort();
+    assert_eq!(lines, sorted_lines);
+}
@@ -0,0 +1,21 @@
+#![cfg(feature = "std-clock")]
+
+use std::time::Duration;
+
+#[test]
+fn std_clock() {
+    time_graph::enable_data_collection(true);
+
+    for _ in 0..3 {
+        time_graph::spanned!("sleep", {
+            std::thread::sleep(Duration::from_millis(10));
+        });
+    }
+
+    let graph = time_graph::get_full_graph();
+    let span = graph.span_by_name("sleep").unwrap();
+    assert_eq!(span.called, 3);
+    assert!(span.min >= Duration::from_millis(10));
+    assert!(span.elapsed >= Duration::from_millis(30));
+    assert!(span.elapsed < Duration::from_millis(300));
+}
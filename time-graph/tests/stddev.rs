@@ -0,0 +1,69 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+
+static NOW: AtomicU64 = AtomicU64::new(0);
+
+struct ManualClock;
+
+impl time_graph::TimeSource for ManualClock {
+    fn now(&self) -> u64 {
+        NOW.load(Ordering::SeqCst)
+    }
+}
+
+#[time_graph::instrument]
+fn advance(milliseconds: u64) {
+    NOW.fetch_add(milliseconds * 1_000_000, Ordering::SeqCst);
+}
+
+#[time_graph::instrument]
+fn once() {}
+
+#[test]
+fn stddev() {
+    time_graph::set_clock(ManualClock);
+    time_graph::enable_data_collection(true);
+
+    for milliseconds in [2, 4, 4, 4] {
+        advance(milliseconds);
+    }
+    // the remaining calls come from another thread, and are merged with the
+    // values from this thread
+    std::thread::spawn(|| {
+        for milliseconds in [5, 5, 7, 9] {
+            advance(milliseconds);
+        }
+    }).join().unwrap();
+
+    once();
+
+    let graph = time_graph::get_full_graph();
+
+    // sample standard deviation of the values above is sqrt(32 / 7) ms
+    let span = graph.span_by_name("advance").unwrap();
+    assert_eq!(span.called, 8);
+    let expected = (32.0f64 / 7.0).sqrt() * 1e-3;
+    assert!((span.stddev().as_secs_f64() - expected).abs() < 1e-9);
+
+    assert_eq!(graph.span_by_name("once").unwrap().stddev(), Duration::ZERO);
+
+    // merging graphs combines the standard deviations, here each value is
+    // present twice and the sample standard deviation is sqrt(64 / 15) ms
+    let mut merged = time_graph::get_full_graph();
+    merged.merge(&graph);
+    let span = merged.span_by_name("advance").unwrap();
+    assert_eq!(span.called, 16);
+    let expected = (64.0f64 / 15.0).sqrt() * 1e-3;
+    assert!((span.stddev().as_secs_f64() - expected).abs() < 1e-9);
+
+    #[cfg(feature = "table")]
+    {
+        let options = time_graph::TableOptions {
+            columns: vec![time_graph::TableColumn::Name, time_graph::TableColumn::StdDev],
+            ..Default::default()
+        };
+        let table = graph.as_table_with(options);
+        assert!(table.contains("std dev"));
+        assert!(table.contains("2.14ms"));
+    }
+}
@@ -0,0 +1,33 @@
+use std::time::Duration;
+
+mod common;
+
+use common::{run_computation, run_other};
+
+#[test]
+fn summary() {
+    time_graph::set_clock(common::ManualClock);
+    time_graph::enable_data_collection(true);
+    run_other(5);
+    run_computation(3);
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+    let summary = graph.summary();
+
+    assert_eq!(summary.total_spans, 5);
+    // 1 run_other, 1 run_computation, 3 compute, 1 another span,
+    // 3 + 1 + 9 bottom_5us
+    assert_eq!(summary.total_calls, 19);
+    assert_eq!(summary.total_elapsed, graph.spans().map(|span| span.elapsed).sum());
+
+    // run_computation takes 3 * 2ms + 13 * 5µs
+    let slowest = summary.slowest_span.unwrap();
+    let expected = graph.span_by_name("run_computation").unwrap();
+    assert_eq!(slowest.id, expected.id);
+    assert_eq!(slowest.name, expected.callsite.full_name());
+    assert_eq!(slowest.elapsed, Duration::from_micros(6_065));
+
+    // run_computation => compute => bottom_5us
+    assert_eq!(summary.deepest_depth, 3);
+}
@@ -0,0 +1,40 @@
+#![cfg(feature = "table")]
+
+use time_graph::{spanned, TableColumn, TableOptions};
+
+#[test]
+fn zero_call_count() {
+    time_graph::enable_data_collection(true);
+    let graph = spanned!("outer", {
+        spanned!("inner", {});
+        // the outer span is in the graph, but did not finish running yet
+        time_graph::get_full_graph()
+    });
+    time_graph::enable_data_collection(false);
+
+    let outer = graph.spans().find(|span| span.callsite.name() == "outer").unwrap();
+    assert_eq!(outer.called, 0);
+
+    let table = graph.as_table();
+    assert!(table.contains("table::outer"));
+    assert!(table.contains("table::inner"));
+
+    let table = graph.as_table_with(TableOptions {
+        columns: vec![TableColumn::Name, TableColumn::SelfTime, TableColumn::Count],
+        sort_by: Some(TableColumn::Name),
+        descending: true,
+        ..TableOptions::default()
+    });
+
+    let header = table.lines().nth(1).unwrap();
+    let header = header.split('║')
+        .map(|cell| cell.trim())
+        .filter(|cell| !cell.is_empty())
+        .collect::<Vec<_>>();
+    assert_eq!(header, ["span name", "self time", "call count"]);
+    assert!(!table.contains("called by"));
+
+    let outer = table.find("table::outer").unwrap();
+    let inner = table.find("table::inner").unwrap();
+    assert!(outer < inner);
+}
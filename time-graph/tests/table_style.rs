@@ -0,0 +1,35 @@
+#![cfg(feature = "table")]
+
+use time_graph::{TableOptions, TableStyle};
+
+#[time_graph::instrument]
+fn outer() {
+    inner();
+}
+
+#[time_graph::instrument]
+fn inner() {}
+
+#[test]
+fn simple_style() {
+    time_graph::enable_data_collection(true);
+    outer();
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+
+    let table = graph.as_table_styled(TableStyle::simple());
+    assert!(table.starts_with("+----+"));
+    assert!(table.contains("| table_style::outer"));
+    // missing values, warnings and durations in µs are also ASCII-only
+    assert!(table.is_ascii(), "{}", table);
+
+    let table = graph.as_table_with(TableOptions {
+        style: TableStyle::simple(),
+        ..TableOptions::short()
+    });
+    assert!(table.is_ascii(), "{}", table);
+
+    let table = graph.as_table();
+    assert!(!table.is_ascii());
+}
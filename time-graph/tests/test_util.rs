@@ -0,0 +1,46 @@
+#![cfg(feature = "test-util")]
+
+use std::time::Duration;
+
+use time_graph::test_util::{assert_span_called, assert_span_elapsed};
+
+#[time_graph::instrument]
+fn sleep_recursive(count: usize) {
+    std::thread::sleep(Duration::from_millis(10));
+    if count > 0 {
+        sleep_recursive(count - 1);
+    }
+}
+
+#[test]
+fn test_util() {
+    time_graph::enable_data_collection(true);
+    sleep_recursive(5);
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+    assert_span_called(&graph, "sleep_recursive", 6);
+    assert_span_elapsed(&graph, "sleep_recursive", Duration::from_millis(60), Duration::from_secs(1));
+
+    let message = |result: std::thread::Result<()>| {
+        let error = result.unwrap_err();
+        error.downcast_ref::<String>().unwrap().clone()
+    };
+
+    let result = std::panic::catch_unwind(|| assert_span_called(&graph, "sleep_recursive", 3));
+    assert_eq!(
+        message(result),
+        "expected span 'sleep_recursive' to be called 3 times, but it was called 6 times"
+    );
+
+    let result = std::panic::catch_unwind(|| {
+        assert_span_elapsed(&graph, "sleep_recursive", Duration::ZERO, Duration::from_millis(1))
+    });
+    assert!(message(result).starts_with("expected span 'sleep_recursive' to take between 0ns and 1ms, but it took "));
+
+    let result = std::panic::catch_unwind(|| assert_span_called(&graph, "missing", 1));
+    assert_eq!(
+        message(result),
+        "could not find a span named 'missing' in the graph, available spans are [\"sleep_recursive\"]"
+    );
+}
@@ -0,0 +1,47 @@
+use std::sync::mpsc;
+
+#[time_graph::instrument]
+fn parent() {
+    child();
+}
+
+#[time_graph::instrument]
+fn child() {}
+
+#[test]
+fn multiple_threads() {
+    time_graph::enable_data_collection(true);
+
+    std::thread::scope(|scope| {
+        for _ in 0..4 {
+            scope.spawn(parent);
+        }
+    });
+
+    // data from a thread which is still running
+    let (done_sender, done_receiver) = mpsc::channel();
+    let (exit_sender, exit_receiver) = mpsc::channel::<()>();
+    let running = std::thread::spawn(move || {
+        parent();
+        done_sender.send(()).unwrap();
+        exit_receiver.recv().unwrap();
+    });
+    done_receiver.recv().unwrap();
+
+    let graph = time_graph::get_full_graph();
+    assert_eq!(graph.span_by_name("parent").unwrap().called, 5);
+    assert_eq!(graph.span_by_name("child").unwrap().called, 5);
+    assert_eq!(graph.calls().next().unwrap().count, 5);
+
+    exit_sender.send(()).unwrap();
+    running.join().unwrap();
+
+    let graph = time_graph::get_full_graph();
+    assert_eq!(graph.span_by_name("parent").unwrap().called, 5);
+
+    time_graph::clear_collected_data();
+    let graph = time_graph::get_full_graph();
+    assert_eq!(graph.spans().count(), 0);
+
+    time_graph::enable_data_collection(false);
+}
@@ -0,0 +1,23 @@
+use std::time::Duration;
+
+#[time_graph::instrument]
+fn sleep(duration: Duration) {
+    std::thread::sleep(duration);
+}
+
+#[test]
+fn min_max() {
+    time_graph::enable_data_collection(true);
+    sleep(Duration::from_millis(1));
+    sleep(Duration::from_millis(20));
+    time_graph::enable_data_collection(false);
+
+    let graph = time_graph::get_full_graph();
+    let span = graph.spans().find(|span| span.callsite.name() == "sleep").unwrap();
+
+    assert_eq!(span.called, 2);
+    assert!(span.min >= Duration::from_millis(1));
+    assert!(span.max >= Duration::from_millis(20));
+    assert!(span.min < span.max);
+    assert_eq!(span.min + span.max, span.elapsed);
+}
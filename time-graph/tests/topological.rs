@@ -0,0 +1,62 @@
+// call graph from `common`, with additional recursive calls and cycles
+
+mod common;
+
+use common::run_computation;
+
+#[time_graph::instrument]
+fn recursive(depth: u64) {
+    if depth > 0 {
+        recursive(depth - 1);
+    }
+}
+
+#[time_graph::instrument]
+fn ping(count: u64) {
+    if count > 0 {
+        pong(count - 1);
+    }
+}
+
+#[time_graph::instrument]
+fn pong(count: u64) {
+    if count > 0 {
+        ping(count - 1);
+    }
+}
+
+#[test]
+fn topological() {
+    time_graph::enable_data_collection(true);
+    run_computation(4);
+    recursive(3);
+
+    let graph = time_graph::get_full_graph();
+    let spans = graph.spans_topological().unwrap();
+    assert_eq!(spans.len(), graph.spans().count());
+
+    let position = |name: &str| spans.iter().position(|span| span.callsite.name() == name).unwrap();
+    assert!(position("run_computation") < position("compute"));
+    assert!(position("run_computation") < position("another span"));
+    assert!(position("compute") < position("bottom_5us"));
+    assert!(position("another span") < position("bottom_5us"));
+
+    // recursive calls are not cycles
+    assert!(graph.calls().any(|call| call.caller == call.callee));
+
+    for call in graph.calls() {
+        let caller = spans.iter().position(|span| span.id == call.caller).unwrap();
+        let callee = spans.iter().position(|span| span.id == call.callee).unwrap();
+        assert!(caller <= callee);
+    }
+
+    // mutual recursion creates a cycle
+    ping(4);
+    let graph = time_graph::get_full_graph();
+    let error = match graph.spans_topological() {
+        Ok(_) => panic!("expected a cycle"),
+        Err(error) => error,
+    };
+    let name = graph.spans().find(|span| span.id == error.span).unwrap().callsite.name();
+    assert!(name == "ping" || name == "pong");
+}
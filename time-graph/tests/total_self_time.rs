@@ -0,0 +1,39 @@
+use std::time::Duration;
+
+#[time_graph::instrument]
+fn root() {
+    std::thread::sleep(Duration::from_millis(5));
+    for _ in 0..2 {
+        middle();
+    }
+    leaf();
+}
+
+#[time_graph::instrument]
+fn middle() {
+    std::thread::sleep(Duration::from_millis(5));
+    leaf();
+}
+
+#[time_graph::instrument]
+fn leaf() {
+    std::thread::sleep(Duration::from_millis(5));
+}
+
+#[test]
+fn total_self_time() {
+    time_graph::enable_data_collection(true);
+    root();
+
+    let graph = time_graph::get_full_graph();
+    let root = graph.span_by_name("root").unwrap();
+
+    let total_elapsed = graph.spans().map(|span| span.elapsed).sum::<Duration>();
+    assert!(total_elapsed > root.elapsed);
+
+    // the time of children is only counted once, so the total self time is
+    // the same as the time of the root, up to rounding errors
+    let total = graph.total_self_time();
+    let difference = total.abs_diff(root.elapsed);
+    assert!(difference < Duration::from_micros(10));
+}
@@ -0,0 +1,47 @@
+mod common;
+
+use common::{run_computation, run_other};
+
+#[time_graph::instrument]
+fn recursive(count: usize) {
+    if count > 0 {
+        recursive(count - 1);
+    }
+}
+
+#[test]
+fn tree() {
+    time_graph::enable_data_collection(true);
+    run_other(1);
+    run_computation(4);
+    recursive(2);
+    time_graph::enable_data_collection(false);
+
+    let tree = time_graph::get_full_graph().as_tree();
+    let lines = tree.lines()
+        .map(|line| {
+            let name = line.trim_start();
+            let depth = (line.len() - name.len()) / 4;
+            let name = name.split(',').next().unwrap();
+            (depth, name)
+        })
+        .collect::<Vec<_>>();
+
+    // spans are sorted by source location
+    assert_eq!(lines, [
+        (0, "tree::common::run_computation"),
+        (1, "tree::common::{another span}"),
+        (2, "tree::common::details::bottom_5us"),
+        (1, "tree::common::compute"),
+        (2, "tree::common::details::bottom_5us"),
+        (1, "tree::common::details::bottom_5us"),
+        (0, "tree::common::run_other"),
+        (0, "tree::recursive"),
+        (1, "tree::recursive"),
+    ]);
+
+    assert!(tree.contains("    tree::common::compute, called 4 times"));
+    assert!(tree.contains("        tree::common::details::bottom_5us, called 6 times"));
+    assert!(tree.contains("    tree::common::details::bottom_5us, called 16 times"));
+    assert!(tree.contains("    tree::recursive, called 2 times (cycle)"));
+}
@@ -0,0 +1,8 @@
+#[test]
+fn ui() {
+    let tests = trybuild::TestCases::new();
+    tests.pass("tests/ui/trait_method.rs");
+    tests.pass("tests/ui/generic_method.rs");
+    tests.compile_fail("tests/ui/must_use.rs");
+    tests.compile_fail("tests/ui/invalid_level.rs");
+}
@@ -0,0 +1,37 @@
+use std::fmt::Debug;
+
+struct Wrapper<'a, T> {
+    values: &'a [T],
+}
+
+impl<'a, T: Clone> Wrapper<'a, T> {
+    #[time_graph::instrument]
+    fn first<'b, U>(&'b self, default: U) -> T
+    where
+        U: Into<T> + Debug,
+        'a: 'b,
+    {
+        self.values.first().cloned().unwrap_or_else(|| default.into())
+    }
+
+    #[time_graph::instrument(record = "count")]
+    fn take<const N: usize>(&self, count: usize) -> Vec<T> {
+        self.values.iter().take(count.min(N)).cloned().collect()
+    }
+}
+
+#[time_graph::instrument]
+fn sum<I>(values: I) -> i64
+where
+    I: IntoIterator<Item = i64>,
+{
+    values.into_iter().sum()
+}
+
+fn main() {
+    let values = [1, 2, 3];
+    let wrapper = Wrapper { values: &values };
+    assert_eq!(wrapper.first(0), 1);
+    assert_eq!(wrapper.take::<2>(3), vec![1, 2]);
+    assert_eq!(sum(vec![1, 2, 3]), 6);
+}
@@ -0,0 +1,6 @@
+#[time_graph::instrument(level = "verbose")]
+fn function() {}
+
+fn main() {
+    function();
+}
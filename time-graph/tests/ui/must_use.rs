@@ -0,0 +1,13 @@
+#![deny(unused_must_use)]
+
+/// The answer
+#[time_graph::instrument]
+#[must_use]
+#[inline]
+fn answer() -> usize {
+    42
+}
+
+fn main() {
+    answer();
+}
@@ -0,0 +1,40 @@
+trait Shape {
+    fn area(&self) -> f64;
+    fn scale(&mut self, factor: f64);
+}
+
+struct Square {
+    side: f64,
+}
+
+impl Shape for Square {
+    #[time_graph::instrument]
+    fn area(&self) -> f64 {
+        self.side * self.side
+    }
+
+    #[time_graph::instrument(name = "Square::scale")]
+    fn scale(&mut self, factor: f64) {
+        self.side *= factor;
+    }
+}
+
+impl Square {
+    #[time_graph::instrument]
+    fn into_side(self) -> f64 {
+        self.side
+    }
+
+    #[time_graph::instrument]
+    async fn area_async(&self) -> f64 {
+        self.area()
+    }
+}
+
+fn main() {
+    let mut square = Square { side: 2.0 };
+    square.scale(2.0);
+    assert_eq!(square.area(), 16.0);
+    drop(square.area_async());
+    assert_eq!(square.into_side(), 4.0);
+}
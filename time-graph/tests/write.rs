@@ -0,0 +1,48 @@
+use std::path::PathBuf;
+
+#[time_graph::instrument]
+fn outer() {
+    inner();
+}
+
+#[time_graph::instrument]
+fn inner() {}
+
+fn temporary_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("time-graph-{}-{}", std::process::id(), name))
+}
+
+#[test]
+fn write_to_file() {
+    time_graph::enable_data_collection(true);
+    outer();
+
+    let graph = time_graph::get_full_graph();
+
+    let path = temporary_path("graph.dot");
+    graph.write_dot(&path).unwrap();
+    assert_eq!(std::fs::read_to_string(&path).unwrap(), graph.as_dot());
+    std::fs::remove_file(&path).unwrap();
+
+    #[cfg(feature = "json")]
+    {
+        let path = temporary_path("graph.json");
+        graph.write_json(&path).unwrap();
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), graph.as_json());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(feature = "table")]
+    {
+        let path = temporary_path("graph.txt");
+        graph.write_table(&path).unwrap();
+        let content = std::fs::read_to_string(&path).unwrap();
+        assert_eq!(content, graph.as_table());
+        assert!(content.contains("outer"));
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    // errors are propagated to the caller
+    let path = temporary_path("missing-directory").join("graph.dot");
+    assert!(graph.write_dot(path).is_err());
+}